@@ -175,6 +175,38 @@ impl Mergeable for AIEntry {
                 .or_else(|| self.children.clone()),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        let mut conflicts: Vec<String> = vec![];
+        if self.def != other.def {
+            conflicts.push("Def".into());
+        }
+        if let (Some(params), Some(other_params)) = (&self.params, &other.params) {
+            conflicts.extend(util::prefix_conflicts(
+                "Params",
+                util::conflicts_pobj(params, other_params, hash_name("Params")),
+            ));
+        }
+        if let (Some(behaviors), Some(other_behaviors)) = (&self.behaviors, &other.behaviors) {
+            conflicts.extend(
+                behaviors
+                    .iter()
+                    .filter(|(k, v)| other_behaviors.get(*k).is_some_and(|other_v| other_v != *v))
+                    .map(|(k, _)| jstr!("Behaviors/{&lexical::to_string(k.hash())}").into()),
+            );
+        }
+        if let (Some(children), Some(other_children)) = (&self.children, &other.children) {
+            for (k, child) in children {
+                if let Some(other_child) = other_children.get(k) {
+                    conflicts.extend(util::prefix_conflicts(
+                        &jstr!("Children/{&lexical::to_string(k.hash())}"),
+                        child.conflicts(other_child),
+                    ));
+                }
+            }
+        }
+        conflicts
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
@@ -784,6 +816,53 @@ impl Mergeable for AIProgram {
             },
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        fn entry_conflicts<'a, K: 'a>(
+            section: &str,
+            entries: impl Iterator<Item = (&'a K, &'a AIEntry)>,
+            other: impl Fn(&K) -> Option<&'a AIEntry>,
+            name: impl Fn(&K) -> std::string::String,
+        ) -> Vec<String> {
+            entries
+                .filter_map(|(k, entry)| {
+                    other(k).map(|other_entry| {
+                        util::prefix_conflicts(
+                            &jstr!("{section}/{&name(k)}"),
+                            entry.conflicts(other_entry),
+                        )
+                    })
+                })
+                .flatten()
+                .collect()
+        }
+
+        let mut conflicts = entry_conflicts(
+            "Demos",
+            self.demos.iter(),
+            |k| other.demos.get(k),
+            |k| lexical::to_string(k.hash()),
+        );
+        conflicts.extend(entry_conflicts(
+            "Behaviors",
+            self.behaviors.iter(),
+            |k| other.behaviors.get(k),
+            |k| lexical::to_string(*k),
+        ));
+        conflicts.extend(entry_conflicts(
+            "Queries",
+            self.queries.iter(),
+            |k| other.queries.get(k),
+            |k| lexical::to_string(*k),
+        ));
+        conflicts.extend(entry_conflicts(
+            "AI",
+            self.roots.iter(),
+            |k| other.roots.get(k),
+            |k| k.to_string(),
+        ));
+        conflicts
+    }
 }
 
 impl TryFrom<&ParameterIO> for AIProgram {
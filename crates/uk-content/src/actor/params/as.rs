@@ -144,6 +144,30 @@ impl Mergeable for Element {
             }),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        let mut conflicts: Vec<String> = vec![];
+        if self.params != other.params {
+            conflicts.push("Parameters".into());
+        }
+        if let (Some(children), Some(other_children)) = (&self.children, &other.children) {
+            for (i, child) in children {
+                if let Some(other_child) = other_children.get(i) {
+                    conflicts.extend(util::prefix_conflicts(
+                        &jstr!("Children/{&lexical::to_string(*i)}"),
+                        child.conflicts(other_child),
+                    ));
+                }
+            }
+        }
+        if let (Some(extend), Some(other_extend)) = (&self.extend, &other.extend) {
+            conflicts.extend(util::prefix_conflicts(
+                "Extend",
+                util::conflicts_plist(extend, other_extend),
+            ));
+        }
+        conflicts
+    }
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
@@ -304,6 +328,23 @@ impl Mergeable for AS {
                 .or_else(|| self.common_params.clone()),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        let mut conflicts: Vec<String> = vec![];
+        if let (Some(root), Some(other_root)) = (&self.root, &other.root) {
+            conflicts.extend(util::prefix_conflicts(
+                "Elements",
+                root.conflicts(other_root),
+            ));
+        }
+        if let (Some(params), Some(other_params)) = (&self.common_params, &other.common_params) {
+            conflicts.extend(util::prefix_conflicts(
+                "CommonParams",
+                util::conflicts_pobj(params, other_params, hash_name("CommonParams")),
+            ));
+        }
+        conflicts
+    }
 }
 
 impl ParameterResource for AS {
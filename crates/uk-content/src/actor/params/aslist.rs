@@ -4,7 +4,12 @@ use serde::{Deserialize, Serialize};
 use uk_content_derive::ParamData;
 use uk_util::OptionResultExt;
 
-use crate::{actor::ParameterResource, prelude::*, util::DeleteMap, Result, UKError};
+use crate::{
+    actor::ParameterResource,
+    prelude::*,
+    util::{self, DeleteMap},
+    Result, UKError,
+};
 
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ParamData)]
 
@@ -187,6 +192,31 @@ impl Mergeable for ASList {
                 .or_else(|| self.cf_defines.clone()),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        let mut conflicts: Vec<String> = vec![];
+        if let (Some(common), Some(other_common)) = (&self.common, &other.common) {
+            conflicts.extend(util::prefix_conflicts(
+                "Common",
+                util::conflicts_pobj(common, other_common, hash_name("Common")),
+            ));
+        }
+        conflicts.extend(util::prefix_conflicts(
+            "AddReses",
+            self.add_reses.conflicts(&other.add_reses),
+        ));
+        conflicts.extend(util::prefix_conflicts(
+            "ASDefines",
+            self.as_defines.conflicts(&other.as_defines),
+        ));
+        if let (Some(defines), Some(other_defines)) = (&self.cf_defines, &other.cf_defines) {
+            conflicts.extend(util::prefix_conflicts(
+                "CFDefines",
+                defines.conflicts(other_defines),
+            ));
+        }
+        conflicts
+    }
 }
 
 impl ParameterResource for ASList {
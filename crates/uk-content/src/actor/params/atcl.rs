@@ -61,6 +61,23 @@ impl Mergeable for AttClient {
             checks: self.checks.merge(&diff.checks),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        util::prefix_conflicts(
+            "AttClientParams",
+            util::conflicts_pobj(
+                &self.client_params,
+                &other.client_params,
+                hash_name("AttClientParams"),
+            ),
+        )
+        .into_iter()
+        .chain(util::prefix_conflicts(
+            "Checks",
+            self.checks.conflicts(&other.checks),
+        ))
+        .collect()
+    }
 }
 
 impl ParameterResource for AttClient {
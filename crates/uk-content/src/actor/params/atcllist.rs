@@ -91,6 +91,19 @@ impl Mergeable for AttClientList {
             att_clients: self.att_clients.merge(&diff.att_clients),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        util::prefix_conflicts(
+            "AttPos",
+            util::conflicts_pobj(&self.att_pos, &other.att_pos, hash_name("AttPos")),
+        )
+        .into_iter()
+        .chain(util::prefix_conflicts(
+            "AttClients",
+            self.att_clients.conflicts(&other.att_clients),
+        ))
+        .collect()
+    }
 }
 
 impl ParameterResource for AttClientList {
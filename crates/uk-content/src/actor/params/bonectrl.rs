@@ -184,6 +184,34 @@ impl Mergeable for BoneControl {
                 .collect(),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        crate::util::conflicts_plist(
+            &ParameterList {
+                objects: self.objects.clone(),
+                ..Default::default()
+            },
+            &ParameterList {
+                objects: other.objects.clone(),
+                ..Default::default()
+            },
+        )
+        .into_iter()
+        .chain(
+            self.bone_groups
+                .iter()
+                .filter_map(|(group, bones)| {
+                    other.bone_groups.get(group).map(|other_bones| {
+                        crate::util::prefix_conflicts(
+                            &jstr!("BoneGroups/{group.as_str()}"),
+                            bones.conflicts(other_bones),
+                        )
+                    })
+                })
+                .flatten(),
+        )
+        .collect()
+    }
 }
 
 impl ParameterResource for BoneControl {
@@ -160,6 +160,17 @@ impl Mergeable for Chemical {
             body:    util::simple_index_merge(&self.body, &diff.body),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        let mut conflicts = util::prefix_conflicts(
+            "chemical_root/chemical_body",
+            util::simple_index_conflicts(&self.body, &other.body),
+        );
+        if util::options_conflict(&self.unknown, &other.unknown) {
+            conflicts.push("chemical_root/chemical_header/3635073347".into());
+        }
+        conflicts
+    }
 }
 
 impl InfoSource for Chemical {
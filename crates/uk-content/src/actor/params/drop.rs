@@ -103,6 +103,21 @@ impl Mergeable for DropTable {
                 .collect(),
         )
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        self.0
+            .iter()
+            .filter_map(|(name, table)| {
+                other.0.get(name).map(|other_table| {
+                    crate::util::prefix_conflicts(
+                        name,
+                        crate::util::conflicts_pobj(table, other_table, hash_name(name)),
+                    )
+                })
+            })
+            .flatten()
+            .collect()
+    }
 }
 
 impl InfoSource for DropTable {
@@ -258,6 +273,25 @@ mod tests {
         assert_eq!(drop2, merged);
     }
 
+    #[test]
+    fn conflicts() {
+        let actor = crate::tests::test_base_actorpack("Enemy_Guardian_A");
+        let pio = roead::aamp::ParameterIO::from_binary(
+            actor
+                .get_data("Actor/DropTable/Enemy_Guardian_A.bdrop")
+                .unwrap(),
+        )
+        .unwrap();
+        let drop = super::DropTable::try_from(&pio).unwrap();
+        assert!(drop.conflicts(&drop).is_empty());
+        let mut other = drop.clone();
+        let table = other.0.values_mut().next().unwrap();
+        *table.0.values_mut().next().unwrap() = roead::aamp::Parameter::I32(-1);
+        let conflicts = drop.conflicts(&other);
+        assert_eq!(conflicts.len(), 1);
+        assert!(conflicts[0].starts_with(drop.0.keys().next().unwrap().as_str()));
+    }
+
     #[test]
     fn info() {
         let actor = crate::tests::test_base_actorpack("Enemy_Guardian_A");
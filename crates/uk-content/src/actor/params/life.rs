@@ -7,7 +7,7 @@ use crate::{
     actor::{InfoSource, ParameterResource},
     constants::{Time, Weather},
     prelude::*,
-    util::{DeleteSet, IteratorExt},
+    util::{self, DeleteKey, DeleteSet, IteratorExt},
     Result, UKError,
 };
 
@@ -338,6 +338,50 @@ impl Mergeable for LifeCondition {
             },
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        fn set_conflicts<T: DeleteKey>(
+            name: &str,
+            set: &Option<DeleteSet<T>>,
+            other: &Option<DeleteSet<T>>,
+        ) -> Vec<String> {
+            match (set, other) {
+                (Some(set), Some(other)) => util::prefix_conflicts(name, set.conflicts(other)),
+                _ => vec![],
+            }
+        }
+
+        let mut conflicts = set_conflicts(
+            "InvalidWeathers",
+            &self.invalid_weathers,
+            &other.invalid_weathers,
+        );
+        conflicts.extend(set_conflicts(
+            "InvalidTimes",
+            &self.invalid_times,
+            &other.invalid_times,
+        ));
+        if util::options_conflict(&self.display_dist, &other.display_dist) {
+            conflicts.push("DisplayDistance".into());
+        }
+        if util::options_conflict(&self.auto_disp_dist_algo, &other.auto_disp_dist_algo) {
+            conflicts.push("AutoDisplayDistanceAlgorithm".into());
+        }
+        if util::options_conflict(&self.y_limit_algo, &other.y_limit_algo) {
+            conflicts.push("YLimitAlgorithm".into());
+        }
+        conflicts.extend(set_conflicts(
+            "DeleteWeathers",
+            &self.delete_weathers,
+            &other.delete_weathers,
+        ));
+        conflicts.extend(set_conflicts(
+            "DeleteTimes",
+            &self.delete_times,
+            &other.delete_times,
+        ));
+        conflicts
+    }
 }
 
 impl InfoSource for LifeCondition {
@@ -153,6 +153,23 @@ impl Mergeable for ActorLink {
             },
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        let mut conflicts = util::prefix_conflicts(
+            "LinkTarget",
+            util::conflicts_pobj(&self.targets, &other.targets, hash_name("LinkTarget")),
+        );
+        if let (Some(tags), Some(other_tags)) = (&self.tags, &other.tags) {
+            conflicts.extend(util::prefix_conflicts("Tags", tags.conflicts(other_tags)));
+        }
+        if let (Some(tags), Some(other_tags)) = (&self.fit_tags, &other.fit_tags) {
+            conflicts.extend(util::prefix_conflicts(
+                "1115720914",
+                tags.conflicts(other_tags),
+            ));
+        }
+        conflicts
+    }
 }
 
 impl InfoSource for ActorLink {
@@ -105,6 +105,15 @@ impl Mergeable for ModelData {
             units:  self.units.merge(&diff.units),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        let mut conflicts: Vec<String> = vec![];
+        if self.folder != other.folder {
+            conflicts.push("Base/Folder".into());
+        }
+        conflicts.extend(prefix_conflicts("Unit", self.units.conflicts(&other.units)));
+        conflicts
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
@@ -256,6 +265,38 @@ impl Mergeable for ModelList {
             locators: self.locators.merge(&diff.locators),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        let mut conflicts = prefix_conflicts(
+            "ControllerInfo",
+            conflicts_pobj(
+                &self.controller_info,
+                &other.controller_info,
+                hash_name("ControllerInfo"),
+            ),
+        );
+        conflicts.extend(prefix_conflicts(
+            "Attention",
+            conflicts_pobj(&self.attention, &other.attention, hash_name("Attention")),
+        ));
+        for (i, data) in &self.model_data {
+            if let Some(other_data) = other.model_data.get(i) {
+                conflicts.extend(prefix_conflicts(
+                    &jstr!("ModelData/{&lexical::to_string(*i)}"),
+                    data.conflicts(other_data),
+                ));
+            }
+        }
+        conflicts.extend(prefix_conflicts(
+            "AnmTarget",
+            simple_index_conflicts(&self.anm_target, &other.anm_target),
+        ));
+        conflicts.extend(prefix_conflicts(
+            "Locators",
+            self.locators.conflicts(&other.locators),
+        ));
+        conflicts
+    }
 }
 
 impl InfoSource for ModelList {
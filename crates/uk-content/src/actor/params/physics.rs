@@ -170,6 +170,17 @@ impl Mergeable for ContactInfo {
                 .cloned(),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        let mut conflicts: Vec<String> = vec![];
+        if util::options_conflict(&self.contact_point_info, &other.contact_point_info) {
+            conflicts.push("ContactPointInfo".into());
+        }
+        if util::options_conflict(&self.collision_info, &other.collision_info) {
+            conflicts.push("CollisionInfo".into());
+        }
+        conflicts
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
@@ -224,6 +235,16 @@ impl Mergeable for CharacterController {
             forms:  util::simple_index_merge(&self.forms, &diff.forms),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        util::conflicts_pobj(&self.header, &other.header, 2311816730)
+            .into_iter()
+            .chain(util::prefix_conflicts(
+                "Form",
+                util::simple_index_conflicts(&self.forms, &other.forms),
+            ))
+            .collect()
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
@@ -338,6 +359,21 @@ impl Mergeable for Cloth {
             cloths: util::simple_index_merge(&self.cloths, &diff.cloths),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        let mut conflicts = util::prefix_conflicts(
+            "ClothSubWind",
+            util::conflicts_pobj(&self.subwind, &other.subwind, hash_name("ClothSubWind")),
+        );
+        if util::options_conflict(&self.setup_file_path, &other.setup_file_path) {
+            conflicts.push("ClothHeader/cloth_setup_file_path".into());
+        }
+        conflicts.extend(util::prefix_conflicts(
+            "Cloth",
+            util::simple_index_conflicts(&self.cloths, &other.cloths),
+        ));
+        conflicts
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
@@ -709,6 +745,51 @@ impl Mergeable for Physics {
                 .or(self.use_system_group_handler),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        let mut conflicts: Vec<String> = vec![];
+        if util::options_conflict(&self.ragdoll, &other.ragdoll) {
+            conflicts.push("Ragdoll".into());
+        }
+        if util::options_conflict(&self.support_bone, &other.support_bone) {
+            conflicts.push("SupportBone".into());
+        }
+        if let (Some(info), Some(other_info)) =
+            (&self.rigid_contact_info, &other.rigid_contact_info)
+        {
+            conflicts.extend(util::prefix_conflicts(
+                "RigidContactInfo",
+                info.conflicts(other_info),
+            ));
+        }
+        if let (Some(bodies), Some(other_bodies)) = (&self.rigid_body_set, &other.rigid_body_set) {
+            conflicts.extend(util::prefix_conflicts(
+                "RigidBodySet",
+                util::simple_index_conflicts(bodies, other_bodies),
+            ));
+        }
+        if let (Some(controller), Some(other_controller)) =
+            (&self.character_controller, &other.character_controller)
+        {
+            conflicts.extend(util::prefix_conflicts(
+                "CharacterController",
+                controller.conflicts(other_controller),
+            ));
+        }
+        if let (Some(cloth), Some(other_cloth)) = (&self.cloth, &other.cloth) {
+            conflicts.extend(util::prefix_conflicts(
+                "Cloth",
+                cloth.conflicts(other_cloth),
+            ));
+        }
+        if util::options_conflict(
+            &self.use_system_group_handler,
+            &other.use_system_group_handler,
+        ) {
+            conflicts.push("ParamSet/use_system_group_handler".into());
+        }
+        conflicts
+    }
 }
 
 impl InfoSource for Physics {
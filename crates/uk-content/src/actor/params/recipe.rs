@@ -180,6 +180,17 @@ impl Mergeable for Recipe {
     fn merge(&self, diff: &Self) -> Self {
         diff.clone()
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        // Recipes are replaced whole, so every table the two change
+        // differently is lost from the earlier one.
+        self.0
+            .keys()
+            .filter(|table| self.0.get(*table) != other.0.get(*table))
+            .chain(other.0.keys().filter(|table| !self.0.contains_key(*table)))
+            .map(|table| table.as_str().into())
+            .collect()
+    }
 }
 
 impl InfoSource for Recipe {
@@ -9,7 +9,7 @@ use uk_util::OptionResultExt;
 use crate::{
     actor::ParameterResource,
     prelude::*,
-    util::{DeleteMap, IndexMap},
+    util::{self, DeleteMap, IndexMap},
     Result, UKError,
 };
 
@@ -151,6 +151,19 @@ impl Mergeable for RagdollBlendWeight {
                 .collect(),
         )
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        self.0
+            .iter()
+            .filter_map(|(key, list)| {
+                other.0.get(key).map(|other_list| {
+                    let (state, system) = (key.state_key.as_str(), key.system_key.as_str());
+                    util::prefix_conflicts(&jstr!("{state}/{system}"), list.conflicts(other_list))
+                })
+            })
+            .flatten()
+            .collect()
+    }
 }
 
 impl ParameterResource for RagdollBlendWeight {
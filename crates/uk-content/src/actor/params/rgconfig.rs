@@ -76,6 +76,23 @@ impl Mergeable for RagdollConfig {
             ),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        util::prefix_conflicts(
+            "AttackTypeImpulseData",
+            util::conflicts_pobj(
+                &self.attack_type_impulse_data,
+                &other.attack_type_impulse_data,
+                hash_name("AttackTypeImpulseData"),
+            ),
+        )
+        .into_iter()
+        .chain(util::prefix_conflicts(
+            "ImpactImpulseInfo",
+            util::simple_index_conflicts(&self.impact_impulse_info, &other.impact_impulse_info),
+        ))
+        .collect()
+    }
 }
 
 impl ParameterResource for RagdollConfig {
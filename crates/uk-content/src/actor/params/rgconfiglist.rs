@@ -110,6 +110,27 @@ impl Mergeable for RagdollConfigList {
             body_param_list: self.body_param_list.merge(&diff.body_param_list),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        util::prefix_conflicts(
+            "CommonData",
+            util::conflicts_pobj(
+                &self.common_data,
+                &other.common_data,
+                hash_name("CommonData"),
+            ),
+        )
+        .into_iter()
+        .chain(util::prefix_conflicts(
+            "ImpulseParamList",
+            util::conflicts_plist(&self.impulse_params, &other.impulse_params),
+        ))
+        .chain(util::prefix_conflicts(
+            "BodyParamList",
+            self.body_param_list.conflicts(&other.body_param_list),
+        ))
+        .collect()
+    }
 }
 
 impl ParameterResource for RagdollConfigList {
@@ -200,6 +221,28 @@ mod tests {
         assert_eq!(rgconfiglist2, merged);
     }
 
+    #[test]
+    fn conflicts() {
+        let actor = crate::tests::test_base_actorpack("Enemy_Moriblin_Junior");
+        let pio = roead::aamp::ParameterIO::from_binary(
+            actor
+                .get_data("Actor/RagdollConfigList/Moriblin_Blue.brgconfiglist")
+                .unwrap(),
+        )
+        .unwrap();
+        let rgconfiglist = super::RagdollConfigList::try_from(&pio).unwrap();
+        assert!(rgconfiglist.conflicts(&rgconfiglist).is_empty());
+        let mut other = rgconfiglist.clone();
+        *other.common_data.0.values_mut().next().unwrap() = roead::aamp::Parameter::I32(-1);
+        let (name, body_param) = other.body_param_list.iter_mut().next().unwrap();
+        let path = join_str::jstr!("BodyParamList/{name.as_str()}");
+        body_param.friction_scale = -1.0;
+        let conflicts = rgconfiglist.conflicts(&other);
+        assert_eq!(conflicts.len(), 2);
+        assert!(conflicts[0].starts_with("CommonData/"));
+        assert_eq!(conflicts[1].as_str(), path);
+    }
+
     #[test]
     fn identify() {
         let path = std::path::Path::new(
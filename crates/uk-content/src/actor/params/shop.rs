@@ -255,6 +255,30 @@ impl Mergeable for ShopData {
                 .collect(),
         )
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        self.0
+            .iter()
+            .filter_map(|(name, table)| Some((name, table, other.0.get(name)?)))
+            .flat_map(|(name, table, other_table)| {
+                match (table, other_table) {
+                    (Some(table), Some(other_table)) => {
+                        table
+                            .iter()
+                            .filter(|(item, data)| {
+                                other_table
+                                    .get(*item)
+                                    .is_some_and(|other_data| other_data != *data)
+                            })
+                            .map(|(item, _)| jstr!("{name.as_str()}/{item.as_str()}").into())
+                            .collect::<Vec<String>>()
+                    }
+                    (None, None) => vec![],
+                    _ => vec![name.as_str().into()],
+                }
+            })
+            .collect()
+    }
 }
 
 impl ParameterResource for ShopData {
@@ -89,6 +89,10 @@ impl Mergeable for ResidentActors {
     fn merge(&self, diff: &Self) -> Self {
         Self(self.0.merge(&diff.0))
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        self.0.conflicts(&other.0)
+    }
 }
 
 impl Resource for ResidentActors {
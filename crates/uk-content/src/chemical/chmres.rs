@@ -76,6 +76,20 @@ impl Mergeable for ChemicalRes {
             element:  self.element.merge(&diff.element),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        crate::util::prefix_conflicts("world", self.world.conflicts(&other.world))
+            .into_iter()
+            .chain(crate::util::prefix_conflicts(
+                "material",
+                self.material.conflicts(&other.material),
+            ))
+            .chain(crate::util::prefix_conflicts(
+                "element",
+                self.element.conflicts(&other.element),
+            ))
+            .collect()
+    }
 }
 
 impl Resource for ChemicalRes {
@@ -74,6 +74,20 @@ impl Mergeable for CookData {
             system: self.system.merge(&diff.system),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        crate::util::prefix_conflicts("Recipes", self.recipes.conflicts(&other.recipes))
+            .into_iter()
+            .chain(crate::util::prefix_conflicts(
+                "SingleRecipes",
+                self.single_recipes.conflicts(&other.single_recipes),
+            ))
+            .chain(crate::util::prefix_conflicts(
+                "System",
+                self.system.conflicts(&other.system),
+            ))
+            .collect()
+    }
 }
 
 impl Resource for CookData {
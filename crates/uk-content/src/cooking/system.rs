@@ -137,6 +137,41 @@ impl Mergeable for System {
             ssaet:  diff.ssaet,
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<smartstring::alias::String> {
+        let mut conflicts = crate::util::prefix_conflicts("CEI", self.cei.conflicts(&other.cei));
+        conflicts.extend(
+            [
+                ("FA", self.fa != other.fa),
+                ("FALR", self.falr != other.falr),
+                ("FALRMR", self.falrmr != other.falrmr),
+                ("FCA", self.fca != other.fca),
+                ("LRMR", self.lrmr != other.lrmr),
+                ("MEA", self.mea != other.mea),
+            ]
+            .into_iter()
+            .filter(|(_, differs)| *differs)
+            .map(|(key, _)| key.into()),
+        );
+        conflicts.extend(crate::util::prefix_conflicts(
+            "NMMR",
+            self.nmmr.conflicts(&other.nmmr),
+        ));
+        conflicts.extend(crate::util::prefix_conflicts(
+            "NMSSR",
+            self.nmssr.conflicts(&other.nmssr),
+        ));
+        conflicts.extend(
+            [
+                ("SFALR", self.sfalr != other.sfalr),
+                ("SSAET", self.ssaet != other.ssaet),
+            ]
+            .into_iter()
+            .filter(|(_, differs)| *differs)
+            .map(|(key, _)| key.into()),
+        );
+        conflicts
+    }
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
@@ -226,4 +261,19 @@ impl Mergeable for Cei {
             t:   diff.t,
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<smartstring::alias::String> {
+        [
+            ("BT", self.bt != other.bt),
+            ("MR", self.mr != other.mr),
+            ("Ma", self.ma != other.ma),
+            ("Mi", self.mi != other.mi),
+            ("SSA", self.ssa != other.ssa),
+            ("T", self.t != other.t),
+        ]
+        .into_iter()
+        .filter(|(_, differs)| *differs)
+        .map(|(key, _)| key.into())
+        .collect()
+    }
 }
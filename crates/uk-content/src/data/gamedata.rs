@@ -132,6 +132,10 @@ impl Mergeable for GameData {
             flags:     self.flags.merge(&diff.flags),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        self.flags.conflicts(&other.flags)
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
@@ -266,6 +270,17 @@ macro_rules! build_gamedata_pack {
     };
 }
 
+macro_rules! gamedata_conflicts {
+    ($data:expr, $other:expr, $conflicts:expr, $($type:ident),*) => {
+        $(
+            $conflicts.extend(crate::util::prefix_conflicts(
+                stringify!($type),
+                $data.$type.conflicts(&$other.$type),
+            ));
+        )*
+    };
+}
+
 impl GameDataPack {
     pub const STAGES: &'static [&'static str] =
         &["MainField", "AocField", "CDungeon", "MainFieldDungeon"];
@@ -510,6 +525,34 @@ impl Mergeable for GameDataPack {
             vector4f_data: self.vector4f_data.merge(&diff.vector4f_data),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        let mut conflicts = vec![];
+        gamedata_conflicts!(
+            self,
+            other,
+            conflicts,
+            bool_array_data,
+            bool_data,
+            f32_array_data,
+            f32_data,
+            revival_bool_data,
+            revival_s32_data,
+            s32_array_data,
+            s32_data,
+            string32_data,
+            string64_array_data,
+            string64_data,
+            string256_array_data,
+            string256_data,
+            vector2f_array_data,
+            vector2f_data,
+            vector3f_array_data,
+            vector3f_data,
+            vector4f_data
+        );
+        conflicts
+    }
 }
 
 impl Resource for GameDataPack {
@@ -155,6 +155,14 @@ impl Mergeable for SaveData {
             flags:  self.flags.merge(&diff.flags),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        self.flags
+            .iter_full()
+            .filter(|(flag, del)| other.flags.is_delete(*flag).is_some_and(|d| d != **del))
+            .map(|(flag, _)| flag.name.clone())
+            .collect()
+    }
 }
 
 impl SaveData {
@@ -271,6 +279,20 @@ impl Mergeable for SaveDataPack {
                 .collect(),
         )
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        ["game_data.sav", "caption.sav", "option.sav"]
+            .into_iter()
+            .filter_map(|key| {
+                let (data, other_data) = (self.0.get(key)?, other.0.get(key)?);
+                Some(crate::util::prefix_conflicts(
+                    key,
+                    data.conflicts(other_data),
+                ))
+            })
+            .flatten()
+            .collect()
+    }
 }
 
 impl Resource for SaveDataPack {
@@ -115,6 +115,16 @@ impl Mergeable for ShopGameDataInfo {
             sold_out_info: self.sold_out_info.merge(&diff.sold_out_info),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        crate::util::prefix_conflicts("ShopAreaInfo", self.area_info.conflicts(&other.area_info))
+            .into_iter()
+            .chain(crate::util::prefix_conflicts(
+                "SoldOutInfo",
+                self.sold_out_info.conflicts(&other.sold_out_info),
+            ))
+            .collect()
+    }
 }
 
 impl Resource for ShopGameDataInfo {
@@ -26,6 +26,14 @@ impl Mergeable for WeaponSeries {
             not_rank_up: diff.not_rank_up,
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        let mut conflicts = self.actors.conflicts(&other.actors);
+        if self.not_rank_up != other.not_rank_up {
+            conflicts.push("not_rank_up".into());
+        }
+        conflicts
+    }
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
@@ -276,6 +284,24 @@ impl Mergeable for LevelSensor {
             weapon:  self.weapon.deep_merge(&diff.weapon),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        crate::util::prefix_conflicts("enemy", self.enemy.deep_conflicts(&other.enemy))
+            .into_iter()
+            .chain(crate::util::prefix_conflicts(
+                "flag",
+                self.flag.conflicts(&other.flag),
+            ))
+            .chain(crate::util::prefix_conflicts(
+                "setting",
+                self.setting.conflicts(&other.setting),
+            ))
+            .chain(crate::util::prefix_conflicts(
+                "weapon",
+                self.weapon.deep_conflicts(&other.weapon),
+            ))
+            .collect()
+    }
 }
 
 impl Resource for LevelSensor {
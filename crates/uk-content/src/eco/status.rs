@@ -109,6 +109,15 @@ impl Mergeable for StatusEffectValues {
             _ => panic!("Attempted to merge incompatible status effect types"),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        match (self, other) {
+            (Self::Normal(self_values), Self::Normal(other_values)) => {
+                self_values.conflicts(other_values)
+            }
+            _ => vec![],
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
@@ -174,6 +183,18 @@ impl Mergeable for StatusEffectList {
                 .collect(),
         )
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        self.0
+            .iter()
+            .filter_map(|(effect, values)| {
+                other.0.get(effect).map(|other_values| {
+                    crate::util::prefix_conflicts(effect, values.conflicts(other_values))
+                })
+            })
+            .flatten()
+            .collect()
+    }
 }
 
 impl Resource for StatusEffectList {
@@ -96,6 +96,26 @@ mod tests {
         assert_eq!(merged, eventinfo2);
     }
 
+    #[test]
+    fn merge_shallow_diff() {
+        use roead::byml::map;
+        // Diffs from earlier versions replace whole top-level entries, so
+        // nested keys the mod removed must not survive the merge.
+        let base = super::EventInfo(map!(
+            "Demo000_0" => map!(
+                "subtype" => Byml::String("Demo".into()),
+                "se_ctrl_type" => Byml::String("Type0".into())
+            ),
+            "Demo001_0" => map!("subtype" => Byml::String("Demo".into()))
+        ));
+        let other = super::EventInfo(map!(
+            "Demo000_0" => map!("subtype" => Byml::String("Demo".into())),
+            "Demo001_0" => map!("subtype" => Byml::String("Demo".into()))
+        ));
+        let diff = super::EventInfo(crate::util::diff_byml_shallow(&base.0, &other.0));
+        assert_eq!(base.merge(&diff), other);
+    }
+
     #[test]
     fn identify() {
         let path = std::path::Path::new("content/Pack/TitleBG.pack//Event/EventInfo.product.sbyml");
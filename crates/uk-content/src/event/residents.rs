@@ -65,6 +65,10 @@ impl Mergeable for ResidentEvents {
     fn merge(&self, diff: &Self) -> Self {
         Self(self.0.merge(&diff.0))
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        self.0.conflicts(&other.0)
+    }
 }
 
 impl Resource for ResidentEvents {
@@ -52,6 +52,19 @@ impl Mergeable for LayoutArchive {
                 .collect(),
         )
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        self.0
+            .iter()
+            .filter(|(name, data)| {
+                other
+                    .0
+                    .get(*name)
+                    .is_some_and(|other_data| other_data != *data)
+            })
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
 }
 
 impl Resource for LayoutArchive {
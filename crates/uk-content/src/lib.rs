@@ -100,6 +100,16 @@ pub enum UKError {
     InvalidWeatherOrTime(std::string::String),
     #[error("Missing resource at {0}")]
     MissingResource(std::string::String),
+    #[error(
+        "Tried to {action} incompatible resources: {base} and {other}{}",
+        .path.as_ref().map(|p| format!(" at {p}")).unwrap_or_default()
+    )]
+    IncompatibleResources {
+        action: &'static str,
+        base:   &'static str,
+        other:  &'static str,
+        path:   Option<std::string::String>,
+    },
     #[error("{0}")]
     Other(&'static str),
     #[error("{0}")]
@@ -117,6 +127,28 @@ pub enum UKError {
 }
 
 impl UKError {
+    /// Attach the canonical path of the resource being processed to an
+    /// [`UKError::IncompatibleResources`] error. Other errors are returned
+    /// unchanged.
+    pub fn with_path(self, path: impl Into<std::string::String>) -> Self {
+        match self {
+            Self::IncompatibleResources {
+                action,
+                base,
+                other,
+                ..
+            } => {
+                Self::IncompatibleResources {
+                    action,
+                    base,
+                    other,
+                    path: Some(path.into()),
+                }
+            }
+            e => e,
+        }
+    }
+
     pub fn context_data(&self) -> Option<ContextData> {
         match self {
             Self::MissingAampKey(_, data) => data.clone(),
@@ -196,6 +228,18 @@ pub mod prelude {
         fn diff(&self, other: &Self) -> Self;
         #[must_use]
         fn merge(&self, diff: &Self) -> Self;
+        /// Given two diffs against the same base, returns the paths (e.g.
+        /// `Normal0/ItemName01`) at which both make different changes.
+        #[must_use]
+        fn conflicts(&self, other: &Self) -> Vec<String>;
+    }
+
+    /// Fallible counterpart to [`Mergeable`] for types which can hold values
+    /// that cannot be diffed or merged with each other, such as the variants of
+    /// [`MergeableResource`](crate::resource::MergeableResource).
+    pub trait TryMergeable: Sized {
+        fn try_diff(&self, other: &Self) -> crate::Result<Self>;
+        fn try_merge(&self, diff: &Self) -> crate::Result<Self>;
     }
 
     macro_rules! impl_simple_aamp {
@@ -222,6 +266,10 @@ pub mod prelude {
                         ),
                     })
                 }
+
+                fn conflicts(&self, other: &Self) -> Vec<String> {
+                    crate::util::conflicts_plist(&self.$field.param_root, &other.$field.param_root)
+                }
             }
         };
     }
@@ -242,6 +290,10 @@ pub mod prelude {
                 param_root: crate::util::merge_plist(&self.param_root, &diff.param_root),
             }
         }
+
+        fn conflicts(&self, other: &Self) -> Vec<String> {
+            crate::util::conflicts_plist(&self.param_root, &other.param_root)
+        }
     }
 
     pub(crate) use impl_simple_aamp;
@@ -256,6 +308,25 @@ pub mod prelude {
                 fn merge(&self, diff: &Self) -> Self {
                     crate::util::merge_byml_shallow(&self.$field, &diff.$field).into()
                 }
+
+                fn conflicts(&self, other: &Self) -> Vec<String> {
+                    crate::util::conflicts_byml_shallow(&self.$field, &other.$field)
+                }
+            }
+        };
+        ($type:ty, $field:tt, deep) => {
+            impl Mergeable for $type {
+                fn diff(&self, other: &Self) -> Self {
+                    crate::util::diff_byml_deep(&self.$field, &other.$field).into()
+                }
+
+                fn merge(&self, diff: &Self) -> Self {
+                    crate::util::merge_byml_deep(&self.$field, &diff.$field).into()
+                }
+
+                fn conflicts(&self, other: &Self) -> Vec<String> {
+                    crate::util::conflicts_byml_deep(&self.$field, &other.$field)
+                }
             }
         };
     }
@@ -268,6 +339,10 @@ pub mod prelude {
         fn merge(&self, diff: &Self) -> Self {
             crate::util::merge_byml_shallow(self, diff)
         }
+
+        fn conflicts(&self, other: &Self) -> Vec<String> {
+            crate::util::conflicts_byml_shallow(self, other)
+        }
     }
 
     pub(crate) use impl_simple_byml;
@@ -176,6 +176,22 @@ impl Mergeable for Static {
             start_pos: self.start_pos.deep_merge(&diff.start_pos),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        self.general
+            .iter()
+            .filter_map(|(key, entries)| {
+                other.general.get(key).map(|other_entries| {
+                    crate::util::prefix_conflicts(key, entries.conflicts(other_entries))
+                })
+            })
+            .flatten()
+            .chain(crate::util::prefix_conflicts(
+                "StartPos",
+                self.start_pos.deep_conflicts(&other.start_pos),
+            ))
+            .collect()
+    }
 }
 
 impl Resource for Static {
@@ -1,7 +1,11 @@
 use roead::byml::Byml;
 use serde::{Deserialize, Serialize};
 
-use crate::{prelude::*, util::SortedDeleteMap, Result, UKError};
+use crate::{
+    prelude::*,
+    util::{self, SortedDeleteMap},
+    Result, UKError,
+};
 
 #[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
 
@@ -86,14 +90,167 @@ impl From<MapUnit> for Byml {
     }
 }
 
+/// Diffs placed objects or rails by `HashId`, storing only the changed fields
+/// of each modified entry so that edits to the same entry by different mods can
+/// be combined.
+fn diff_entries(
+    base: &SortedDeleteMap<u32, Byml>,
+    other: &SortedDeleteMap<u32, Byml>,
+) -> SortedDeleteMap<u32, Byml> {
+    other
+        .iter()
+        .filter_map(|(id, entry)| {
+            match base.get(id) {
+                Some(base_entry) if base_entry == entry => None,
+                Some(base_entry) => Some((*id, util::diff_byml_deep(base_entry, entry), false)),
+                None => Some((*id, entry.clone(), false)),
+            }
+        })
+        .chain(
+            base.keys()
+                .filter(|id| !other.contains_key(*id))
+                .map(|id| (*id, Byml::Null, true)),
+        )
+        .collect()
+}
+
+fn merge_entries(
+    base: &SortedDeleteMap<u32, Byml>,
+    diff: &SortedDeleteMap<u32, Byml>,
+) -> SortedDeleteMap<u32, Byml> {
+    let mut merged = base.clone();
+    for (id, (entry, del)) in diff.iter_full() {
+        if *del {
+            merged.set_delete(id);
+        } else if let Some(base_entry) = merged.get_mut(id) {
+            // Mods packaged before entries were diffed field by field store
+            // each changed entry whole.
+            if util::is_deep_diff(entry) {
+                *base_entry = util::merge_byml_deep(base_entry, entry);
+            } else {
+                *base_entry = entry.clone();
+            }
+        } else if entry
+            .as_map()
+            .is_ok_and(|entry| entry.contains_key("HashId"))
+        {
+            merged.insert(*id, entry.clone());
+        }
+        // Otherwise this is a partial diff of an entry which is gone,
+        // presumably deleted by another mod, so it cannot be restored.
+    }
+    merged.and_delete()
+}
+
+fn entry_conflicts(
+    diff: &SortedDeleteMap<u32, Byml>,
+    other: &SortedDeleteMap<u32, Byml>,
+) -> Vec<String> {
+    diff.iter_full()
+        .flat_map(|(id, (entry, del))| {
+            match (other.get(id), other.is_delete(id)) {
+                (Some(other_entry), _) if !*del => {
+                    util::prefix_conflicts(
+                        &id.to_string(),
+                        util::conflicts_byml_deep(entry, other_entry),
+                    )
+                }
+                (_, Some(other_del)) if other_del != *del => vec![id.to_string().into()],
+                _ => vec![],
+            }
+        })
+        .collect()
+}
+
+impl MapUnit {
+    /// Every `HashId` used by the unit's objects and rails.
+    pub fn hash_ids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.objects.keys().chain(self.rails.keys()).copied()
+    }
+
+    /// The `SRTHash` of each object which has one, by `HashId`.
+    pub fn srt_hashes(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.objects.iter().filter_map(|(id, obj)| {
+            let srt = obj.as_map().ok()?.get("SRTHash")?.as_int::<i64>().ok()?;
+            Some((*id, srt as u32))
+        })
+    }
+
+    /// The destination `HashId` of each `LinksToObj` entry, by the `HashId` of
+    /// the linking object.
+    pub fn links(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.objects.iter().flat_map(|(id, obj)| {
+            obj.as_map()
+                .ok()
+                .and_then(|obj| obj.get("LinksToObj"))
+                .and_then(|links| links.as_array().ok())
+                .into_iter()
+                .flatten()
+                .filter_map(move |link| {
+                    let dest = link.as_map().ok()?.get("DestUnitHashId")?;
+                    Some((*id, dest.as_int::<u32>().ok()?))
+                })
+        })
+    }
+
+    /// Changes the `HashId` of an object or rail and updates every link to it
+    /// from the unit's objects. Links from the other unit of the map section
+    /// need to be updated with [`MapUnit::relink`]. Returns false if there is
+    /// no such entry.
+    pub fn rehash(&mut self, old: u32, new: u32) -> bool {
+        let entries = if self.objects.get(old).is_some() {
+            &mut self.objects
+        } else if self.rails.get(old).is_some() {
+            &mut self.rails
+        } else {
+            return false;
+        };
+        let Some(mut entry) = entries.get(old).cloned() else {
+            return false;
+        };
+        if let Byml::Map(entry) = &mut entry {
+            entry.insert("HashId".into(), Byml::U32(new));
+        }
+        entries.set_delete(old);
+        entries.delete();
+        entries.insert(new, entry);
+        self.relink(old, new);
+        true
+    }
+
+    /// Points every `LinksToObj` entry of the unit's objects which links to
+    /// `old` to `new` instead.
+    pub fn relink(&mut self, old: u32, new: u32) {
+        for (_, obj) in self.objects.iter_mut() {
+            let Byml::Map(obj) = obj else {
+                continue;
+            };
+            let Some(Byml::Array(links)) = obj.get_mut("LinksToObj") else {
+                continue;
+            };
+            for link in links.iter_mut() {
+                if let Byml::Map(link) = link {
+                    if link
+                        .get("DestUnitHashId")
+                        .and_then(|dest| dest.as_int::<u32>().ok())
+                        == Some(old)
+                    {
+                        link.insert("DestUnitHashId".into(), Byml::U32(new));
+                    }
+                }
+            }
+        }
+    }
+}
+
 impl Mergeable for MapUnit {
     fn diff(&self, other: &Self) -> Self {
         Self {
             pos_x:   other.pos_x,
             pos_z:   other.pos_z,
             size:    other.size,
-            objects: self.objects.diff(&other.objects),
-            rails:   self.rails.diff(&other.rails),
+            objects: diff_entries(&self.objects, &other.objects),
+            rails:   diff_entries(&self.rails, &other.rails),
         }
     }
 
@@ -102,10 +259,20 @@ impl Mergeable for MapUnit {
             pos_x:   diff.pos_x,
             pos_z:   diff.pos_z,
             size:    diff.size,
-            objects: self.objects.merge(&diff.objects),
-            rails:   self.rails.merge(&diff.rails),
+            objects: merge_entries(&self.objects, &diff.objects),
+            rails:   merge_entries(&self.rails, &diff.rails),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        util::prefix_conflicts("Objs", entry_conflicts(&self.objects, &other.objects))
+            .into_iter()
+            .chain(util::prefix_conflicts(
+                "Rails",
+                entry_conflicts(&self.rails, &other.rails),
+            ))
+            .collect()
+    }
 }
 
 impl Resource for MapUnit {
@@ -227,6 +394,31 @@ mod tests {
         assert_eq!(merged, munt2);
     }
 
+    #[test]
+    fn merge_old_entry_diff() {
+        let byml = load_mainfield_munt();
+        let munt = super::MapUnit::try_from(&byml).unwrap();
+        let (id, obj) = munt
+            .objects
+            .iter()
+            .find(|(_, obj)| obj.as_map().unwrap().contains_key("!Parameters"))
+            .map(|(id, obj)| (*id, obj.clone()))
+            .unwrap();
+        let mut modded = obj.clone();
+        modded.as_mut_map().unwrap().remove("!Parameters");
+        // Mods packaged before field by field diffs store the whole object
+        let mut diff = munt.clone();
+        diff.objects = [(id, modded.clone())].into_iter().collect();
+        diff.rails = Default::default();
+        let merged = munt.merge(&diff);
+        assert_eq!(merged.objects.get(id), Some(&modded));
+
+        let mut munt2 = munt.clone();
+        munt2.objects.insert(id, modded.clone());
+        let merged = munt.merge(&munt.diff(&munt2));
+        assert_eq!(merged.objects.get(id), Some(&modded));
+    }
+
     #[test]
     fn merge_cdungeon() {
         let byml = load_cdungeon_munt();
@@ -238,6 +430,66 @@ mod tests {
         assert_eq!(merged, munt2);
     }
 
+    #[test]
+    fn merge_object_fields() {
+        let munt = super::MapUnit::try_from(&load_mainfield_munt()).unwrap();
+        let (&id, obj) = munt.objects.iter().next().unwrap();
+        let translate = Byml::Array(vec![Byml::Float(1.0); 3]);
+        let mut mod1 = munt.clone();
+        let mut obj1 = obj.as_map().unwrap().clone();
+        obj1.insert("Translate".into(), translate.clone());
+        mod1.objects.insert(id, Byml::Map(obj1));
+        let mut mod2 = munt.clone();
+        let mut obj2 = obj.as_map().unwrap().clone();
+        obj2.insert(
+            "UnitConfigName".into(),
+            Byml::String("Enemy_Lynel_Gold".into()),
+        );
+        mod2.objects.insert(id, Byml::Map(obj2));
+        let diff1 = munt.diff(&mod1);
+        let diff2 = munt.diff(&mod2);
+        assert!(diff1.conflicts(&diff2).is_empty());
+        let merged = munt.merge(&diff1).merge(&diff2);
+        let merged_obj = merged.objects.get(id).unwrap().as_map().unwrap();
+        assert_eq!(merged_obj["Translate"], translate);
+        assert_eq!(
+            merged_obj["UnitConfigName"],
+            Byml::String("Enemy_Lynel_Gold".into())
+        );
+        assert_eq!(merged.objects.len(), munt.objects.len());
+    }
+
+    #[test]
+    fn rehash() {
+        use roead::byml::map;
+        let byml = map!(
+            "Objs" => Byml::Array(vec![
+                map!(
+                    "HashId" => Byml::U32(1),
+                    "LinksToObj" => Byml::Array(vec![map!(
+                        "DefinitionName" => Byml::String("BasicSig".into()),
+                        "DestUnitHashId" => Byml::U32(2)
+                    )])
+                ),
+                map!("HashId" => Byml::U32(2), "SRTHash" => Byml::I32(-1)),
+            ]),
+            "Rails" => Byml::Array(vec![])
+        );
+        let mut munt = super::MapUnit::try_from(&byml).unwrap();
+        assert_eq!(munt.links().collect::<Vec<_>>(), vec![(1, 2)]);
+        assert_eq!(munt.srt_hashes().collect::<Vec<_>>(), vec![(2, u32::MAX)]);
+        assert!(munt.rehash(2, 3));
+        assert_eq!(munt.hash_ids().collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(munt.links().collect::<Vec<_>>(), vec![(1, 3)]);
+        assert_eq!(
+            munt.objects.get(3).unwrap().as_map().unwrap()["HashId"],
+            Byml::U32(3)
+        );
+        assert!(!munt.rehash(2, 4));
+        munt.relink(3, 5);
+        assert_eq!(munt.links().collect::<Vec<_>>(), vec![(1, 5)]);
+    }
+
     #[test]
     fn identify() {
         let path = std::path::Path::new("content/Map/MainField/F-3/F-3_Dynamic.smubin");
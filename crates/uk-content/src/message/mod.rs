@@ -43,6 +43,14 @@ impl Mergeable for Msyt {
             entries: entries.into_iter().collect(),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|(k, v)| other.entries.get(*k).is_some_and(|other_v| other_v != *v))
+            .map(|(k, _)| k.as_str().into())
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone, Default, PartialEq)]
@@ -97,6 +105,18 @@ impl Mergeable for MessagePack {
                 .collect(),
         )
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        self.0
+            .iter()
+            .filter_map(|(file, text)| {
+                other.0.get(file).map(|other_text| {
+                    crate::util::prefix_conflicts(file, text.conflicts(other_text))
+                })
+            })
+            .flatten()
+            .collect()
+    }
 }
 
 impl TryFrom<&'_ Sarc<'_>> for MessagePack {
@@ -44,6 +44,10 @@ impl Mergeable for QuestProduct {
     fn merge(&self, diff: &Self) -> Self {
         Self(self.0.merge(&diff.0))
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        self.0.conflicts(&other.0)
+    }
 }
 
 impl Resource for QuestProduct {
@@ -93,8 +93,9 @@ pub enum MergeableResource {
     BinaryOverride(Box<(Vec<u8>, String)>),
 }
 
-impl std::fmt::Display for MergeableResource {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl MergeableResource {
+    /// The name of the resource type, e.g. `ActorLink`.
+    pub fn kind(&self) -> &'static str {
         match self {
             // Self::Actor(_) => "Actor",
             Self::ActorInfo(_) => "ActorInfo",
@@ -149,7 +150,12 @@ impl std::fmt::Display for MergeableResource {
             Self::GenericByml(_) => "GenericByml",
             Self::BinaryOverride(_) => "BinaryOverride",
         }
-        .fmt(f)
+    }
+}
+
+impl std::fmt::Display for MergeableResource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.kind().fmt(f)
     }
 }
 
@@ -241,9 +247,9 @@ impl_from_res!(Tips);
 impl_from_res!(UMii);
 impl_from_res!(WorldInfo);
 
-impl Mergeable for MergeableResource {
-    fn diff(&self, other: &Self) -> Self {
-        match (self, other) {
+impl TryMergeable for MergeableResource {
+    fn try_diff(&self, other: &Self) -> crate::Result<Self> {
+        Ok(match (self, other) {
             // (Self::Actor(a), Self::Actor(b)) => Self::Actor(Box::new(a.diff(b))),
             (Self::ActorInfo(a), Self::ActorInfo(b)) => Self::ActorInfo(Box::new(a.diff(b))),
             (Self::ActorLink(a), Self::ActorLink(b)) => Self::ActorLink(Box::new(a.diff(b))),
@@ -327,21 +333,25 @@ impl Mergeable for MergeableResource {
             (Self::Tips(a), Self::Tips(b)) => Self::Tips(Box::new(a.diff(b))),
             (Self::UMii(a), Self::UMii(b)) => Self::UMii(Box::new(a.diff(b))),
             (Self::WorldInfo(a), Self::WorldInfo(b)) => Self::WorldInfo(Box::new(a.diff(b))),
-            (Self::GenericByml(a), Self::GenericByml(b)) => Self::GenericByml(Box::new(a.diff(b))),
+            (Self::GenericByml(a), Self::GenericByml(b)) => {
+                Self::GenericByml(Box::new(crate::util::diff_byml_deep(a, b)))
+            }
             (Self::GenericAamp(a), Self::GenericAamp(b)) => Self::GenericAamp(Box::new(a.diff(b))),
             (Self::BinaryOverride(_), anything) => anything.clone(),
             (_anything, Self::BinaryOverride(bin)) => Self::BinaryOverride(bin.clone()),
             _ => {
-                panic!(
-                    "Tried to diff incompatible resources: {} and {}",
-                    &self, &other
-                )
-            }
-        }
+                return Err(crate::UKError::IncompatibleResources {
+                    action: "diff",
+                    base:   self.kind(),
+                    other:  other.kind(),
+                    path:   None,
+                });
+            }
+        })
     }
 
-    fn merge(&self, diff: &Self) -> Self {
-        match (self, diff) {
+    fn try_merge(&self, diff: &Self) -> crate::Result<Self> {
+        Ok(match (self, diff) {
             // (Self::Actor(a), Self::Actor(b)) => Self::Actor(Box::new(a.merge(b))),
             (Self::ActorInfo(a), Self::ActorInfo(b)) => Self::ActorInfo(Box::new(a.merge(b))),
             (Self::ActorLink(a), Self::ActorLink(b)) => Self::ActorLink(Box::new(a.merge(b))),
@@ -425,16 +435,81 @@ impl Mergeable for MergeableResource {
             (Self::Tips(a), Self::Tips(b)) => Self::Tips(Box::new(a.merge(b))),
             (Self::UMii(a), Self::UMii(b)) => Self::UMii(Box::new(a.merge(b))),
             (Self::WorldInfo(a), Self::WorldInfo(b)) => Self::WorldInfo(Box::new(a.merge(b))),
-            (Self::GenericByml(a), Self::GenericByml(b)) => Self::GenericByml(Box::new(a.merge(b))),
+            (Self::GenericByml(a), Self::GenericByml(b)) => {
+                Self::GenericByml(Box::new(crate::util::merge_byml_deep(a, b)))
+            }
             (Self::GenericAamp(a), Self::GenericAamp(b)) => Self::GenericAamp(Box::new(a.merge(b))),
             (Self::BinaryOverride(bin), _anything) => Self::BinaryOverride(bin.clone()),
             (_anything, Self::BinaryOverride(bin)) => Self::BinaryOverride(bin.clone()),
             _ => {
-                panic!(
-                    "Tried to merge incompatible resources: {} and {}",
-                    &self, &diff
-                )
-            }
+                return Err(crate::UKError::IncompatibleResources {
+                    action: "merge",
+                    base:   self.kind(),
+                    other:  diff.kind(),
+                    path:   None,
+                });
+            }
+        })
+    }
+}
+
+impl MergeableResource {
+    /// Given two diffs of the same kind of resource, returns the paths at
+    /// which both make different changes. Resources of different kinds have
+    /// none, since they cannot be merged at all.
+    pub fn conflicts(&self, other: &Self) -> Vec<String> {
+        match (self, other) {
+            (Self::ActorInfo(a), Self::ActorInfo(b)) => a.conflicts(b),
+            (Self::ActorLink(a), Self::ActorLink(b)) => a.conflicts(b),
+            (Self::AIProgram(a), Self::AIProgram(b)) => a.conflicts(b),
+            (Self::AISchedule(a), Self::AISchedule(b)) => a.conflicts(b),
+            (Self::AnimationInfo(a), Self::AnimationInfo(b)) => a.conflicts(b),
+            (Self::AreaData(a), Self::AreaData(b)) => a.conflicts(b),
+            (Self::AS(a), Self::AS(b)) => a.conflicts(b),
+            (Self::ASList(a), Self::ASList(b)) => a.conflicts(b),
+            (Self::AttClient(a), Self::AttClient(b)) => a.conflicts(b),
+            (Self::AttClientList(a), Self::AttClientList(b)) => a.conflicts(b),
+            (Self::Awareness(a), Self::Awareness(b)) => a.conflicts(b),
+            (Self::BarslistInfo(a), Self::BarslistInfo(b)) => a.conflicts(b),
+            (Self::BoneControl(a), Self::BoneControl(b)) => a.conflicts(b),
+            (Self::Chemical(a), Self::Chemical(b)) => a.conflicts(b),
+            (Self::ChemicalRes(a), Self::ChemicalRes(b)) => a.conflicts(b),
+            (Self::CookData(a), Self::CookData(b)) => a.conflicts(b),
+            (Self::DamageParam(a), Self::DamageParam(b)) => a.conflicts(b),
+            (Self::Demo(a), Self::Demo(b)) => a.conflicts(b),
+            (Self::DropTable(a), Self::DropTable(b)) => a.conflicts(b),
+            (Self::EventInfo(a), Self::EventInfo(b)) => a.conflicts(b),
+            (Self::FontArchive(a), Self::FontArchive(b)) => a.conflicts(b),
+            (Self::GameDataPack(a), Self::GameDataPack(b)) => a.conflicts(b),
+            (Self::GeneralParamList(a), Self::GeneralParamList(b)) => a.conflicts(b),
+            (Self::LazyTraverseList(a), Self::LazyTraverseList(b)) => a.conflicts(b),
+            (Self::LayoutArchive(a), Self::LayoutArchive(b)) => a.conflicts(b),
+            (Self::LevelSensor(a), Self::LevelSensor(b)) => a.conflicts(b),
+            (Self::LifeCondition(a), Self::LifeCondition(b)) => a.conflicts(b),
+            (Self::Location(a), Self::Location(b)) => a.conflicts(b),
+            (Self::Lod(a), Self::Lod(b)) => a.conflicts(b),
+            (Self::MapUnit(a), Self::MapUnit(b)) => a.conflicts(b),
+            (Self::MessagePack(a), Self::MessagePack(b)) => a.conflicts(b),
+            (Self::ModelList(a), Self::ModelList(b)) => a.conflicts(b),
+            (Self::Physics(a), Self::Physics(b)) => a.conflicts(b),
+            (Self::QuestProduct(a), Self::QuestProduct(b)) => a.conflicts(b),
+            (Self::RagdollBlendWeight(a), Self::RagdollBlendWeight(b)) => a.conflicts(b),
+            (Self::RagdollConfig(a), Self::RagdollConfig(b)) => a.conflicts(b),
+            (Self::RagdollConfigList(a), Self::RagdollConfigList(b)) => a.conflicts(b),
+            (Self::Recipe(a), Self::Recipe(b)) => a.conflicts(b),
+            (Self::ResidentActors(a), Self::ResidentActors(b)) => a.conflicts(b),
+            (Self::ResidentEvents(a), Self::ResidentEvents(b)) => a.conflicts(b),
+            (Self::SaveDataPack(a), Self::SaveDataPack(b)) => a.conflicts(b),
+            (Self::ShopData(a), Self::ShopData(b)) => a.conflicts(b),
+            (Self::ShopGameDataInfo(a), Self::ShopGameDataInfo(b)) => a.conflicts(b),
+            (Self::Static(a), Self::Static(b)) => a.conflicts(b),
+            (Self::StatusEffectList(a), Self::StatusEffectList(b)) => a.conflicts(b),
+            (Self::Tips(a), Self::Tips(b)) => a.conflicts(b),
+            (Self::UMii(a), Self::UMii(b)) => a.conflicts(b),
+            (Self::WorldInfo(a), Self::WorldInfo(b)) => a.conflicts(b),
+            (Self::GenericByml(a), Self::GenericByml(b)) => crate::util::conflicts_byml_deep(a, b),
+            (Self::GenericAamp(a), Self::GenericAamp(b)) => a.conflicts(b),
+            _ => vec![],
         }
     }
 }
@@ -720,6 +795,14 @@ impl Mergeable for SarcMap {
             files:     self.files.merge(&diff.files),
         }
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        self.files
+            .iter_full()
+            .filter(|(file, del)| other.files.is_delete(*file).is_some_and(|d| d != **del))
+            .map(|(file, _)| file.clone())
+            .collect()
+    }
 }
 
 impl SarcMap {
@@ -845,3 +928,37 @@ impl ResourceData {
         }
     }
 }
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+mod tests {
+    use roead::byml::Byml;
+
+    use super::MergeableResource;
+    use crate::{event::info::EventInfo, prelude::*, UKError};
+
+    #[test]
+    fn merge_incompatible() {
+        let base = MergeableResource::EventInfo(Box::new(EventInfo(Byml::Map(Default::default()))));
+        let diff = MergeableResource::GenericByml(Box::new(Byml::Map(Default::default())));
+        let err = base
+            .try_merge(&diff)
+            .unwrap_err()
+            .with_path("Event/EventInfo.product.byml");
+        assert!(matches!(
+            &err,
+            UKError::IncompatibleResources {
+                action: "merge",
+                base: "EventInfo",
+                other: "GenericByml",
+                path: Some(path),
+            } if path == "Event/EventInfo.product.byml"
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Tried to merge incompatible resources: EventInfo and GenericByml at \
+             Event/EventInfo.product.byml"
+        );
+        assert!(base.conflicts(&diff).is_empty());
+    }
+}
@@ -54,6 +54,10 @@ impl Mergeable for BarslistInfo {
     fn merge(&self, diff: &Self) -> Self {
         Self(self.0.deep_merge(&diff.0))
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<String> {
+        self.0.deep_conflicts(&other.0)
+    }
 }
 
 impl Resource for BarslistInfo {
@@ -12,6 +12,37 @@ pub type IndexSet<V> = indexmap::IndexSet<V, std::hash::BuildHasherDefault<rustc
 pub trait DeleteKey: Hash + Eq + Clone {}
 impl<T> DeleteKey for T where T: Hash + Eq + Clone {}
 
+/// A delete map key which can name its entry in a merge conflict path.
+pub trait ConflictKey {
+    fn conflict_name(&self) -> smartstring::alias::String;
+}
+
+macro_rules! impl_conflict_key {
+    ($($type:ty),*) => {
+        $(
+            impl ConflictKey for $type {
+                fn conflict_name(&self) -> smartstring::alias::String {
+                    self.to_string().into()
+                }
+            }
+        )*
+    };
+}
+
+impl_conflict_key!(smartstring::alias::String, u32, usize, super::BymlHashValue);
+
+impl<const N: usize> ConflictKey for roead::types::FixedSafeString<N> {
+    fn conflict_name(&self) -> smartstring::alias::String {
+        self.as_str().into()
+    }
+}
+
+impl ConflictKey for (smartstring::alias::String, i32) {
+    fn conflict_name(&self) -> smartstring::alias::String {
+        join_str::jstr!("{&self.0}/{&lexical::to_string(self.1)}").into()
+    }
+}
+
 pub struct DeleteIterator<I, T>
 where
     I: Iterator<Item = (T, bool)>,
@@ -174,6 +205,19 @@ impl<T: Clone + PartialEq> Mergeable for DeleteVec<T> {
         }
         Self(all_items).and_delete()
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<smartstring::alias::String> {
+        self.0
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (item, del))| {
+                other
+                    .is_delete(item)
+                    .filter(|other_del| other_del != del)
+                    .map(|_| i.to_string().into())
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -300,6 +344,20 @@ impl<T: DeleteKey> Mergeable for DeleteSet<T> {
             .collect::<Self>()
             .and_delete()
     }
+
+    fn conflicts(&self, other: &Self) -> Vec<smartstring::alias::String> {
+        self.0
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (item, del))| {
+                other
+                    .0
+                    .get(item)
+                    .filter(|other_del| *other_del != del)
+                    .map(|_| i.to_string().into())
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -393,6 +451,11 @@ impl<T: DeleteKey + Ord> SortedDeleteSet<T> {
         self.0.contains_key(item.borrow())
     }
 
+    #[inline]
+    pub fn is_delete(&self, item: impl Borrow<T>) -> Option<bool> {
+        self.0.get(item.borrow()).copied()
+    }
+
     #[inline]
     pub fn insert(&mut self, item: T) {
         self.0.insert(item, false);
@@ -595,7 +658,7 @@ macro_rules! impl_delete_map {
             }
         }
 
-        impl<T: $($key)*, U: PartialEq + Clone> crate::prelude::Mergeable for $type<T, U> {
+        impl<T: $($key)* + ConflictKey, U: PartialEq + Clone> crate::prelude::Mergeable for $type<T, U> {
             fn diff(&self, other: &Self) -> Self {
                 other
                     .0
@@ -618,6 +681,15 @@ macro_rules! impl_delete_map {
                         .collect(),
                 )
             }
+
+            fn conflicts(&self, other: &Self) -> Vec<smartstring::alias::String> {
+                self.0
+                    .iter()
+                    .filter_map(|(k, v)| {
+                        other.0.get(k).filter(|other_v| *other_v != v).map(|_| k.conflict_name())
+                    })
+                    .collect()
+            }
         }
 
         impl<T: $($key)*, U: crate::prelude::Mergeable + Clone + PartialEq + Default> $type<T, U> {
@@ -664,6 +736,26 @@ macro_rules! impl_delete_map {
                     .collect::<$type<_, _>>()
                     .and_delete()
             }
+
+            pub fn deep_conflicts(&self, other: &Self) -> Vec<smartstring::alias::String>
+            where
+                T: ConflictKey,
+            {
+                self.0
+                    .iter()
+                    .flat_map(|(k, (v, del))| {
+                        match other.0.get(k) {
+                            Some((other_v, false)) if !*del => {
+                                crate::util::prefix_conflicts(&k.conflict_name(), v.conflicts(other_v))
+                            }
+                            Some((other_v, other_del)) if other_v != v || other_del != del => {
+                                vec![k.conflict_name()]
+                            }
+                            _ => vec![],
+                        }
+                    })
+                    .collect()
+            }
         }
     };
 }
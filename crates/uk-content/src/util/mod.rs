@@ -94,6 +94,8 @@ pub fn merge_pobj(base: &ParameterObject, diff: &ParameterObject) -> ParameterOb
         .collect()
 }
 
+/// Diffs two BYML hashes by their top-level keys. If either value is not a
+/// hash, the diff is simply the other value, which replaces the base on merge.
 pub fn diff_byml_shallow(base: &Byml, other: &Byml) -> Byml {
     if let (Ok(base), Ok(other)) = (base.as_map(), other.as_map()) {
         Byml::Map(
@@ -114,10 +116,13 @@ pub fn diff_byml_shallow(base: &Byml, other: &Byml) -> Byml {
                 .collect(),
         )
     } else {
-        panic!("Can only shallow diff BYML hashes")
+        other.clone()
     }
 }
 
+/// Merges a diff from [`diff_byml_shallow`] into a BYML hash. A diff which is
+/// not a hash replaces the base outright, as does any diff of a base which is
+/// not a hash.
 pub fn merge_byml_shallow(base: &Byml, diff: &Byml) -> Byml {
     match (base, diff) {
         (Byml::Map(base), Byml::Map(diff)) => {
@@ -130,7 +135,365 @@ pub fn merge_byml_shallow(base: &Byml, diff: &Byml) -> Byml {
             Byml::Map(new)
         }
         (Byml::Map(base), Byml::Null) => Byml::Map(base.clone()),
-        _ => panic!("Can only shallow merge BYML hashes"),
+        (_, diff) => diff.clone(),
+    }
+}
+
+/// Keys which can identify the entries of a BYML array of hashes, in order of
+/// preference.
+pub const BYML_ID_KEYS: &[&str] = &["HashId", "name", "DataName", "DestUnitHashId"];
+/// Marks a deep diff of a keyed array, storing the key used to identify
+/// entries.
+const KEYED_ARRAY: &str = "__ukmm_keyed_array";
+/// Changed or deleted (`Null`) entries of a keyed array, by ID.
+const KEYED_CHANGES: &str = "__ukmm_changes";
+/// New entries appended to a keyed array, in order.
+const KEYED_ADDED: &str = "__ukmm_added";
+/// Marks the top level of a hash diffed by [`diff_byml_deep`]. Mods packaged
+/// before deep diffs carry shallow diffs, which replace values whole, so they
+/// have to be told apart when merging.
+const DEEP_DIFF: &str = "__ukmm_deep";
+
+/// Whether a BYML diff was made by [`diff_byml_deep`], rather than being a
+/// shallow diff or a whole document.
+pub fn is_deep_diff(diff: &Byml) -> bool {
+    matches!(
+        diff,
+        Byml::Map(diff) if diff.contains_key(DEEP_DIFF) || diff.contains_key(KEYED_ARRAY)
+    )
+}
+
+fn byml_id(value: &Byml) -> Option<std::string::String> {
+    match value {
+        Byml::String(s) => Some(s.to_string()),
+        Byml::I32(v) => Some(v.to_string()),
+        Byml::U32(v) => Some(v.to_string()),
+        Byml::I64(v) => Some(v.to_string()),
+        Byml::U64(v) => Some(v.to_string()),
+        _ => None,
+    }
+}
+
+fn entry_id(entry: &Byml, key: &str) -> Option<std::string::String> {
+    entry
+        .as_map()
+        .ok()
+        .and_then(|map| map.get(key))
+        .and_then(byml_id)
+}
+
+/// Finds a key which uniquely identifies every entry of an array of hashes.
+fn array_id_key(array: &[Byml]) -> Option<&'static str> {
+    if array.is_empty() {
+        return None;
+    }
+    BYML_ID_KEYS.iter().copied().find(|key| {
+        let mut ids = HashSet::default();
+        array
+            .iter()
+            .all(|entry| entry_id(entry, key).is_some_and(|id| ids.insert(id)))
+    })
+}
+
+fn diff_byml_keyed(base: &[Byml], other: &[Byml]) -> Option<Byml> {
+    let key = array_id_key(base)?;
+    if array_id_key(other) != Some(key) {
+        return None;
+    }
+    let base_ids: Vec<_> = base.iter().filter_map(|e| entry_id(e, key)).collect();
+    let other_ids: Vec<_> = other.iter().filter_map(|e| entry_id(e, key)).collect();
+    let base_set: HashSet<_> = base_ids.iter().collect();
+    let other_entries: HashMap<_, _> = other_ids.iter().zip(other).collect();
+    // New entries can only be appended, so if the mod reorders entries or
+    // inserts them in the middle, the whole array has to be replaced instead.
+    let existing = other_ids
+        .iter()
+        .take_while(|id| base_set.contains(id))
+        .count();
+    if other_ids[existing..].iter().any(|id| base_set.contains(id))
+        || !base_ids
+            .iter()
+            .filter(|id| other_entries.contains_key(id))
+            .eq(other_ids[..existing].iter())
+    {
+        return None;
+    }
+    let changes: Map = base_ids
+        .iter()
+        .zip(base)
+        .filter_map(|(id, entry)| {
+            match other_entries.get(id) {
+                Some(other_entry) if *other_entry == entry => None,
+                Some(other_entry) => Some((id.as_str().into(), diff_deep(entry, other_entry))),
+                None => Some((id.as_str().into(), Byml::Null)),
+            }
+        })
+        .collect();
+    Some(Byml::Map(
+        [
+            (KEYED_ARRAY.into(), Byml::String(key.into())),
+            (KEYED_CHANGES.into(), Byml::Map(changes)),
+            (KEYED_ADDED.into(), Byml::Array(other[existing..].to_vec())),
+        ]
+        .into_iter()
+        .collect(),
+    ))
+}
+
+fn merge_byml_keyed(base: &[Byml], diff: &Map) -> Byml {
+    let Some(Byml::String(key)) = diff.get(KEYED_ARRAY) else {
+        return Byml::Array(base.to_vec());
+    };
+    let key = key.as_str();
+    let changes = diff.get(KEYED_CHANGES).and_then(|c| c.as_map().ok());
+    let mut merged: Vec<Byml> = base
+        .iter()
+        .filter_map(|entry| {
+            let change = changes
+                .zip(entry_id(entry, key))
+                .and_then(|(changes, id)| changes.get(id.as_str()));
+            match change {
+                Some(Byml::Null) => None,
+                Some(change) => Some(merge_deep(entry, change)),
+                None => Some(entry.clone()),
+            }
+        })
+        .collect();
+    if let Some(Byml::Array(added)) = diff.get(KEYED_ADDED) {
+        for entry in added {
+            let id = entry_id(entry, key);
+            match merged
+                .iter()
+                .position(|e| id.is_some() && entry_id(e, key) == id)
+            {
+                Some(i) => merged[i] = entry.clone(),
+                None => merged.push(entry.clone()),
+            }
+        }
+    }
+    Byml::Array(merged)
+}
+
+/// Diffs two BYML documents recursively. Nested hashes are diffed key by key,
+/// with deleted keys marked by `Null` at any depth, and arrays of hashes which
+/// can be identified by one of [`BYML_ID_KEYS`] are diffed entry by entry.
+/// Any other changed value is replaced as a whole.
+pub fn diff_byml_deep(base: &Byml, other: &Byml) -> Byml {
+    let mut diff = diff_deep(base, other);
+    if let Byml::Map(diff) = &mut diff {
+        if !diff.contains_key(KEYED_ARRAY) {
+            diff.insert(DEEP_DIFF.into(), Byml::Bool(true));
+        }
+    }
+    diff
+}
+
+fn diff_deep(base: &Byml, other: &Byml) -> Byml {
+    match (base, other) {
+        (Byml::Map(base), Byml::Map(other)) => {
+            Byml::Map(
+                other
+                    .iter()
+                    .filter_map(|(key, value)| {
+                        match base.get(key) {
+                            Some(base_value) if base_value == value => None,
+                            Some(base_value) => Some((key.clone(), diff_deep(base_value, value))),
+                            None => Some((key.clone(), value.clone())),
+                        }
+                    })
+                    .chain(
+                        base.keys()
+                            .filter(|&key| (!other.contains_key(key)))
+                            .map(|key| (key.clone(), Byml::Null)),
+                    )
+                    .collect(),
+            )
+        }
+        (Byml::Array(base), Byml::Array(other)) => {
+            diff_byml_keyed(base, other).unwrap_or_else(|| Byml::Array(other.clone()))
+        }
+        _ => other.clone(),
+    }
+}
+
+/// Merges a diff produced by [`diff_byml_deep`] into a BYML document. Any
+/// other diff is merged with [`merge_byml_shallow`], as it was made before
+/// deep diffs and replaces the values it has whole.
+pub fn merge_byml_deep(base: &Byml, diff: &Byml) -> Byml {
+    if is_deep_diff(diff) {
+        merge_deep(base, diff)
+    } else {
+        merge_byml_shallow(base, diff)
+    }
+}
+
+fn merge_deep(base: &Byml, diff: &Byml) -> Byml {
+    match (base, diff) {
+        (_, Byml::Map(diff)) if diff.contains_key(KEYED_ARRAY) => {
+            match base {
+                Byml::Array(base) => merge_byml_keyed(base, diff),
+                _ => merge_byml_keyed(&[], diff),
+            }
+        }
+        (Byml::Map(base), Byml::Map(diff)) => {
+            Byml::Map(
+                base.iter()
+                    .filter_map(|(key, value)| {
+                        match diff.get(key) {
+                            Some(Byml::Null) => None,
+                            Some(diff_value) => Some((key.clone(), merge_deep(value, diff_value))),
+                            None => Some((key.clone(), value.clone())),
+                        }
+                    })
+                    .chain(
+                        diff.iter()
+                            .filter(|(key, value)| {
+                                !base.contains_key(*key)
+                                    && **value != Byml::Null
+                                    && key.as_str() != DEEP_DIFF
+                            })
+                            .map(|(key, value)| (key.clone(), value.clone())),
+                    )
+                    .collect(),
+            )
+        }
+        (Byml::Map(base), Byml::Null) => Byml::Map(base.clone()),
+        _ => diff.clone(),
+    }
+}
+
+/// Lists the paths at which two deep BYML diffs against the same base make
+/// different changes.
+pub fn conflicts_byml_deep(diff: &Byml, other: &Byml) -> Vec<smartstring::alias::String> {
+    let (Byml::Map(diff), Byml::Map(other)) = (diff, other) else {
+        return vec![];
+    };
+    if let (Some(Byml::String(key)), true) =
+        (diff.get(KEYED_ARRAY), other.contains_key(KEYED_ARRAY))
+    {
+        let mut conflicts = match (diff.get(KEYED_CHANGES), other.get(KEYED_CHANGES)) {
+            (Some(changes), Some(other_changes)) => conflicts_byml_deep(changes, other_changes),
+            _ => vec![],
+        };
+        if let (Some(Byml::Array(added)), Some(Byml::Array(other_added))) =
+            (diff.get(KEYED_ADDED), other.get(KEYED_ADDED))
+        {
+            conflicts.extend(added.iter().filter_map(|entry| {
+                let id = entry_id(entry, key)?;
+                other_added
+                    .iter()
+                    .any(|o| o != entry && entry_id(o, key).as_ref() == Some(&id))
+                    .then(|| id.into())
+            }));
+        }
+        return conflicts;
+    }
+    diff.iter()
+        .flat_map(|(key, value)| {
+            match other.get(key) {
+                Some(other_value @ Byml::Map(_)) if matches!(value, Byml::Map(_)) => {
+                    prefix_conflicts(key.as_str(), conflicts_byml_deep(value, other_value))
+                }
+                Some(other_value) if other_value != value => vec![key.as_str().into()],
+                _ => vec![],
+            }
+        })
+        .collect()
+}
+
+/// Prefixes each conflict path with the key of its parent.
+pub fn prefix_conflicts(
+    prefix: &str,
+    conflicts: Vec<smartstring::alias::String>,
+) -> Vec<smartstring::alias::String> {
+    conflicts
+        .into_iter()
+        .map(|path| {
+            if path.is_empty() {
+                prefix.into()
+            } else {
+                join_str::jstr!("{prefix}/{&path}").into()
+            }
+        })
+        .collect()
+}
+
+fn param_name(name: &Name, index: usize, parent: u32) -> smartstring::alias::String {
+    get_default_name_table()
+        .get_name(name.hash(), index, parent)
+        .map(|name| name.to_string().into())
+        .unwrap_or_else(|| name.hash().to_string().into())
+}
+
+/// Lists the paths at which two parameter list diffs against the same base
+/// set different values.
+pub fn conflicts_plist<P: ParameterListing>(
+    diff: &P,
+    other: &P,
+) -> Vec<smartstring::alias::String> {
+    conflicts_plist_inner(diff, other, hash_name("param_root"))
+}
+
+fn conflicts_plist_inner<P: ParameterListing>(
+    diff: &P,
+    other: &P,
+    parent: u32,
+) -> Vec<smartstring::alias::String> {
+    let mut conflicts = vec![];
+    for (i, (k, v)) in diff.lists().0.iter().enumerate() {
+        if let Some(other_v) = other.lists().0.get(k) {
+            conflicts.extend(prefix_conflicts(
+                &param_name(k, i, parent),
+                conflicts_plist_inner(v, other_v, k.hash()),
+            ));
+        }
+    }
+    for (i, (k, v)) in diff.objects().0.iter().enumerate() {
+        if let Some(other_v) = other.objects().0.get(k) {
+            conflicts.extend(prefix_conflicts(
+                &param_name(k, i, parent),
+                conflicts_pobj(v, other_v, k.hash()),
+            ));
+        }
+    }
+    conflicts
+}
+
+/// Lists the names of the parameters which two parameter object diffs
+/// against the same base set to different values.
+pub fn conflicts_pobj(
+    diff: &ParameterObject,
+    other: &ParameterObject,
+    parent: u32,
+) -> Vec<smartstring::alias::String> {
+    diff.0
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (k, v))| {
+            other
+                .0
+                .get(k)
+                .filter(|other_v| *other_v != v)
+                .map(|_| param_name(k, i, parent))
+        })
+        .collect()
+}
+
+/// Lists the top-level keys at which two shallow BYML hash diffs against the
+/// same base make different changes.
+pub fn conflicts_byml_shallow(diff: &Byml, other: &Byml) -> Vec<smartstring::alias::String> {
+    match (diff.as_map(), other.as_map()) {
+        (Ok(diff), Ok(other)) => {
+            diff.iter()
+                .filter_map(|(key, value)| {
+                    other
+                        .get(key)
+                        .filter(|other_value| *other_value != value)
+                        .map(|_| key.as_str().into())
+                })
+                .collect()
+        }
+        _ => vec![],
     }
 }
 
@@ -155,6 +518,22 @@ pub fn simple_index_merge<T: Clone + PartialEq>(
         .collect()
 }
 
+/// Whether two diffs against the same base both set an optional value, to
+/// different values.
+pub fn options_conflict<T: PartialEq>(diff: &Option<T>, other: &Option<T>) -> bool {
+    matches!((diff, other), (Some(diff), Some(other)) if diff != other)
+}
+
+pub fn simple_index_conflicts<T: PartialEq>(
+    diff: &BTreeMap<usize, T>,
+    other: &BTreeMap<usize, T>,
+) -> Vec<smartstring::alias::String> {
+    diff.iter()
+        .filter(|&(i, item)| other.get(i).is_some_and(|other_item| other_item != item))
+        .map(|(i, _)| lexical::to_string(*i).into())
+        .collect()
+}
+
 #[derive(
     Debug,
     Default,
@@ -214,6 +593,12 @@ impl FromStr for BymlHashValue {
     }
 }
 
+impl std::fmt::Display for BymlHashValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
 impl From<u32> for BymlHashValue {
     fn from(val: u32) -> Self {
         Self(val)
@@ -285,6 +670,108 @@ impl ParameterExt for Parameter {
     }
 }
 
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+#[test]
+fn test_deep_byml() {
+    use roead::byml::map;
+    let base = map!(
+        "Flags" => map!(
+            "IsEnemy" => Byml::Bool(true),
+            "Life" => Byml::I32(100)
+        ),
+        "Objs" => Byml::Array(vec![
+            map!("HashId" => Byml::U32(1), "Name" => Byml::String("Enemy_Bokoblin_Junior".into())),
+            map!("HashId" => Byml::U32(2), "Name" => Byml::String("Obj_TreasureChest".into())),
+        ]),
+        "Unused" => Byml::Bool(false)
+    );
+    let other = map!(
+        "Flags" => map!(
+            "IsEnemy" => Byml::Bool(true),
+            "Life" => Byml::I32(200)
+        ),
+        "Objs" => Byml::Array(vec![
+            map!("HashId" => Byml::U32(1), "Name" => Byml::String("Enemy_Bokoblin_Gold".into())),
+            map!("HashId" => Byml::U32(3), "Name" => Byml::String("Enemy_Lynel_Gold".into())),
+        ])
+    );
+    let diff = diff_byml_deep(&base, &other);
+    let diff_map = diff.as_map().unwrap();
+    assert_eq!(diff_map["Flags"].as_map().unwrap().len(), 1);
+    assert_eq!(diff_map["Unused"], Byml::Null);
+    assert_eq!(merge_byml_deep(&base, &diff), other);
+
+    let other2 = map!(
+        "Flags" => map!(
+            "IsEnemy" => Byml::Bool(false),
+            "Life" => Byml::I32(100)
+        ),
+        "Objs" => Byml::Array(vec![
+            map!("HashId" => Byml::U32(1), "Name" => Byml::String("Enemy_Bokoblin_Senior".into())),
+            map!("HashId" => Byml::U32(2), "Name" => Byml::String("Obj_TreasureChest".into())),
+        ]),
+        "Unused" => Byml::Bool(false)
+    );
+    let diff2 = diff_byml_deep(&base, &other2);
+    let merged = merge_byml_deep(&merge_byml_deep(&base, &diff), &diff2);
+    let flags = merged.as_map().unwrap()["Flags"].as_map().unwrap();
+    assert_eq!(flags["IsEnemy"], Byml::Bool(false));
+    assert_eq!(flags["Life"], Byml::I32(200));
+    assert_eq!(
+        merged.as_map().unwrap()["Objs"].as_array().unwrap().len(),
+        2
+    );
+    assert_eq!(conflicts_byml_deep(&diff, &diff2), vec![
+        smartstring::alias::String::from("Objs/1/Name")
+    ]);
+}
+
+#[allow(clippy::unwrap_used)]
+#[cfg(test)]
+#[test]
+fn test_deep_byml_old_diff() {
+    use roead::byml::map;
+    let base = map!(
+        "Flags" => map!(
+            "IsEnemy" => Byml::Bool(true),
+            "Life" => Byml::I32(100)
+        ),
+        "Unused" => Byml::Bool(false)
+    );
+    let other = map!(
+        "Flags" => map!("Life" => Byml::I32(200)),
+        "Unused" => Byml::Bool(false)
+    );
+    // Mods packaged before deep diffs have shallow diffs, which replace each
+    // changed value whole
+    let diff = diff_byml_shallow(&base, &other);
+    assert!(!is_deep_diff(&diff));
+    let merged = merge_byml_deep(&base, &diff);
+    assert_eq!(merged, other);
+    assert!(!merged.as_map().unwrap()["Flags"]
+        .as_map()
+        .unwrap()
+        .contains_key("IsEnemy"));
+
+    let diff = diff_byml_deep(&base, &other);
+    assert!(is_deep_diff(&diff));
+    assert_eq!(merge_byml_deep(&base, &diff), other);
+}
+
+#[cfg(test)]
+#[test]
+fn test_shallow_byml_non_map() {
+    use roead::byml::map;
+    let base = map!("Life" => Byml::I32(100));
+    let other = Byml::Array(vec![Byml::I32(100)]);
+    let diff = diff_byml_shallow(&base, &other);
+    assert_eq!(diff, other);
+    assert_eq!(merge_byml_shallow(&base, &diff), other);
+    assert_eq!(merge_byml_shallow(&other, &base), base);
+    assert_eq!(merge_byml_shallow(&base, &Byml::Null), base);
+}
+
 #[allow(clippy::unwrap_used)]
 #[cfg(test)]
 #[test]
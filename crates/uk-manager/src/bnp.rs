@@ -19,6 +19,7 @@ use uk_mod::pack::ModPacker;
 use uk_reader::ResourceReader;
 use uk_util::PathExt;
 
+pub use self::export::export_bnp;
 use crate::{settings::Platform, util::extract_7z};
 mod actorinfo;
 mod areadata;
@@ -28,6 +29,7 @@ mod drops;
 mod dstatic;
 mod effects;
 mod events;
+mod export;
 mod gamedata;
 mod mainstatic;
 mod maps;
@@ -520,6 +522,12 @@ pub fn unpack_bnp(core: &crate::core::Manager, path: &Path) -> Result<PathBuf> {
 }
 
 pub fn convert_bnp(core: &crate::core::Manager, path: &Path) -> Result<PathBuf> {
+    convert_bnp_to(core, path, &std::env::temp_dir())
+}
+
+/// Convert a BNP to a UKMM mod at `dest`, which is either the file to write
+/// or a folder to write it to under the mod's name.
+pub fn convert_bnp_to(core: &crate::core::Manager, path: &Path, dest: &Path) -> Result<PathBuf> {
     let tempdir = unpack_bnp(core, path).with_context(|| {
         format!(
             "Failed to unpack {}",
@@ -528,14 +536,13 @@ pub fn convert_bnp(core: &crate::core::Manager, path: &Path) -> Result<PathBuf>
                 .unwrap_or_default()
         )
     })?;
-    let tempfile = std::env::temp_dir();
     let meta = if let Some(info_path) = tempdir.join("info.json").exists_then() {
         ModPacker::parse_info(info_path)?
     } else {
         ModPacker::parse_rules(tempdir.join("rules.txt")).context("Failed to parse BNP metadata")?
     };
     let name = meta.name.clone();
-    let new_mod = ModPacker::new(tempdir, tempfile.as_path(), Some(meta), vec![
+    let new_mod = ModPacker::new(tempdir, dest, Some(meta), vec![
         core.settings()
             .dump()
             .context("No dump for current platform")?,
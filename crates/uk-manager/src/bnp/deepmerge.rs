@@ -10,7 +10,7 @@ use uk_content::util::merge_plist;
 
 use super::{parse_aamp_diff, AampDiffEntry, BnpConverter};
 
-fn handle_diff_entry(
+pub(super) fn handle_diff_entry(
     sarc: &mut SarcWriter,
     nest_root: &str,
     contents: &AampDiffEntry,
@@ -0,0 +1,920 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow_ext::{Context, Result};
+use fs_err as fs;
+use rayon::prelude::*;
+use roead::{
+    aamp::{Parameter, ParameterIO, ParameterList, ParameterListing},
+    byml::{Byml, Map},
+    sarc::{Sarc, SarcWriter},
+    yaz0::{compress_if, decompress_if},
+};
+use rustc_hash::FxHashMap;
+use serde_json::json;
+use smartstring::alias::String;
+use uk_content::{
+    constants::Language,
+    data::gamedata::GameData,
+    message::MessagePack,
+    platform_prefixes,
+    prelude::{Endian, Mergeable, Resource},
+    resource::{ActorInfo, GameDataPack, SaveDataPack},
+    util::diff_plist,
+};
+use uk_mod::{unpack::ModReader, Meta, ModOption, ModOptionGroup, ModPlatform, OptionGroup};
+use uk_reader::ResourceReader;
+
+use super::texts::TextsLog;
+
+static RULES_TITLE_IDS: &str = "00050000101C9300,00050000101C9400,00050000101C9500";
+
+/// Diffs of AAMP files, by BCML's nested path to the file, e.g.
+/// `content/Actor/Pack/Enemy_Bokoblin.sbactorpack//Actor/AIProgram/Bokoblin.baiprog`.
+type AampDiffs = Vec<(std::string::String, ParameterList)>;
+
+/// What cleaning one file of an unpacked mod found.
+#[derive(Default)]
+struct Cleaned {
+    deepmerge: AampDiffs,
+    pack: Option<std::string::String>,
+}
+
+/// Builds the files of a BNP from a UKMM mod. Each tree (the base mod or one
+/// of its options) is unpacked onto the dump just as it would be deployed,
+/// then anything BCML can merge is moved from the files into logs, and
+/// anything left unchanged from the dump is removed.
+struct BnpExporter {
+    dump:    Arc<ResourceReader>,
+    endian:  Endian,
+    lang:    Language,
+    content: &'static str,
+    aoc:     &'static str,
+}
+
+impl BnpExporter {
+    fn build(&self, reader: ModReader, root: &Path) -> Result<()> {
+        uk_mod::unpack::ModUnpacker::new(
+            self.dump.clone(),
+            self.endian,
+            self.lang,
+            vec![reader],
+            root.to_path_buf(),
+        )
+        .unpack()?;
+        let logs = root.join("logs");
+        fs::create_dir_all(&logs)?;
+        self.bootup_logs(root, &logs)
+            .context("Failed to create gamedata and savedata logs")?;
+        self.actorinfo_log(root, &logs)
+            .context("Failed to create actor info log")?;
+        self.texts_log(root, &logs)
+            .context("Failed to create texts log")?;
+        let (deepmerge, packs) = self.clean(root)?;
+        if !deepmerge.is_empty() {
+            fs::write(
+                logs.join("deepmerge.aamp"),
+                deepmerge_log(deepmerge).to_binary(),
+            )?;
+        }
+        if !packs.is_empty() {
+            fs::write(
+                logs.join("packs.json"),
+                serde_json::to_string_pretty(&packs)?,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Move the gamedata and savedata out of Bootup.pack into their logs.
+    /// Whatever else the mod changes in Bootup.pack is left for [`clean`].
+    ///
+    /// [`clean`]: Self::clean
+    fn bootup_logs(&self, root: &Path, logs: &Path) -> Result<()> {
+        let bootup = root.join(self.content).join("Pack/Bootup.pack");
+        if !bootup.exists() {
+            return Ok(());
+        }
+        let data = fs::read(&bootup)?;
+        let sarc = Sarc::new(&data)?;
+        let mut writer = SarcWriter::from_sarc(&sarc);
+        if let Some(gamedata) = sarc.get_data("GameData/gamedata.ssarc") {
+            let base = self
+                .dump
+                .get_bytes_from_sarc("Pack/Bootup.pack//GameData/gamedata.ssarc")?;
+            let base = GameDataPack::from_binary(decompress_if(&base))?;
+            let modded = GameDataPack::from_binary(decompress_if(gamedata))?;
+            let log = gamedata_log(&base, &modded);
+            if !log.is_empty() {
+                fs::write(logs.join("gamedata.yml"), Byml::Map(log).to_text())?;
+            }
+            writer
+                .files
+                .retain(|name, _| name != "GameData/gamedata.ssarc");
+        }
+        if let Some(savedata) = sarc.get_data("GameData/savedataformat.ssarc") {
+            let base = self
+                .dump
+                .get_bytes_from_sarc("Pack/Bootup.pack//GameData/savedataformat.ssarc")?;
+            let base = SaveDataPack::from_binary(decompress_if(&base))?;
+            let modded = SaveDataPack::from_binary(decompress_if(savedata))?;
+            if let Some(log) = savedata_log(&base, &modded) {
+                fs::write(logs.join("savedata.yml"), Byml::Map(log).to_text())?;
+            }
+            writer
+                .files
+                .retain(|name, _| name != "GameData/savedataformat.ssarc");
+        }
+        fs::write(&bootup, writer.to_binary())?;
+        Ok(())
+    }
+
+    fn actorinfo_log(&self, root: &Path, logs: &Path) -> Result<()> {
+        let path = root
+            .join(self.content)
+            .join("Actor/ActorInfo.product.sbyml");
+        if !path.exists() {
+            return Ok(());
+        }
+        let base = ActorInfo::from_binary(decompress_if(
+            &self
+                .dump
+                .get_bytes_uncached("Actor/ActorInfo.product.sbyml")?,
+        ))?;
+        let modded = ActorInfo::from_binary(decompress_if(&fs::read(&path)?))?;
+        // BCML merges actor info entry by entry, so every added or changed
+        // actor goes in the log whole.
+        let log = modded
+            .0
+            .iter()
+            .filter(|(hash, actor)| base.0.get(*hash) != Some(*actor))
+            .map(|(hash, actor)| (hash.to_string().into(), actor.clone()))
+            .collect::<Map>();
+        if !log.is_empty() {
+            fs::write(logs.join("actorinfo.yml"), Byml::Map(log).to_text())?;
+        }
+        fs::remove_file(path)?;
+        Ok(())
+    }
+
+    fn texts_log(&self, root: &Path, logs: &Path) -> Result<()> {
+        let path = root
+            .join(self.content)
+            .join(self.lang.bootup_path().as_str());
+        if !path.exists() {
+            return Ok(());
+        }
+        let message_path = self.lang.message_path();
+        let base =
+            MessagePack::from_binary(decompress_if(&self.dump.get_bytes_from_sarc(&format!(
+                "{}//{}",
+                self.lang.bootup_path(),
+                message_path
+            ))?))?;
+        let data = fs::read(&path)?;
+        let modded = MessagePack::from_binary(decompress_if(
+            Sarc::new(&data)?
+                .get_data(message_path.as_str())
+                .context("Mod language pack has no messages")?,
+        ))?;
+        let diff = base.diff(&modded);
+        if !diff.0.is_empty() {
+            let mut log = TextsLog::default();
+            log.insert(
+                self.lang,
+                diff.0
+                    .into_iter()
+                    .map(|(file, text)| {
+                        (
+                            format!("{file}.msyt").into(),
+                            text.entries
+                                .into_iter()
+                                .map(|(entry, value)| (entry.into(), value))
+                                .collect(),
+                        )
+                    })
+                    .collect(),
+            );
+            fs::write(logs.join("texts.json"), serde_json::to_string_pretty(&log)?)?;
+        }
+        fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// Strip every file in the content and DLC folders down to what the mod
+    /// changes. Files the same as in the dump are removed, SARCs keep only
+    /// the files they change, and changes to AAMP files the dump has are
+    /// moved to the deepmerge log. Returns the AAMP diffs and the SARCs BCML
+    /// has to fill back up from the game files, for `packs.json`.
+    fn clean(&self, root: &Path) -> Result<(AampDiffs, FxHashMap<String, String>)> {
+        let files = [(self.content, false), (self.aoc, true)]
+            .into_iter()
+            .filter(|(prefix, _)| root.join(prefix).exists())
+            .flat_map(|(prefix, aoc)| {
+                jwalk::WalkDir::new(root.join(prefix))
+                    .into_iter()
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.file_type().is_file())
+                    .map(move |e| (prefix, aoc, e.path()))
+            })
+            .collect::<Vec<_>>();
+        let cleaned = files
+            .into_par_iter()
+            .map(|(prefix, aoc, file)| {
+                let rel = file
+                    .strip_prefix(root.join(prefix))
+                    .expect("File should be in its root folder")
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                self.clean_file(&file, prefix, &rel, aoc)
+                    .with_context(|| format!("Failed to process {rel}"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut deepmerge = vec![];
+        let mut packs = FxHashMap::default();
+        for cleaned in cleaned {
+            deepmerge.extend(cleaned.deepmerge);
+            if let Some(pack) = cleaned.pack {
+                packs.insert(uk_content::canonicalize(&pack), pack.into());
+            }
+        }
+        Ok((deepmerge, packs))
+    }
+
+    fn clean_file(&self, file: &Path, prefix: &str, rel: &str, aoc: bool) -> Result<Cleaned> {
+        let mut cleaned = Cleaned::default();
+        let base = if aoc {
+            self.dump.get_aoc_bytes_uncached(rel)
+        } else {
+            self.dump.get_bytes_uncached(rel)
+        };
+        // Files the game does not have are new, so they stay as they are.
+        let Ok(base) = base else {
+            return Ok(cleaned);
+        };
+        let data = fs::read(file)?;
+        let (base, modded) = (decompress_if(&base), decompress_if(&data));
+        let bnp_path = format!("{prefix}/{rel}");
+        if base == modded {
+            fs::remove_file(file)?;
+        } else if let (Ok(base), Ok(modded)) = (Sarc::new(&*base), Sarc::new(&*modded)) {
+            match clean_sarc(&bnp_path, &modded, &base, 0, &mut cleaned.deepmerge) {
+                Some(mut writer) => {
+                    fs::write(file, compress_if(&writer.to_binary(), file))?;
+                    cleaned.pack = Some(bnp_path);
+                }
+                None => fs::remove_file(file)?,
+            }
+        } else if let Some(diff) = diff_aamp(&base, &modded) {
+            cleaned.deepmerge.push((bnp_path, diff));
+            fs::remove_file(file)?;
+        }
+        Ok(cleaned)
+    }
+}
+
+/// Remove the files of a SARC which are the same as in the game, recursing
+/// into nested SARCs. Changed AAMP files are moved to `deepmerge` where
+/// BCML's deepmerge log can reach them, which is in a SARC or in a SARC nested
+/// in one. Returns `None` if nothing is left.
+fn clean_sarc(
+    path: &str,
+    sarc: &Sarc,
+    base: &Sarc,
+    depth: usize,
+    deepmerge: &mut AampDiffs,
+) -> Option<SarcWriter> {
+    let mut writer = SarcWriter::from_sarc(sarc);
+    for file in sarc.files() {
+        let Some(name) = file.name() else {
+            continue;
+        };
+        let Some(base_data) = base.get_data(name) else {
+            continue;
+        };
+        let (base_data, data) = (decompress_if(base_data), decompress_if(file.data()));
+        let nested_path = format!("{path}//{name}");
+        if base_data == data {
+            writer.files.retain(|n, _| n != name);
+        } else if let (Ok(nested_base), Ok(nested)) = (Sarc::new(&*base_data), Sarc::new(&*data)) {
+            match clean_sarc(&nested_path, &nested, &nested_base, depth + 1, deepmerge) {
+                Some(mut nested) => {
+                    writer
+                        .files
+                        .insert(name.into(), compress_if(&nested.to_binary(), name).to_vec());
+                }
+                None => writer.files.retain(|n, _| n != name),
+            }
+        } else if depth < 2 {
+            if let Some(diff) = diff_aamp(&base_data, &data) {
+                deepmerge.push((nested_path, diff));
+                writer.files.retain(|n, _| n != name);
+            }
+        }
+    }
+    (!writer.files.is_empty()).then_some(writer)
+}
+
+fn diff_aamp(base: &[u8], modded: &[u8]) -> Option<ParameterList> {
+    if !(base.starts_with(b"AAMP") && modded.starts_with(b"AAMP")) {
+        return None;
+    }
+    let base = ParameterIO::from_binary(base).ok()?;
+    let modded = ParameterIO::from_binary(modded).ok()?;
+    Some(diff_plist(&base.param_root, &modded.param_root))
+}
+
+fn deepmerge_log(diffs: AampDiffs) -> ParameterIO {
+    let mut pio = ParameterIO::new();
+    pio.objects_mut().insert(
+        "FileTable",
+        diffs
+            .iter()
+            .enumerate()
+            .map(|(i, (file, _))| {
+                (
+                    format!("File{i}"),
+                    Parameter::String256(Box::new(file.as_str().into())),
+                )
+            })
+            .collect(),
+    );
+    for (file, diff) in diffs {
+        pio.lists_mut().insert(file.as_str(), diff);
+    }
+    pio
+}
+
+/// The BCML gamedata log: for each data type, the flags added or changed by
+/// name, and the names of the flags deleted.
+fn gamedata_log(base: &GameDataPack, modded: &GameDataPack) -> Map {
+    let types: [(&str, &[(&GameData, &GameData)]); 16] = [
+        ("bool_array_data", &[(
+            &base.bool_array_data,
+            &modded.bool_array_data,
+        )]),
+        ("bool_data", &[
+            (&base.bool_data, &modded.bool_data),
+            (&base.revival_bool_data, &modded.revival_bool_data),
+        ]),
+        ("f32_array_data", &[(
+            &base.f32_array_data,
+            &modded.f32_array_data,
+        )]),
+        ("f32_data", &[(&base.f32_data, &modded.f32_data)]),
+        ("s32_array_data", &[(
+            &base.s32_array_data,
+            &modded.s32_array_data,
+        )]),
+        ("s32_data", &[
+            (&base.s32_data, &modded.s32_data),
+            (&base.revival_s32_data, &modded.revival_s32_data),
+        ]),
+        ("string_data", &[(
+            &base.string32_data,
+            &modded.string32_data,
+        )]),
+        ("string64_array_data", &[(
+            &base.string64_array_data,
+            &modded.string64_array_data,
+        )]),
+        ("string64_data", &[(
+            &base.string64_data,
+            &modded.string64_data,
+        )]),
+        ("string256_array_data", &[(
+            &base.string256_array_data,
+            &modded.string256_array_data,
+        )]),
+        ("string256_data", &[(
+            &base.string256_data,
+            &modded.string256_data,
+        )]),
+        ("vector2f_array_data", &[(
+            &base.vector2f_array_data,
+            &modded.vector2f_array_data,
+        )]),
+        ("vector2f_data", &[(
+            &base.vector2f_data,
+            &modded.vector2f_data,
+        )]),
+        ("vector3f_array_data", &[(
+            &base.vector3f_array_data,
+            &modded.vector3f_array_data,
+        )]),
+        ("vector3f_data", &[(
+            &base.vector3f_data,
+            &modded.vector3f_data,
+        )]),
+        ("vector4f_data", &[(
+            &base.vector4f_data,
+            &modded.vector4f_data,
+        )]),
+    ];
+    let mut log = Map::default();
+    for (data_type, pairs) in types {
+        let mut add = Map::default();
+        let mut del = vec![];
+        for (base, modded) in pairs {
+            for (name, (flag, delete)) in base.flags.diff(&modded.flags).iter_full() {
+                if *delete {
+                    del.push(Byml::String(name.clone()));
+                } else {
+                    add.insert(name.clone(), flag.clone().into());
+                }
+            }
+        }
+        if !(add.is_empty() && del.is_empty()) {
+            let mut diff = Map::default();
+            diff.insert("add".into(), Byml::Map(add));
+            diff.insert("del".into(), Byml::Array(del));
+            log.insert(data_type.into(), Byml::Map(diff));
+        }
+    }
+    log
+}
+
+/// The BCML savedata log: the flags added to `game_data.sav`, and the hashes
+/// of the flags removed from it.
+fn savedata_log(base: &SaveDataPack, modded: &SaveDataPack) -> Option<Map> {
+    let (base, modded) = (base.0.get("game_data.sav")?, modded.0.get("game_data.sav")?);
+    let mut add = vec![];
+    let mut del = vec![];
+    for (flag, delete) in base.flags.diff(&modded.flags).iter_full() {
+        if *delete {
+            del.push(Byml::I32(flag.hash));
+        } else {
+            add.push(flag.clone().into());
+        }
+    }
+    if add.is_empty() && del.is_empty() {
+        return None;
+    }
+    let mut log = Map::default();
+    log.insert("add".into(), Byml::Array(add));
+    log.insert("del".into(), Byml::Array(del));
+    Some(log)
+}
+
+/// BCML identifies mods by their name and version, encoded as URL-safe
+/// base64.
+fn bcml_id(name: &str, version: &str) -> std::string::String {
+    const CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+    let data = format!("{name}=+={version}");
+    let mut id = std::string::String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.as_bytes().chunks(3) {
+        let bytes = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                id.push(CHARS[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                id.push('=');
+            }
+        }
+    }
+    id
+}
+
+/// The options which become BNP options: those not already applied to the
+/// base mod, except the rest of an exclusive group with one applied.
+fn bnp_options<'a>(meta: &'a Meta, applied: &'a [ModOption]) -> Vec<&'a ModOption> {
+    meta.options
+        .iter()
+        .filter(|group| {
+            !matches!(group, OptionGroup::Exclusive(_))
+                || !group.options().iter().any(|opt| applied.contains(opt))
+        })
+        .flat_map(|group| group.options())
+        .filter(|opt| !applied.contains(opt))
+        .collect()
+}
+
+fn bnp_option(option: &ModOption) -> serde_json::Value {
+    json!({
+        "name": option.name,
+        "desc": option.description,
+        "folder": option.path,
+        "requires": option.requires,
+    })
+}
+
+/// The BNP `info.json`. Options already applied to the base mod are left out,
+/// along with the rest of their exclusive groups. BCML has no groups for
+/// options which can be combined, so those all go in one list.
+fn info_json(meta: &Meta, endian: Endian, applied: &[ModOption]) -> serde_json::Value {
+    let mut multi = vec![];
+    let mut single = vec![];
+    for group in &meta.options {
+        match group {
+            OptionGroup::Multiple(group) => {
+                for option in group.options.iter().filter(|opt| !applied.contains(opt)) {
+                    let mut bnp = bnp_option(option);
+                    bnp["default"] = json!(group.defaults.contains(&option.path));
+                    multi.push(bnp);
+                }
+            }
+            OptionGroup::Exclusive(group) => {
+                if group.options.iter().any(|opt| applied.contains(opt)) {
+                    continue;
+                }
+                single.push(json!({
+                    "name": group.name,
+                    "desc": group.description,
+                    "required": group.required,
+                    "options": group.options.iter().map(bnp_option).collect::<Vec<_>>(),
+                }));
+            }
+        }
+    }
+    json!({
+        "name": meta.name,
+        "desc": meta.description,
+        "version": meta.version,
+        "url": meta.url.as_deref().unwrap_or_default(),
+        "image": "",
+        "id": bcml_id(&meta.name, &meta.version),
+        "platform": match endian {
+            Endian::Big => "wiiu",
+            Endian::Little => "switch",
+        },
+        "depends": [],
+        "showCompare": false,
+        "showConvert": false,
+        "options": { "multi": multi, "single": single },
+    })
+}
+
+fn rules_txt(meta: &Meta) -> std::string::String {
+    let one_line = |text: &str| text.replace(['\r', '\n'], " ");
+    [
+        "[Definition]".into(),
+        format!("titleIds = {RULES_TITLE_IDS}"),
+        format!("name = {}", one_line(&meta.name)),
+        format!(
+            "path = The Legend of Zelda: Breath of the Wild/Mods/{}",
+            one_line(&meta.name)
+        ),
+        format!("description = {}", one_line(&meta.description)),
+        "version = 7".into(),
+        std::string::String::new(),
+    ]
+    .join("\n")
+}
+
+/// Remove the files of an option's tree which are exactly the same in the
+/// base mod's tree, so the option only carries what it changes.
+fn strip_base_files(option_root: &Path, base_root: &Path) -> Result<()> {
+    for entry in jwalk::WalkDir::new(option_root)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+    {
+        let path = entry.path();
+        let rel = path.strip_prefix(option_root)?;
+        if rel.starts_with("logs") {
+            continue;
+        }
+        let base = base_root.join(rel);
+        if base.exists() && fs::read(&base)? == fs::read(&path)? {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Export a UKMM mod as a BNP which BCML can install, at `dest`, which is
+/// either the file to write or a folder to write it to under the mod's name.
+///
+/// The options `reader` was opened with are applied to the BNP's base files.
+/// Every other option of the mod becomes a BNP option. Each tree is built by
+/// merging the mod onto the dump in the same way as deploying it, and the
+/// changes BCML merges itself are written to its logs: gamedata, savedata,
+/// actor info, texts, AAMP files (as deepmerge), and SARCs (as `packs.json`).
+/// Other resources, like maps or event info, go in whole.
+pub fn export_bnp(core: &crate::core::Manager, reader: ModReader, dest: &Path) -> Result<PathBuf> {
+    let settings = core.settings();
+    let endian: Endian = settings.current_mode.into();
+    if let ModPlatform::Specific(platform) = &reader.meta.platform {
+        if *platform != endian {
+            anyhow_ext::bail!(
+                "{} is a {} mod, but UKMM is in {} mode",
+                reader.meta.name,
+                reader.meta.platform,
+                ModPlatform::Specific(endian)
+            );
+        }
+    }
+    let (content, aoc) = platform_prefixes(endian);
+    let exporter = BnpExporter {
+        dump: settings
+            .dump()
+            .context("No dump for current mode. Have you configured your settings?")?,
+        lang: settings
+            .platform_config()
+            .context("No config for current platform. Have you configured your settings?")?
+            .language,
+        endian,
+        content,
+        aoc,
+    };
+    drop(settings);
+    let meta = reader.meta.clone();
+    let applied = reader.options().to_vec();
+    let path = reader.path.clone();
+    let root = crate::util::get_temp_folder().clone();
+
+    log::info!("Building BNP files for {}…", meta.name);
+    exporter.build(reader, &root)?;
+    fs::write(
+        root.join("info.json"),
+        serde_json::to_string_pretty(&info_json(&meta, endian, &applied))?,
+    )?;
+    fs::write(root.join("rules.txt"), rules_txt(&meta))?;
+
+    for option in bnp_options(&meta, &applied) {
+        log::info!("Building BNP files for option {}…", option.name);
+        let mut options = applied.clone();
+        options.push(option.clone());
+        let options = meta.with_requirements(options)?;
+        let option_root = root.join("options").join(&option.path);
+        exporter
+            .build(ModReader::open(&path, options)?, &option_root)
+            .with_context(|| format!("Failed to build option {}", option.name))?;
+        strip_base_files(&option_root, &root)?;
+    }
+
+    let dest = if dest.is_dir() {
+        dest.join(sanitise_file_name::sanitise(&meta.name) + ".bnp")
+    } else {
+        dest.to_path_buf()
+    };
+    log::info!("Compressing BNP…");
+    sevenz_rust::compress_to_path(&root, &dest).context("Failed to compress BNP")?;
+    log::info!("Exported {} to {}", meta.name, dest.display());
+    Ok(dest)
+}
+
+#[cfg(test)]
+#[test]
+fn id_encoding() {
+    assert_eq!(bcml_id("Mod", "1.0"), "TW9kPSs9MS4w");
+    assert_eq!(bcml_id("A", "1"), "QT0rPTE=");
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+fn test_flag(name: &str, value: i32) -> uk_content::data::gamedata::FlagData {
+    let flag = Byml::Map(
+        [
+            ("DataName", Byml::String(name.into())),
+            ("DeleteRev", Byml::I32(-1)),
+            ("HashValue", Byml::I32(roead::aamp::hash_name(name) as i32)),
+            ("InitValue", Byml::I32(value)),
+            ("IsEventAssociated", Byml::Bool(false)),
+            ("IsOneTrigger", Byml::Bool(false)),
+            ("IsProgramReadable", Byml::Bool(true)),
+            ("IsProgramWritable", Byml::Bool(true)),
+            ("IsSave", Byml::Bool(true)),
+            ("MaxValue", Byml::I32(100)),
+            ("MinValue", Byml::I32(0)),
+            ("ResetType", Byml::I32(0)),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.into(), value))
+        .collect(),
+    );
+    (&flag).try_into().unwrap()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[test]
+fn gamedata_round_trip() {
+    let mut base = GameDataPack::default();
+    base.s32_data.flags = [
+        ("Kept".into(), test_flag("Kept", 0)),
+        ("Removed".into(), test_flag("Removed", 0)),
+    ]
+    .into_iter()
+    .collect();
+    let mut modded = GameDataPack::default();
+    modded.s32_data.flags = [
+        ("Kept".into(), test_flag("Kept", 5)),
+        ("Added".into(), test_flag("Added", 1)),
+    ]
+    .into_iter()
+    .collect();
+
+    let log = gamedata_log(&base, &modded);
+    let log = Byml::from_text(Byml::Map(log).to_text())
+        .unwrap()
+        .into_map()
+        .unwrap();
+    assert_eq!(log.len(), 1);
+    let mut merged = base.clone();
+    super::gamedata::apply_gamedata_log(&mut merged, &log).unwrap();
+    assert_eq!(merged, modded);
+    assert!(gamedata_log(&base, &base).is_empty());
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[test]
+fn savedata_round_trip() {
+    use uk_content::data::savedata::{Flag, SaveData};
+    let pack = |flags: &[&str]| {
+        SaveDataPack(
+            [("game_data.sav".into(), SaveData {
+                header: Default::default(),
+                flags:  flags.iter().map(|name| Flag::from(*name)).collect(),
+            })]
+            .into_iter()
+            .collect(),
+        )
+    };
+    let base = pack(&["Kept", "Removed"]);
+    let modded = pack(&["Kept", "Added"]);
+
+    let log = savedata_log(&base, &modded).unwrap();
+    let log = Byml::from_text(Byml::Map(log).to_text())
+        .unwrap()
+        .into_map()
+        .unwrap();
+    let mut merged = base.clone();
+    super::savedata::apply_savedata_log(&mut merged, log).unwrap();
+    assert_eq!(merged, modded);
+    assert!(savedata_log(&base, &base).is_none());
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[test]
+fn deepmerge_round_trip() {
+    let aamp = |value: i32| {
+        let mut pio = ParameterIO::new();
+        pio.objects_mut().insert(
+            "Test",
+            [("Value", Parameter::I32(value))].into_iter().collect(),
+        );
+        pio.to_binary()
+    };
+    let sarc = |files: Vec<(&str, Vec<u8>)>| {
+        let mut writer = SarcWriter::new(roead::Endian::Big);
+        for (name, data) in files {
+            writer.files.insert(name.into(), data);
+        }
+        writer.to_binary()
+    };
+    let base = sarc(vec![
+        ("Actor/AIProgram/Test.baiprog", aamp(1)),
+        ("Actor/Same.bxml", aamp(1)),
+        ("Actor/Other.bin", b"base".to_vec()),
+        (
+            "Nested.pack",
+            sarc(vec![("Actor/Physics/Test.bphysics", aamp(1))]),
+        ),
+    ]);
+    let modded = sarc(vec![
+        ("Actor/AIProgram/Test.baiprog", aamp(2)),
+        ("Actor/Same.bxml", aamp(1)),
+        ("Actor/Other.bin", b"modded".to_vec()),
+        ("Actor/New.bin", b"new".to_vec()),
+        (
+            "Nested.pack",
+            sarc(vec![("Actor/Physics/Test.bphysics", aamp(3))]),
+        ),
+    ]);
+    let (base, modded) = (Sarc::new(&base).unwrap(), Sarc::new(&modded).unwrap());
+
+    let mut diffs = vec![];
+    let cleaned = clean_sarc("content/Pack/Test.pack", &modded, &base, 0, &mut diffs).unwrap();
+    let mut kept = cleaned
+        .files
+        .keys()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    kept.sort();
+    assert_eq!(kept, ["Actor/New.bin", "Actor/Other.bin"]);
+    let mut paths = diffs
+        .iter()
+        .map(|(path, _)| path.as_str())
+        .collect::<Vec<_>>();
+    paths.sort();
+    assert_eq!(paths, [
+        "content/Pack/Test.pack//Actor/AIProgram/Test.baiprog",
+        "content/Pack/Test.pack//Nested.pack//Actor/Physics/Test.bphysics",
+    ]);
+
+    let log = ParameterIO::from_binary(deepmerge_log(diffs).to_binary()).unwrap();
+    let parsed = super::parse_aamp_diff("FileTable", &log).unwrap();
+    let Some(super::AampDiffEntry::Sarc(entries)) = parsed.get("content/Pack/Test.pack") else {
+        panic!("Deepmerge log should have an entry for the pack");
+    };
+    let mut merged = SarcWriter::from_sarc(&base);
+    for (nest_root, contents) in entries {
+        super::deepmerge::handle_diff_entry(&mut merged, nest_root, contents).unwrap();
+    }
+    let merged = merged.to_binary();
+    let merged = Sarc::new(&merged).unwrap();
+    let root = |sarc: &Sarc, name: &str| {
+        ParameterIO::from_binary(sarc.get_data(name).unwrap())
+            .unwrap()
+            .param_root
+    };
+    assert_eq!(
+        root(&merged, "Actor/AIProgram/Test.baiprog"),
+        root(&modded, "Actor/AIProgram/Test.baiprog")
+    );
+    let nested = |sarc: &Sarc| {
+        Sarc::new(decompress_if(sarc.get_data("Nested.pack").unwrap()).to_vec()).unwrap()
+    };
+    assert_eq!(
+        root(&nested(&merged), "Actor/Physics/Test.bphysics"),
+        root(&nested(&modded), "Actor/Physics/Test.bphysics")
+    );
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[test]
+fn info_round_trip() {
+    use uk_mod::{ExclusiveOptionGroup, ModCategory, MultipleOptionGroup};
+    let option = |path: &str, requires: &[&str]| {
+        ModOption {
+            name: path.to_uppercase().into(),
+            description: format!("The {path} option").into(),
+            path: path.into(),
+            requires: requires.iter().map(PathBuf::from).collect(),
+        }
+    };
+    let exclusive = |name: &str, options: Vec<ModOption>| {
+        OptionGroup::Exclusive(ExclusiveOptionGroup {
+            name: name.into(),
+            description: format!("Pick a {name}").into(),
+            required: true,
+            default: None,
+            options,
+        })
+    };
+    let meta = Meta {
+        api: env!("CARGO_PKG_VERSION").into(),
+        name: "Test Mod".into(),
+        version: "1.2.0".into(),
+        author: "Someone".into(),
+        category: ModCategory::Other,
+        description: "A mod for testing".into(),
+        platform: ModPlatform::Specific(Endian::Big),
+        url: None,
+        options: vec![
+            OptionGroup::Multiple(MultipleOptionGroup {
+                name: "Extras".into(),
+                description: "Anything else".into(),
+                required: false,
+                defaults: [PathBuf::from("more")].into_iter().collect(),
+                options: vec![option("extra", &[]), option("more", &["extra"])],
+            }),
+            exclusive("Difficulty", vec![option("easy", &[]), option("hard", &[])]),
+            exclusive("Colour", vec![option("red", &[]), option("blue", &[])]),
+        ],
+        masters: Default::default(),
+        load_after: vec![],
+        load_before: vec![],
+    };
+    let applied = [option("red", &[])];
+    assert_eq!(
+        bnp_options(&meta, &applied)
+            .into_iter()
+            .map(|opt| opt.path.to_str().unwrap())
+            .collect::<Vec<_>>(),
+        ["extra", "more", "easy", "hard"]
+    );
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("info.json");
+    fs::write(
+        &path,
+        serde_json::to_string_pretty(&info_json(&meta, Endian::Big, &applied)).unwrap(),
+    )
+    .unwrap();
+    let parsed = uk_mod::pack::ModPacker::parse_info(path).unwrap();
+    assert_eq!(parsed.name, meta.name);
+    assert_eq!(parsed.version, meta.version);
+    assert_eq!(parsed.description, meta.description);
+    assert_eq!(parsed.platform, meta.platform);
+    assert_eq!(parsed.options.len(), 2);
+    let OptionGroup::Multiple(multi) = &parsed.options[0] else {
+        panic!("Combinable options should be one multiple group");
+    };
+    assert_eq!(&multi.options, meta.options[0].options());
+    assert_eq!(multi.defaults.len(), 1);
+    assert!(multi.defaults.contains(Path::new("more")));
+    assert_eq!(parsed.options[1].name(), "Difficulty");
+    assert_eq!(parsed.options[1].options(), meta.options[1].options());
+}
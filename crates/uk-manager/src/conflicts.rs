@@ -0,0 +1,241 @@
+use std::{
+    collections::{hash_map::Entry, BTreeMap},
+    path::Path,
+};
+
+use anyhow_ext::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DisplayFromStr};
+use smartstring::alias::String;
+use uk_content::resource::{MergeableResource, ResourceData};
+use uk_mod::unpack::ModReader;
+
+use crate::{mods, util::HashMap};
+
+/// How the versions of a resource supplied by several mods are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConflictKind {
+    /// At least one mod supplies the file as raw binary data, so some mod's
+    /// changes are discarded entirely.
+    Overwrite,
+    /// Every mod supplies a mergeable diff (or SARC file list), so all of
+    /// their changes are merged, with later mods taking priority where they
+    /// edit the same values.
+    Merged,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConflictMod {
+    #[serde_as(as = "DisplayFromStr")]
+    pub hash: usize,
+    pub name: String,
+}
+
+impl From<&mods::Mod> for ConflictMod {
+    fn from(mod_: &mods::Mod) -> Self {
+        Self {
+            hash: mod_.hash(),
+            name: mod_.meta.name.clone(),
+        }
+    }
+}
+
+/// A single canonical resource touched by more than one enabled mod.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileConflict {
+    /// Canonical resource path, with DLC files prefixed by `Aoc/0010/`.
+    pub path:   String,
+    pub kind:   ConflictKind,
+    /// Every enabled mod which touches the resource, in load order.
+    pub mods:   Vec<ConflictMod>,
+    /// The mod whose version ends up in the merged output, as deploying
+    /// decides it: the last mod for a file UKMM cannot merge, otherwise the
+    /// first mod to override the file with raw data, or else the last mod to
+    /// merge it.
+    pub winner: ConflictMod,
+}
+
+impl FileConflict {
+    #[inline]
+    pub fn is_overwrite(&self) -> bool {
+        self.kind == ConflictKind::Overwrite
+    }
+}
+
+/// Report of every resource touched by more than one enabled mod in the
+/// current profile, sorted by canonical path.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConflictReport {
+    pub conflicts: Vec<FileConflict>,
+}
+
+impl ConflictReport {
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.conflicts.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &FileConflict> {
+        self.conflicts.iter()
+    }
+
+    /// Iterate only the conflicts in which some mod's changes are discarded
+    /// entirely.
+    pub fn overwrites(&self) -> impl Iterator<Item = &FileConflict> {
+        self.conflicts.iter().filter(|c| c.is_overwrite())
+    }
+
+    /// Iterate the conflicts involving the mod with the given hash.
+    pub fn for_mod(&self, hash: usize) -> impl Iterator<Item = &FileConflict> {
+        self.conflicts
+            .iter()
+            .filter(move |c| c.mods.iter().any(|m| m.hash == hash))
+    }
+}
+
+/// How a mod supplies its version of a resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Supply {
+    /// A diff or SARC file list, merged with the other mods' versions.
+    Merged,
+    /// Raw data for a file UKMM cannot merge.
+    Binary,
+    /// Raw data for a file UKMM can usually merge, but could not parse in this
+    /// mod, so it replaces the whole file.
+    Override,
+}
+
+/// How the mod supplies the resource, taking the strongest of its versions.
+fn supply(reader: &ModReader, path: &str) -> Result<Supply> {
+    let versions = match reader.get_versions(Path::new(path)) {
+        Ok(versions) => versions,
+        Err(e) => {
+            // Text and other specially handled files are not stored as
+            // ordinary resources, so treat them as merged.
+            log::debug!("Could not read {} from mod {}: {e}", path, reader.meta.name);
+            return Ok(Supply::Merged);
+        }
+    };
+    let mut supply = Supply::Merged;
+    for data in versions {
+        let res: ResourceData = minicbor_ser::from_slice(&data).with_context(|| {
+            format!(
+                "Error deserializing resource {} from mod {}",
+                path, reader.meta.name
+            )
+        })?;
+        match res {
+            ResourceData::Mergeable(MergeableResource::BinaryOverride(_)) => {
+                return Ok(Supply::Override);
+            }
+            ResourceData::Binary(_) => supply = Supply::Binary,
+            _ => (),
+        }
+    }
+    Ok(supply)
+}
+
+/// Work out how a resource is combined and which mod wins, from how each mod
+/// supplies it in load order, the same way deploying does. If any mod merges
+/// or overrides the file, it is a mergeable resource: raw data for it is
+/// skipped, and the first override replaces everything merged before it and
+/// ignores everything after. Otherwise the last mod's version wins.
+fn resolve(supplies: &[(usize, Supply)]) -> (ConflictKind, usize) {
+    let last = supplies.last().expect("Conflict must involve mods").0;
+    if supplies.iter().all(|(_, s)| *s == Supply::Binary) {
+        return (ConflictKind::Overwrite, last);
+    }
+    if let Some(&(i, _)) = supplies.iter().find(|(_, s)| *s == Supply::Override) {
+        return (ConflictKind::Overwrite, i);
+    }
+    let winner = supplies
+        .iter()
+        .rev()
+        .find(|(_, s)| *s == Supply::Merged)
+        .map(|(i, _)| *i)
+        .unwrap_or(last);
+    if supplies.iter().any(|(_, s)| *s == Supply::Binary) {
+        (ConflictKind::Overwrite, winner)
+    } else {
+        (ConflictKind::Merged, winner)
+    }
+}
+
+/// Analyze the enabled mods of the current profile for resources which more
+/// than one of them touches.
+pub fn analyze(manager: &mods::Manager) -> Result<ConflictReport> {
+    log::info!("Analyzing mod conflicts for current profile");
+    let mods = manager.mods().collect::<Vec<_>>();
+    let mut touched: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (i, mod_) in mods.iter().enumerate() {
+        let manifest = mod_
+            .manifest()
+            .with_context(|| format!("Failed to load manifest for mod {}", mod_.meta.name))?;
+        for file in manifest.resources() {
+            touched.entry(file).or_default().push(i);
+        }
+    }
+    let mut readers: HashMap<usize, ModReader> = HashMap::default();
+    let mut conflicts = vec![];
+    for (path, indices) in touched.into_iter().filter(|(_, m)| m.len() > 1) {
+        log::trace!("{} is modified by {} mods", path, indices.len());
+        let mut supplies = Vec::with_capacity(indices.len());
+        for &i in &indices {
+            let mod_ = &mods[i];
+            let reader = match readers.entry(i) {
+                Entry::Occupied(e) => e.into_mut(),
+                Entry::Vacant(e) => {
+                    e.insert(
+                        ModReader::open_peek(&mod_.path, mod_.enabled_options.clone())
+                            .with_context(|| format!("Failed to open mod {}", mod_.meta.name))?,
+                    )
+                }
+            };
+            supplies.push((i, supply(reader, &path)?));
+        }
+        let (kind, winner) = resolve(&supplies);
+        conflicts.push(FileConflict {
+            path,
+            kind,
+            mods: indices.iter().map(|&i| (&mods[i]).into()).collect(),
+            winner: (&mods[winner]).into(),
+        });
+    }
+    log::info!("Found {} conflicting resources", conflicts.len());
+    Ok(ConflictReport { conflicts })
+}
+
+#[cfg(test)]
+#[test]
+fn conflict_winners() {
+    use Supply::*;
+    assert_eq!(
+        resolve(&[(0, Merged), (1, Merged)]),
+        (ConflictKind::Merged, 1)
+    );
+    assert_eq!(
+        resolve(&[(0, Binary), (1, Binary)]),
+        (ConflictKind::Overwrite, 1)
+    );
+    // Raw data for a file other mods merge is skipped, so the mergeable mod
+    // after it wins
+    assert_eq!(
+        resolve(&[(0, Binary), (1, Merged)]),
+        (ConflictKind::Overwrite, 1)
+    );
+    assert_eq!(
+        resolve(&[(0, Merged), (1, Binary), (2, Merged), (3, Binary)]),
+        (ConflictKind::Overwrite, 2)
+    );
+    // An override replaces what was merged before it and ignores the rest
+    assert_eq!(
+        resolve(&[(0, Merged), (1, Override), (2, Merged), (3, Override)]),
+        (ConflictKind::Overwrite, 1)
+    );
+}
@@ -1,7 +1,8 @@
 #![allow(clippy::unwrap_used, unstable_name_collisions)]
 
 use std::{
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
+    io::{BufReader, Write},
     path::{Path, PathBuf},
     sync::{Arc, Weak},
 };
@@ -19,28 +20,183 @@ use serde::{Deserialize, Serialize};
 use smartstring::alias::String;
 use uk_content::{constants::Language, platform_prefixes};
 use uk_mod::{
-    unpack::{ModReader, ModUnpacker},
+    unpack::{MapIssue, ModReader, ModUnpacker},
     Manifest,
 };
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
 
+use self::journal::Journal;
+pub use self::plan::{DeployPlan, RootPlan, RstbChange, TargetPlan};
 use crate::{
     mods,
-    settings::{DeployMethod, Platform, Settings},
+    settings::{DeployConfig, DeployLayout, DeployMethod, Platform, Settings},
     util,
 };
 
+mod journal;
+mod plan;
+mod rules;
+
+static RSTB_PATH: &str = "System/Resource/ResourceSizeTable.product.srsizetable";
+/// The hash function used by [`hash_file`].
+static HASH_ALGORITHM: &str = "xxh3-64";
+
+/// Content hashes of the files in the output folder as of the last
+/// deployment, recorded when the content hash deploy option is enabled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FileHashes {
+    /// The hash function the hashes were made with. Hashes logged before it
+    /// was recorded were made with one which is not stable between builds.
+    #[serde(default)]
+    algorithm:     String,
+    content_files: BTreeMap<String, u64>,
+    aoc_files:     BTreeMap<String, u64>,
+}
+
+impl Default for FileHashes {
+    fn default() -> Self {
+        Self {
+            algorithm:     HASH_ALGORITHM.into(),
+            content_files: Default::default(),
+            aoc_files:     Default::default(),
+        }
+    }
+}
+
+impl FileHashes {
+    #[inline]
+    fn clear(&mut self) {
+        self.content_files.clear();
+        self.aoc_files.clear();
+    }
+}
+
+/// Changes waiting to be deployed to one deploy target.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct TargetState {
+    files: Manifest,
+    delete: Manifest,
+    #[serde(default)]
+    deployed: FileHashes,
+    /// Every file UKMM has deployed to the output folder and not removed
+    /// since, to tell them apart from game files put there by anything else.
+    #[serde(default)]
+    managed: Manifest,
+    /// The user confirmed deploying to an SD root which already had game
+    /// files UKMM did not deploy.
+    #[serde(default)]
+    allow_unmanaged: bool,
+}
+
+impl TargetState {
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.delete.is_empty()
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.files.content_files.len()
+            + self.files.aoc_files.len()
+            + self.delete.content_files.len()
+            + self.delete.aoc_files.len()
+    }
+}
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 struct PendingLog {
-    files:  Manifest,
-    delete: Manifest,
+    /// Pending changes for each deploy target, by name.
+    #[serde(default)]
+    targets:  BTreeMap<String, TargetState>,
+    // Pending changes logged before there could be more than one deploy
+    // target, which now belong to the default target.
+    #[serde(default, skip_serializing)]
+    files:    Manifest,
+    #[serde(default, skip_serializing)]
+    delete:   Manifest,
+    #[serde(default, skip_serializing)]
+    deployed: FileHashes,
 }
 
 #[derive(Debug)]
 pub struct Manager {
-    settings: Weak<RwLock<Settings>>,
+    settings:    Weak<RwLock<Settings>>,
     mod_manager: Weak<RwLock<mods::Manager>>,
-    pending_files: RwLock<Manifest>,
-    pending_delete: RwLock<Manifest>,
+    targets:     RwLock<BTreeMap<String, TargetState>>,
+}
+
+/// Hash the contents of a file. The hashes are stored between runs, so the
+/// hash function has to give the same result in every build.
+fn hash_file(path: &Path) -> Result<u64> {
+    Ok(twox_hash::xxh3::hash64(&fs::read(path)?))
+}
+
+/// Whether two files differ, checking their sizes before hashing them.
+fn contents_differ(a: &Path, b: &Path) -> bool {
+    match (a.metadata(), b.metadata()) {
+        (Ok(a_meta), Ok(b_meta)) if a_meta.len() == b_meta.len() => {
+            !matches!((hash_file(a), hash_file(b)), (Ok(a), Ok(b)) if a == b)
+        }
+        _ => true,
+    }
+}
+
+fn load_rstb(table_path: &Path, platform: Platform) -> Result<ResourceSizeTable> {
+    if table_path.exists() {
+        log::debug!("Updating existing merged RSTB");
+        Ok(ResourceSizeTable::from_binary(
+            decompress(fs::read(table_path).context("Failed to open merged RSTB")?)
+                .context("Failed to decompress merged RSTB")?,
+        )
+        .context("Failed to parse merged RSTB")?)
+    } else {
+        log::debug!("Creating new RSTB");
+        Ok(ResourceSizeTable::new_from_stock(platform.into()))
+    }
+}
+
+/// Apply the RSTB values from a merge to the table, returning every entry
+/// which actually changed.
+fn update_rstb(
+    table: &mut ResourceSizeTable,
+    updates: DashMap<String, Option<u32>>,
+) -> BTreeMap<String, RstbChange> {
+    updates
+        .into_iter()
+        .filter_map(|(canon, size)| {
+            let old = table.get(canon.as_str());
+            match size {
+                Some(size) if old.map(|s| s < size).unwrap_or(true) => {
+                    table.set(canon.as_str(), size);
+                    Some((canon, RstbChange {
+                        old,
+                        new: Some(size),
+                    }))
+                }
+                None if old.is_some() => {
+                    table.remove(canon.as_str());
+                    Some((canon, RstbChange { old, new: None }))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Files in the change manifest which none of the mods supply anymore.
+fn find_orphans(total_manifest: &Manifest, manifest: &Manifest) -> Manifest {
+    Manifest {
+        content_files: manifest
+            .content_files
+            .difference(&total_manifest.content_files)
+            .cloned()
+            .collect(),
+        aoc_files:     manifest
+            .aoc_files
+            .difference(&total_manifest.aoc_files)
+            .cloned()
+            .collect(),
+    }
 }
 
 impl Manager {
@@ -49,157 +205,357 @@ impl Manager {
         settings.platform_dir().join("pending.yml")
     }
 
+    #[inline(always)]
+    fn journal_dir(settings: &Settings, target: &str) -> PathBuf {
+        settings
+            .platform_dir()
+            .join("deploy_journal")
+            .join(uk_mod::pack::sanitise(target))
+    }
+
+    #[inline(always)]
+    fn deploy_targets(settings: &Settings) -> &[DeployConfig] {
+        settings
+            .platform_config()
+            .map(|c| c.deploy_targets.as_slice())
+            .unwrap_or_default()
+    }
+
     pub fn init(
         settings: &Arc<RwLock<Settings>>,
         mod_manager: &Arc<RwLock<mods::Manager>>,
     ) -> Result<Self> {
         log::info!("Initializing deployment manager");
-        let pending = match fs::read_to_string(Self::log_path(&settings.read()))
+        let settings_ = settings.read();
+        let pending = match fs::read_to_string(Self::log_path(&settings_))
             .map_err(anyhow_ext::Error::from)
             .and_then(|text| Ok(serde_yaml::from_str::<PendingLog>(&text)?))
         {
-            Ok(log) => {
-                if !log.files.is_empty() || !log.delete.is_empty() {
-                    log::info!("Pending deployment data found");
-                    log::debug!("{:#?}", &log);
-                } else {
-                    log::info!("No files pending deployment");
-                }
-                log
-            }
+            Ok(log) => log,
             Err(e) => {
                 log::warn!("Could not load pending deployment data:\n{}", &e);
-                log::info!("No files pending deployment");
                 Default::default()
             }
         };
+        let PendingLog {
+            mut targets,
+            files,
+            delete,
+            deployed,
+        } = pending;
+        let configured = Self::deploy_targets(&settings_);
+        if let Some(default) = configured.first() {
+            if !targets.contains_key(&default.name) {
+                targets.insert(default.name.clone(), TargetState {
+                    files,
+                    delete,
+                    deployed,
+                    ..Default::default()
+                });
+            }
+        }
+        targets.retain(|name, _| configured.iter().any(|c| &c.name == name));
+        for (name, state) in targets.iter_mut() {
+            if state.deployed.algorithm != HASH_ALGORITHM {
+                if !state.deployed.content_files.is_empty() || !state.deployed.aoc_files.is_empty()
+                {
+                    log::info!(
+                        "Discarding file hashes for {name} made with an old hash function, so \
+                         changes made outside UKMM cannot be detected until the next deploy"
+                    );
+                }
+                state.deployed = FileHashes::default();
+            }
+        }
+        if targets.values().any(|t| !t.is_empty()) {
+            log::info!("Pending deployment data found");
+            log::debug!("{:#?}", &targets);
+        } else {
+            log::info!("No files pending deployment");
+        }
+        for config in configured {
+            if Journal::exists(&Self::journal_dir(&settings_, &config.name)) {
+                log::warn!(
+                    "The last deployment to {} did not finish. It will be rolled back before \
+                     deploying again.",
+                    config.name
+                );
+            }
+        }
+        drop(settings_);
         Ok(Self {
-            settings: Arc::downgrade(settings),
+            settings:    Arc::downgrade(settings),
             mod_manager: Arc::downgrade(mod_manager),
-            pending_files: RwLock::new(pending.files),
-            pending_delete: RwLock::new(pending.delete),
+            targets:     RwLock::new(targets),
         })
     }
 
+    /// Whether any deploy target has changes waiting to be deployed.
     #[inline]
     pub fn pending(&self) -> bool {
-        !(self.pending_delete.read().is_empty() && self.pending_files.read().is_empty())
+        self.targets.read().values().any(|t| !t.is_empty())
+    }
+
+    /// Whether the named deploy target has changes waiting to be deployed.
+    #[inline]
+    pub fn target_pending(&self, target: &str) -> bool {
+        self.targets
+            .read()
+            .get(target)
+            .map(|t| !t.is_empty())
+            .unwrap_or(false)
     }
 
+    /// Number of files waiting to be deployed, summed over every deploy
+    /// target.
     #[inline]
     pub fn pending_len(&self) -> usize {
-        let dels = self.pending_delete.read();
-        let files = self.pending_files.read();
-        dels.content_files.len()
-            + dels.aoc_files.len()
-            + files.content_files.len()
-            + files.aoc_files.len()
+        self.targets.read().values().map(TargetState::len).sum()
+    }
+
+    /// Add changed files to the pending changes of every deploy target.
+    fn add_pending(&self, settings: &Settings, files: &Manifest, delete: &Manifest) {
+        let mut targets = self.targets.write();
+        for config in Self::deploy_targets(settings) {
+            let state = targets.entry(config.name.clone()).or_default();
+            state.files.extend(files);
+            state.delete.extend(delete);
+        }
+    }
+
+    /// Whether the last deployment to any target failed or was interrupted
+    /// without being rolled back, which may leave its output folder only
+    /// partly deployed.
+    pub fn interrupted(&self) -> bool {
+        let settings = self.settings.upgrade().unwrap();
+        let settings = settings.read();
+        Self::deploy_targets(&settings)
+            .iter()
+            .any(|c| Journal::exists(&Self::journal_dir(&settings, &c.name)))
+    }
+
+    /// Undo any deployments which failed or were interrupted, restoring each
+    /// output folder to the last successful deployment. The pending changes
+    /// are kept so they can be deployed again. Returns whether there was
+    /// anything to roll back.
+    pub fn rollback(&self) -> Result<bool> {
+        let settings = self.settings.upgrade().unwrap();
+        let settings = settings.read();
+        let mut rolled_back = false;
+        for config in Self::deploy_targets(&settings) {
+            if let Some(journal) = Journal::open(&Self::journal_dir(&settings, &config.name))? {
+                journal.rollback()?;
+                log::info!("Rolled back unfinished deployment to {}", config.name);
+                rolled_back = true;
+            }
+        }
+        if !rolled_back {
+            log::info!("No unfinished deployment to roll back");
+        }
+        Ok(rolled_back)
     }
 
     pub fn reset_pending(&self) -> Result<()> {
-        self.pending_delete.write().clear();
-        self.pending_files.write().clear();
         let settings = self
             .settings
             .upgrade()
             .expect("YIKES the settings manager is gone");
         let settings = settings.read();
-        let source = settings.merged_dir();
+        let configs = Self::deploy_targets(&settings);
+        if configs.is_empty() {
+            anyhow_ext::bail!("No deployment config for current platform");
+        }
+        let mut targets = self.targets.write();
+        let mut old = std::mem::take(&mut *targets);
+        for config in configs {
+            let mut state = pending_changes(&settings, config);
+            let old_state = old.remove(&config.name).unwrap_or_default();
+            state.allow_unmanaged = old_state.allow_unmanaged;
+            state.managed = old_state.managed;
+            if config.content_hash
+                && !matches!(config.method, DeployMethod::Symlink | DeployMethod::Archive)
+            {
+                state.deployed = old_state.deployed;
+                let (dest_content, dest_aoc) =
+                    config.final_output_paths(settings.current_mode.into());
+                let modified = modified_since_deploy(&state.deployed, &dest_content, &dest_aoc);
+                for file in modified.content_files.iter().chain(&modified.aoc_files) {
+                    log::warn!(
+                        "File {file} deployed to {} was changed outside UKMM since the last deploy",
+                        config.name
+                    );
+                }
+            }
+            targets.insert(config.name.clone(), state);
+        }
+        Ok(())
+    }
+
+    /// Find files in the named target's output folder which were changed or
+    /// removed since they were last deployed. Only files deployed with the
+    /// content hash option enabled can be checked.
+    pub fn externally_modified(&self, target: &str) -> Result<Manifest> {
+        let settings = self
+            .settings
+            .upgrade()
+            .expect("YIKES the settings manager is gone");
+        let settings = settings.read();
         let config = settings
             .platform_config()
-            .and_then(|c| c.deploy_config.as_ref())
-            .context("No deployment config for current platform")?;
-        let dest = &config.output;
-        let (content, aoc) = platform_prefixes(settings.current_mode.into());
-
-        let collect_files = |root: &str| -> BTreeSet<String> {
-            let source = source.join(root);
-            let dest = dest.join(root);
-            jwalk::WalkDir::new(&source)
-                .into_iter()
-                .filter_map(|file| {
-                    file.ok().and_then(|file| {
-                        file.metadata().ok().and_then(|meta| {
-                            let path = file.path();
-                            let rel = path.strip_prefix(&source).unwrap();
-                            let dest = dest.join(rel);
-                            if !dest.is_dir() && (!dest.exists()
-                                || dest.metadata().ok()?.modified().ok()? < meta.modified().ok()?)
-                            {
-                                Some(rel.to_slash_lossy().into())
-                            } else {
-                                None
-                            }
-                        })
-                    })
-                })
-                .collect()
-        };
-
-        *self.pending_files.write() = Manifest {
-            content_files: collect_files(content),
-            aoc_files:     collect_files(aoc),
-        };
-
-        let collect_deletes = |root: &str| -> BTreeSet<String> {
-            let source = source.join(root);
-            let dest = dest.join(root);
-            jwalk::WalkDir::new(&source)
-                .into_iter()
-                .filter_map(|file| {
-                    file.ok().and_then(|file| {
-                        let path = file.path();
-                        let rel = path.strip_prefix(&source).unwrap();
-                        let dest = dest.join(rel);
-                        (dest.exists() && !dest.is_dir() && !path.exists())
-                            .then_some(rel.to_slash_lossy().into())
-                    })
-                })
-                .collect()
-        };
-
-        *self.pending_delete.write() = Manifest {
-            content_files: collect_deletes(content),
-            aoc_files:     collect_deletes(aoc),
-        };
-
-        Ok(())
+            .and_then(|c| c.deploy_target(target))
+            .with_context(|| format!("No deploy target named {target}"))?;
+        let (dest_content, dest_aoc) = config.final_output_paths(settings.current_mode.into());
+        let targets = self.targets.read();
+        Ok(targets
+            .get(target)
+            .map(|state| modified_since_deploy(&state.deployed, &dest_content, &dest_aoc))
+            .unwrap_or_default())
     }
 
     fn save(&self) -> Result<()> {
         fs::write(
             Self::log_path(&self.settings.upgrade().unwrap().read()),
             serde_yaml::to_string(&PendingLog {
-                delete: self.pending_delete.read().clone(),
-                files:  self.pending_files.read().clone(),
+                targets: self.targets.read().clone(),
+                ..Default::default()
             })?,
         )?;
         Ok(())
     }
 
+    /// Find game files in the named target's SD root which UKMM did not
+    /// deploy, and which would block deploying to it until allowed with
+    /// [`Manager::allow_unmanaged`].
+    pub fn unmanaged_files(&self, target: &str) -> Result<Manifest> {
+        let settings = self
+            .settings
+            .upgrade()
+            .expect("YIKES the settings manager is gone");
+        let settings = settings.read();
+        let config = settings
+            .platform_config()
+            .and_then(|c| c.deploy_target(target))
+            .with_context(|| format!("No deploy target named {target}"))?;
+        let targets = self.targets.read();
+        let state = targets.get(target).cloned().unwrap_or_default();
+        if state.allow_unmanaged || config.method == DeployMethod::Archive {
+            return Ok(Manifest::default());
+        }
+        Ok(find_unmanaged(&settings, config, &state.managed))
+    }
+
+    /// Allow deploying to the named target even though its SD root has game
+    /// files UKMM did not deploy. Deploying may overwrite or remove them.
+    pub fn allow_unmanaged(&self, target: &str) -> Result<()> {
+        log::info!("Allowing deployment over unmanaged files in {target}");
+        self.targets
+            .write()
+            .entry(target.into())
+            .or_default()
+            .allow_unmanaged = true;
+        self.save()
+    }
+
+    /// Deploy the pending changes to every deploy target.
     pub fn deploy(&self) -> Result<()> {
+        self.deploy_where(|_| true).map(|_| ())
+    }
+
+    /// Deploy the pending changes to the named deploy target only. The other
+    /// targets keep their pending changes.
+    pub fn deploy_target(&self, target: &str) -> Result<()> {
+        if self.deploy_where(|c| c.name.as_str() == target)? == 0 {
+            anyhow_ext::bail!("No deploy target named {target}");
+        }
+        Ok(())
+    }
+
+    /// Deploy the pending changes to every target with auto deploy enabled.
+    pub fn deploy_auto(&self) -> Result<()> {
+        self.deploy_where(|c| c.auto).map(|_| ())
+    }
+
+    fn deploy_where(&self, filter: impl Fn(&DeployConfig) -> bool) -> Result<usize> {
         let settings = self
             .settings
             .upgrade()
             .expect("YIKES, the settings manager is gone");
         let settings = settings.read();
-        let mut lang = Language::USen;
-        let mut profile = String::from("");
-        let config = settings
+        let configs = Self::deploy_targets(&settings);
+        if configs.is_empty() {
+            anyhow_ext::bail!("No deployment config for current platform");
+        }
+        crate::settings::validate_deploy_targets(configs)?;
+        // Each target is deployed on its own, so one failing leaves the others
+        // deployed and only its own changes pending.
+        let mut count = 0;
+        let mut errors = vec![];
+        for config in configs.iter().filter(|c| filter(c)) {
+            log::info!("Deploying to {}", config.name);
+            if let Err(e) = self
+                .deploy_to(&settings, config)
+                .with_context(|| format!("Failed to deploy to {}", config.name))
+            {
+                log::error!("{e:?}");
+                errors.push(e);
+            }
+            count += 1;
+        }
+        match errors.len() {
+            0 => Ok(count),
+            1 => Err(errors.remove(0)),
+            _ => {
+                anyhow_ext::bail!(
+                    "Failed to deploy to {} of {count} targets. Details below:\n{}",
+                    errors.len(),
+                    errors
+                        .iter()
+                        .map(|e| format!("{e:?}"))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                )
+            }
+        }
+    }
+
+    fn deploy_to(&self, settings: &Settings, config: &DeployConfig) -> Result<()> {
+        let lang = settings
             .platform_config()
-            .and_then(|c| {
-                lang = c.language;
-                profile = c.profile.clone();
-                c.deploy_config.as_ref()
-            })
-            .context("No deployment config for current platform")?;
+            .map(|c| c.language)
+            .unwrap_or(Language::USen);
         log::debug!("Deployment config:\n{:#?}", &config);
+        let state = self
+            .targets
+            .read()
+            .get(&config.name)
+            .cloned()
+            .unwrap_or_default();
 
         // Determine src and dest folders
         let (content, aoc) = uk_content::platform_prefixes(settings.current_mode.into());
         let src_content  = settings.merged_dir().join(content);
         let src_aoc = settings.merged_dir().join(aoc);
         let (dest_content, dest_aoc) = config.final_output_paths(settings.current_mode.into());
+        let rules_path = dest_content.parent().unwrap().join("rules.txt");
+        let rules = (settings.current_mode == Platform::WiiU
+            && config.cemu_rules
+            && config.method != DeployMethod::Archive)
+            .then(|| self.cemu_rules(settings))
+            .filter(|rules| {
+                fs::read_to_string(&rules_path).ok().as_deref() != Some(rules.as_str())
+            });
+        if !state.allow_unmanaged && config.method != DeployMethod::Archive {
+            let unmanaged = find_unmanaged(settings, config, &state.managed);
+            let count = unmanaged.content_files.len() + unmanaged.aoc_files.len();
+            if count > 0 {
+                anyhow_ext::bail!(
+                    "The SD card for {} already has {count} game files which UKMM did not deploy. \
+                     Deploy from the Deploy tab and confirm, or use `deploy --allow-unmanaged`, \
+                     to deploy over them.",
+                    config.name
+                );
+            }
+        }
         // Remove old behavior
         if util::is_symlink(&config.output) {
             log::info!("Removing old symlink deployment behavior");
@@ -207,6 +563,8 @@ impl Manager {
                 .context("Failed to remove old deployment behavior symlink")?;
         }
 
+        let mut deployed = FileHashes::default();
+        let mut managed = state.managed.clone();
         if config.method == DeployMethod::Symlink {
             log::info!("Deploy method is symlink, checking for symlink");
 
@@ -248,6 +606,18 @@ impl Manager {
                     log::info!("Symlink exists, no deployment needed")
                 }
             }
+        } else if config.method == DeployMethod::Archive {
+            let archive = config.archive_path();
+            if state.is_empty() && archive.exists() {
+                log::info!("Deploy archive is up to date, no deployment needed");
+            } else {
+                log::info!("Deploying to archive at {}", archive.display());
+                let rules = (settings.current_mode == Platform::WiiU && config.cemu_rules)
+                    .then(|| self.cemu_rules(settings));
+                write_archive(settings, config, lang, rules.as_deref())
+                    .context("Failed to write deploy archive")?;
+                log::info!("Deployment complete");
+            }
         } else {
             if util::is_symlink(&dest_content) {
                 util::remove_symlink(&dest_content)
@@ -257,14 +627,17 @@ impl Manager {
                 util::remove_symlink(&dest_aoc)
                     .context("Failed to remove symlink to old symlinked dlc")?;
             }
-            let deletes = self.pending_delete.read();
+            let deletes = &state.delete;
             log::debug!("Deployed files to delete:\n{:#?}", &deletes);
-            let syncs = self.pending_files.read();
+            let syncs = &state.files;
             log::debug!("Files to deploy\n{:#?}", &syncs);
             log::info!("Deploying by {}", match config.method {
                 DeployMethod::Copy => "copy",
                 DeployMethod::HardLink => "hard links",
-                DeployMethod::Symlink => unsafe { std::hint::unreachable_unchecked() },
+                DeployMethod::Reflink => "reflinks",
+                DeployMethod::Symlink | DeployMethod::Archive => unsafe {
+                    std::hint::unreachable_unchecked()
+                },
             });
             log::info!("Deploy layout: {}", config.layout.name());
 
@@ -272,113 +645,176 @@ impl Manager {
                 !file.starts_with("Pack/Bootup_") || **file == lang.bootup_path()
             };
 
-            for (source, dest,  dels, syncs) in [
-                (src_content, dest_content.clone(), &deletes.content_files, &syncs.content_files),
-                (src_aoc, dest_aoc, &deletes.aoc_files, &syncs.aoc_files),
+            let journal_dir = Self::journal_dir(settings, &config.name);
+            if let Some(journal) = Journal::open(&journal_dir)? {
+                log::warn!("The last deployment did not finish, rolling it back first");
+                journal
+                    .rollback()
+                    .context("Failed to roll back unfinished deployment")?;
+            }
+            let journal = Journal::begin(
+                &journal_dir,
+                [
+                    (&dest_content, &deletes.content_files, &syncs.content_files),
+                    (&dest_aoc, &deletes.aoc_files, &syncs.aoc_files),
+                ]
+                .into_iter()
+                .flat_map(|(dest, dels, syncs)| {
+                    dels.iter()
+                        .chain(syncs.iter())
+                        .filter(filter_xbootup)
+                        .map(move |f| dest.join(f.as_str()))
+                })
+                .chain(rules.is_some().then(|| rules_path.clone())),
+            )?;
+            if config.content_hash {
+                deployed = state.deployed.clone();
+            }
+            // Files with no source are removed rather than deployed
+            for (managed, source, dels, syncs) in [
+                (
+                    &mut managed.content_files,
+                    &src_content,
+                    &deletes.content_files,
+                    &syncs.content_files,
+                ),
+                (
+                    &mut managed.aoc_files,
+                    &src_aoc,
+                    &deletes.aoc_files,
+                    &syncs.aoc_files,
+                ),
             ] {
-                dels.par_iter()
-                    .filter(filter_xbootup)
-                    .try_for_each(|f| -> Result<()> {
-                        let file = dest.join(f.as_str());
-                        if file.exists() {
-                            fs::remove_file(file)?;
-                        }
-                        Ok(())
-                    })?;
-
-                syncs.par_iter().filter(filter_xbootup).try_for_each(
-                    |f: &String| -> Result<()> {
-                        let from = source.join(f.as_str());
-                        let out = dest.join(f.as_str());
-                        if out.exists() {
-                            fs::remove_file(&out)?;
-                        }
-                        if from.exists() {
-                            out.parent().map(fs::create_dir_all).transpose()?;
-                            match config.method {
-                                DeployMethod::Copy => fs::copy(from, &out).map(|_| ()),
-                                DeployMethod::HardLink => fs::hard_link(from, &out),
-                                DeployMethod::Symlink => unreachable!(),
-                            }
-                            .with_context(|| format!("Failed to deploy {} to {}", f, out.display()))
-                            .map_err(|e| {
-                                if e.root_cause().to_string().contains("os error 17") {
-                                    e.context(
-                                        "Hard linking failed because the output folder is on a \
-                                         different disk or partition than the storage folder.",
-                                    )
-                                } else {
-                                    e
-                                }
-                            })?;
-                            Ok(())
-                        } else {
-                            log::warn!(
-                                "Source file {} missing, we're assuming it was a deletion lost \
-                                 track of",
-                                from.display()
-                            );
-                            Ok(())
-                        }
-                    },
-                )?;
+                for file in dels.iter().chain(syncs).filter(filter_xbootup) {
+                    managed.remove(file);
+                }
+                managed.extend(
+                    syncs
+                        .iter()
+                        .filter(filter_xbootup)
+                        .filter(|file| source.join(file.as_str()).exists())
+                        .cloned(),
+                );
             }
+            let result = sync_files(config, &journal, &mut deployed, filter_xbootup, [
+                (
+                    src_content,
+                    dest_content.clone(),
+                    &deletes.content_files,
+                    &syncs.content_files,
+                ),
+                (src_aoc, dest_aoc, &deletes.aoc_files, &syncs.aoc_files),
+            ])
+            .and_then(|()| {
+                if let Some(rules) = rules.as_deref() {
+                    log::info!("Updating Cemu rules.txt");
+                    journal.remove(&rules_path)?;
+                    fs::write(&rules_path, rules)?;
+                }
+                Ok(())
+            });
+            if let Err(e) = result {
+                log::error!("Deployment failed, rolling back");
+                return Err(match journal.rollback() {
+                    Ok(()) => {
+                        e.context(
+                            "Deployment failed, so the output folder was restored to the last \
+                             successful deployment",
+                        )
+                    }
+                    Err(rollback_err) => {
+                        e.context(format!(
+                            "Deployment failed and could not be rolled back: {rollback_err:?}"
+                        ))
+                    }
+                });
+            }
+            journal.finish()?;
             log::info!("Deployment complete");
         }
-        let rules_path = dest_content.parent().unwrap().join("rules.txt");
-        if settings.current_mode == Platform::WiiU
-            && settings
-                .platform_config()
-                .and_then(|c| c.deploy_config.as_ref().map(|c| c.cemu_rules))
-                .unwrap_or(false)
-            && !rules_path.exists()
-        {
-            fs::write(rules_path, include_str!("../../../assets/rules.txt"))?;
+        if let (DeployMethod::Symlink, Some(rules)) = (config.method, rules) {
+            log::info!("Updating Cemu rules.txt");
+            fs::write(rules_path, rules)?;
         }
-        self.pending_delete.write().clear();
-        self.pending_files.write().clear();
+        config.clean_title_dirs(settings.current_mode.into())?;
+        self.targets
+            .write()
+            .insert(config.name.clone(), TargetState {
+                deployed,
+                managed,
+                allow_unmanaged: state.allow_unmanaged,
+                ..Default::default()
+            });
         self.save()?;
         Ok(())
     }
 
+    /// Generate the Cemu rules.txt for the current profile and its enabled
+    /// mods.
+    fn cemu_rules(&self, settings: &Settings) -> std::string::String {
+        let mod_manager = self
+            .mod_manager
+            .upgrade()
+            .expect("YIKES, the mod manager system is gone");
+        let mods: Vec<_> = mod_manager.read().mods().collect();
+        let profile = settings
+            .platform_config()
+            .map(|c| c.profile.as_str())
+            .unwrap_or("Default");
+        rules::cemu_rules(profile, &mods)
+    }
+
+    /// Dry run of [`Manager::deploy`], reporting what deploying the pending
+    /// changes would do to each deploy target without touching them.
+    pub fn plan_deploy(&self) -> Result<DeployPlan> {
+        let settings = self
+            .settings
+            .upgrade()
+            .expect("YIKES, the settings manager is gone");
+        let settings = settings.read();
+        crate::settings::validate_deploy_targets(Self::deploy_targets(&settings))?;
+        let targets = self.targets.read();
+        Ok(DeployPlan {
+            targets: Self::deploy_targets(&settings)
+                .iter()
+                .map(|config| {
+                    let state = targets.get(&config.name).cloned().unwrap_or_default();
+                    plan_target(
+                        &settings,
+                        config,
+                        &state.files,
+                        &state.delete,
+                        &DashMap::new(),
+                        false,
+                    )
+                })
+                .collect(),
+            ..Default::default()
+        })
+    }
+
     fn handle_orphans(
         &self,
+        settings: &Settings,
         total_manifest: Manifest,
         manifest: &mut Manifest,
-        out_dir: &Path,
-        platform: Platform,
     ) -> Result<()> {
-        let (orphans_content, orphans_aoc): (Vec<_>, Vec<_>) = (
-            manifest
-                .content_files
-                .difference(&total_manifest.content_files)
-                .cloned()
-                .collect(),
-            manifest
-                .aoc_files
-                .difference(&total_manifest.aoc_files)
-                .cloned()
-                .collect(),
-        );
-        if orphans_content.is_empty() && orphans_aoc.is_empty() {
+        let orphans = find_orphans(&total_manifest, manifest);
+        if orphans.is_empty() {
             log::debug!("No orphans");
             return Ok(());
         }
-        log::debug!(
-            "Orphans to delete:\n{:#?}\n{:#?}",
-            &orphans_content,
-            &orphans_aoc
-        );
+        log::debug!("Orphans to delete:\n{:#?}", &orphans);
         manifest
             .content_files
-            .retain(|f| !orphans_content.contains(f));
-        manifest.aoc_files.retain(|f| !orphans_aoc.contains(f));
-        let mut dels = self.pending_delete.write();
-        dels.content_files.extend(orphans_content.iter().cloned());
-        dels.aoc_files.extend(orphans_aoc.iter().cloned());
-        let (content, dlc) = uk_content::platform_prefixes(platform.into());
-        for (dir, orphans) in [(content, orphans_content), (dlc, orphans_aoc)] {
-            let out_dir = out_dir.join(dir);
+            .retain(|f| !orphans.content_files.contains(f));
+        manifest
+            .aoc_files
+            .retain(|f| !orphans.aoc_files.contains(f));
+        self.add_pending(settings, &Manifest::default(), &orphans);
+        let (content, dlc) = uk_content::platform_prefixes(settings.current_mode.into());
+        for (dir, orphans) in [(content, orphans.content_files), (dlc, orphans.aoc_files)] {
+            let out_dir = settings.merged_dir().join(dir);
             orphans.into_par_iter().try_for_each(|f| -> Result<()> {
                 let file = out_dir.join(f.as_str());
                 if file.exists() {
@@ -399,47 +835,25 @@ impl Manager {
         Ok(())
     }
 
-    fn apply_rstb(
-        &self,
-        merged: &Path,
-        platform: Platform,
-        updates: DashMap<String, Option<u32>>,
-    ) -> Result<()> {
-        static RSTB_PATH: &str = "System/Resource/ResourceSizeTable.product.srsizetable";
+    fn apply_rstb(&self, settings: &Settings, updates: DashMap<String, Option<u32>>) -> Result<()> {
         log::debug!("RSTB updates:\n{:#?}", &updates);
+        let platform = settings.current_mode;
         let content = uk_content::platform_content(platform.into());
-        let table_path = merged.join(content).join(RSTB_PATH);
-        let mut table = if table_path.exists() {
-            log::debug!("Updating existing merged RSTB");
-            ResourceSizeTable::from_binary(
-                decompress(fs::read(&table_path).context("Failed to open merged RSTB")?)
-                    .context("Failed to decompress merged RSTB")?,
-            )
-            .context("Failed to parse merged RSTB")?
-        } else {
-            log::debug!("Creating new RSTB");
-            ResourceSizeTable::new_from_stock(platform.into())
-        };
-        for (canon, size) in updates {
-            match size {
-                Some(size) => {
-                    if table.get(canon.as_str()).map(|s| s < size).unwrap_or(true) {
-                        table.set(canon.as_str(), size);
-                    }
-                }
-                None => {
-                    table.remove(canon.as_str());
-                }
-            }
-        }
-        log::info!("Updated RSTB");
+        let table_path = settings.merged_dir().join(content).join(RSTB_PATH);
+        let mut table = load_rstb(&table_path, platform)?;
+        let changes = update_rstb(&mut table, updates);
+        log::info!("Updated {} RSTB entries", changes.len());
         fs::create_dir_all(table_path.parent().unwrap())?;
         fs::write(table_path, compress(table.to_binary(platform.into())))
             .context("Failed to write merged RSTB")?;
-        self.pending_files
-            .write()
-            .content_files
-            .insert(RSTB_PATH.into());
+        self.add_pending(
+            settings,
+            &Manifest {
+                content_files: [String::from(RSTB_PATH)].into(),
+                ..Default::default()
+            },
+            &Manifest::default(),
+        );
         Ok(())
     }
 
@@ -470,14 +884,9 @@ impl Manager {
                         .with_context(|| jstr!("Failed to open mod: {&m.meta.name}"))
                 })
                 .collect::<Result<Vec<_>>>()?;
-            self.handle_orphans(
-                total_manifest,
-                &mut manifest,
-                &out_dir,
-                settings.current_mode,
-            )?;
+            self.handle_orphans(&settings, total_manifest, &mut manifest)?;
             log::debug!("Change manifest: {:#?}", &manifest);
-            self.pending_files.write().extend(&manifest);
+            self.add_pending(&settings, &manifest, &Manifest::default());
             ModUnpacker::new(
                 dump,
                 endian,
@@ -499,7 +908,7 @@ impl Manager {
                 })
                 .collect::<Result<Vec<_>>>()?;
             util::remove_dir_all(&out_dir).context("Failed to clear merged folder")?;
-            self.pending_files.write().extend(&total_manifest);
+            self.add_pending(&settings, &total_manifest, &Manifest::default());
             ModUnpacker::new(
                 dump,
                 endian,
@@ -509,10 +918,944 @@ impl Manager {
             )
         };
         log::info!("Applying changes");
-        let rstb_updates = unpacker.unpack()?;
-        self.apply_rstb(&out_dir, settings.current_mode, rstb_updates)?;
+        let merge_log = unpacker.with_rehash(settings.rehash_map_objects).unpack()?;
+        for conflict in merge_log.conflicts.iter() {
+            for field in conflict.value() {
+                log::warn!(
+                    "{} in {} is changed by both {} and {}; using {}",
+                    field.path,
+                    conflict.key(),
+                    field.overridden,
+                    field.winner,
+                    field.winner
+                );
+            }
+        }
+        for issue in &merge_log.map_issues {
+            log::warn!("{issue}");
+        }
+        self.apply_rstb(&settings, merge_log.rstb)?;
         self.save()?;
         log::info!("All changed applied successfully");
         Ok(())
     }
+
+    /// Dry run of [`Manager::apply`] followed by [`Manager::deploy`]. Every
+    /// changed file is merged in memory, but nothing is written to the merged
+    /// or output folders and the pending changes are left as they are.
+    pub fn plan_apply(&self, manifest: Option<Manifest>) -> Result<DeployPlan> {
+        let mod_manager = self
+            .mod_manager
+            .upgrade()
+            .expect("YIKES, the mod manager system is gone");
+        let settings = self
+            .settings
+            .upgrade()
+            .expect("YIKES, the settings manager is gone");
+        let settings = settings.read();
+        let dump = settings
+            .dump()
+            .context("No dump available for current platform")?;
+        let out_dir = settings.merged_dir();
+        let mut total_manifest = Manifest::default();
+        let mods = match manifest.as_ref() {
+            Some(manifest) => {
+                mod_manager
+                    .read()
+                    .mods_by_manifest(manifest)
+                    .collect::<Vec<_>>()
+            }
+            None => mod_manager.read().mods().collect(),
+        };
+        let mods = mods
+            .into_iter()
+            .map(|m| {
+                ModReader::open(&m.path, m.enabled_options.clone())
+                    .inspect(|m| total_manifest.extend(&m.manifest))
+                    .with_context(|| jstr!("Failed to open mod: {&m.meta.name}"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut changed = Manifest::default();
+        let mut orphans = Manifest::default();
+        let unpacker = ModUnpacker::new(
+            dump,
+            settings.current_mode.into(),
+            settings.platform_config().unwrap().language,
+            mods,
+            out_dir.clone(),
+        );
+        let cleared = manifest.is_none();
+        let unpacker = match manifest {
+            Some(mut manifest) => {
+                orphans = find_orphans(&total_manifest, &manifest);
+                manifest
+                    .content_files
+                    .retain(|f| !orphans.content_files.contains(f));
+                manifest
+                    .aoc_files
+                    .retain(|f| !orphans.aoc_files.contains(f));
+                changed.extend(&manifest);
+                unpacker.with_manifest(manifest)
+            }
+            None => {
+                changed.extend(&total_manifest);
+                unpacker
+            }
+        };
+        log::info!("Planning changes");
+        let merge_log = unpacker
+            .with_rehash(settings.rehash_map_objects)
+            .with_dry_run(true)
+            .unpack()?;
+        let table_path = out_dir
+            .join(uk_content::platform_content(settings.current_mode.into()))
+            .join(RSTB_PATH);
+        let mut table = if cleared {
+            ResourceSizeTable::new_from_stock(settings.current_mode.into())
+        } else {
+            load_rstb(&table_path, settings.current_mode)?
+        };
+        let rstb = update_rstb(&mut table, merge_log.rstb);
+        merge_log.sizes.insert(
+            table_path,
+            compress(table.to_binary(settings.current_mode.into())).len(),
+        );
+        changed.content_files.insert(RSTB_PATH.into());
+        let targets = self.targets.read();
+        Ok(DeployPlan {
+            targets: Self::deploy_targets(&settings)
+                .iter()
+                .map(|config| {
+                    let mut state = targets.get(&config.name).cloned().unwrap_or_default();
+                    state.files.extend(&changed);
+                    state.delete.extend(&orphans);
+                    plan_target(
+                        &settings,
+                        config,
+                        &state.files,
+                        &state.delete,
+                        &merge_log.sizes,
+                        cleared,
+                    )
+                })
+                .collect(),
+            rstb,
+            orphans,
+        })
+    }
+
+    /// Merge the map units of all enabled mods without deploying them, and
+    /// report any `HashId` or `SRTHash` collisions and dangling links.
+    pub fn check_maps(&self) -> Result<Vec<MapIssue>> {
+        let mod_manager = self
+            .mod_manager
+            .upgrade()
+            .expect("YIKES, the mod manager system is gone");
+        let settings = self
+            .settings
+            .upgrade()
+            .expect("YIKES, the settings manager is gone");
+        let settings = settings.try_read().unwrap();
+        let dump = settings
+            .dump()
+            .context("No dump available for current platform")?;
+        log::info!("Checking merged map units");
+        let mods = mod_manager
+            .read()
+            .mods()
+            .map(|m| {
+                ModReader::open(&m.path, m.enabled_options.clone())
+                    .with_context(|| jstr!("Failed to open mod: {&m.meta.name}"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let issues = ModUnpacker::new(
+            dump,
+            settings.current_mode.into(),
+            settings.platform_config().unwrap().language,
+            mods,
+            settings.merged_dir(),
+        )
+        .check_maps()?;
+        log::info!("Found {} map unit issues", issues.len());
+        Ok(issues)
+    }
+}
+
+/// Compare the merged folder with a deploy target's output folder to find
+/// every file which needs to be deployed to or removed from it.
+fn pending_changes(settings: &Settings, config: &DeployConfig) -> TargetState {
+    if config.method == DeployMethod::Archive {
+        return archive_changes(settings, config);
+    }
+    let source = settings.merged_dir();
+    let (content, aoc) = platform_prefixes(settings.current_mode.into());
+    let (dest_content, dest_aoc) = config.final_output_paths(settings.current_mode.into());
+    let content_hash = config.content_hash && config.method != DeployMethod::Symlink;
+
+    let collect_files = |root: &str, dest: &Path| -> BTreeSet<String> {
+        let source = source.join(root);
+        jwalk::WalkDir::new(&source)
+            .into_iter()
+            .filter_map(|file| {
+                file.ok().and_then(|file| {
+                    file.metadata().ok().and_then(|meta| {
+                        let path = file.path();
+                        let rel = path.strip_prefix(&source).unwrap();
+                        let dest = dest.join(rel);
+                        if content_hash {
+                            return (!meta.is_dir()
+                                && (!dest.exists() || contents_differ(&path, &dest)))
+                            .then(|| rel.to_slash_lossy().into());
+                        }
+                        if !dest.is_dir() && (!dest.exists()
+                            || dest.metadata().ok()?.modified().ok()? < meta.modified().ok()?)
+                        {
+                            Some(rel.to_slash_lossy().into())
+                        } else {
+                            None
+                        }
+                    })
+                })
+            })
+            .collect()
+    };
+
+    let collect_deletes = |root: &str, dest: &Path| -> BTreeSet<String> {
+        let source = source.join(root);
+        jwalk::WalkDir::new(&source)
+            .into_iter()
+            .filter_map(|file| {
+                file.ok().and_then(|file| {
+                    let path = file.path();
+                    let rel = path.strip_prefix(&source).unwrap();
+                    let dest = dest.join(rel);
+                    (dest.exists() && !dest.is_dir() && !path.exists())
+                        .then_some(rel.to_slash_lossy().into())
+                })
+            })
+            .collect()
+    };
+
+    TargetState {
+        files: Manifest {
+            content_files: collect_files(content, &dest_content),
+            aoc_files:     collect_files(aoc, &dest_aoc),
+        },
+        delete: Manifest {
+            content_files: collect_deletes(content, &dest_content),
+            aoc_files:     collect_deletes(aoc, &dest_aoc),
+        },
+        ..Default::default()
+    }
+}
+
+/// Find files in the title folders of an SD root deploy target which UKMM
+/// did not deploy there, going by the files it recorded as deployed. Other
+/// layouts deploy into folders of their own, so nothing in them counts as
+/// unmanaged.
+fn find_unmanaged(settings: &Settings, config: &DeployConfig, managed: &Manifest) -> Manifest {
+    if settings.current_mode != Platform::Switch || config.layout != DeployLayout::SdRoot {
+        return Manifest::default();
+    }
+    let (dest_content, dest_aoc) = config.final_output_paths(settings.current_mode.into());
+    let collect = |dest: &Path, managed: &BTreeSet<String>| -> BTreeSet<String> {
+        if !dest.exists() || util::is_symlink(dest) {
+            return Default::default();
+        }
+        jwalk::WalkDir::new(dest)
+            .into_iter()
+            .filter_map(|file| {
+                let file = file.ok()?;
+                if file.file_type().is_dir() {
+                    return None;
+                }
+                let path = file.path();
+                let rel: String = path.strip_prefix(dest).unwrap().to_slash_lossy().into();
+                (!managed.contains(&rel)).then_some(rel)
+            })
+            .collect()
+    };
+    Manifest {
+        content_files: collect(&dest_content, &managed.content_files),
+        aoc_files:     collect(&dest_aoc, &managed.aoc_files),
+    }
+}
+
+/// Find deployed files which were changed or removed since their hashes were
+/// recorded.
+fn modified_since_deploy(deployed: &FileHashes, dest_content: &Path, dest_aoc: &Path) -> Manifest {
+    let check = |dest: &Path, hashes: &BTreeMap<String, u64>| -> BTreeSet<String> {
+        hashes
+            .par_iter()
+            .filter(|(file, hash)| {
+                hash_file(&dest.join(file.as_str()))
+                    .map(|current| current != **hash)
+                    .unwrap_or(true)
+            })
+            .map(|(file, _)| file.clone())
+            .collect()
+    };
+    Manifest {
+        content_files: check(dest_content, &deployed.content_files),
+        aoc_files:     check(dest_aoc, &deployed.aoc_files),
+    }
+}
+
+/// Sort the files a deployment would write to or remove from a deploy target
+/// by whether they are already in its output folder. Files about to be merged are sized from
+/// `built` instead of the merged folder, and if the merged folder is about to
+/// be cleared, only those files are deployed.
+fn plan_target(
+    settings: &Settings,
+    config: &DeployConfig,
+    files: &Manifest,
+    delete: &Manifest,
+    built: &DashMap<PathBuf, usize>,
+    cleared: bool,
+) -> TargetPlan {
+    let lang = settings
+        .platform_config()
+        .map(|c| c.language)
+        .unwrap_or(Language::USen);
+    let filter_xbootup = |file: &&String| -> bool {
+        !file.starts_with("Pack/Bootup_") || **file == lang.bootup_path()
+    };
+    let (content, aoc) = uk_content::platform_prefixes(settings.current_mode.into());
+    let (dest_content, dest_aoc) = config.final_output_paths(settings.current_mode.into());
+    // An archive is rebuilt as a whole, so what is already in it decides
+    // which files are added and which are replaced.
+    let archived = (config.method == DeployMethod::Archive).then(|| {
+        archive_entries(config, settings.current_mode.into()).unwrap_or_else(|e| {
+            log::warn!("Could not read deploy archive: {e:?}");
+            Default::default()
+        })
+    });
+    let mut plan = TargetPlan {
+        name: config.name.clone(),
+        ..Default::default()
+    };
+    for (root, source, dest, dels, syncs, entries) in [
+        (
+            &mut plan.content,
+            settings.merged_dir().join(content),
+            dest_content,
+            &delete.content_files,
+            &files.content_files,
+            archived.as_ref().map(|a| &a.content_files),
+        ),
+        (
+            &mut plan.aoc,
+            settings.merged_dir().join(aoc),
+            dest_aoc,
+            &delete.aoc_files,
+            &files.aoc_files,
+            archived.as_ref().map(|a| &a.aoc_files),
+        ),
+    ] {
+        let deployed = |file: &String| -> bool {
+            match entries {
+                Some(entries) => entries.contains(file),
+                None => dest.join(file.as_str()).exists(),
+            }
+        };
+        for file in syncs.iter().filter(filter_xbootup) {
+            let from = source.join(file.as_str());
+            let out = dest.join(file.as_str());
+            let size = match built.get(&from) {
+                Some(size) => *size as u64,
+                None if cleared || !from.exists() => {
+                    // Deploying a file with no source removes it
+                    if deployed(file) {
+                        root.delete.insert(file.clone());
+                    }
+                    continue;
+                }
+                None => {
+                    if config.content_hash
+                        && entries.is_none()
+                        && out.exists()
+                        && !contents_differ(&from, &out)
+                    {
+                        continue;
+                    }
+                    from.metadata().map(|meta| meta.len()).unwrap_or_default()
+                }
+            };
+            if deployed(file) {
+                root.replace.insert(file.clone());
+            } else {
+                root.add.insert(file.clone());
+            }
+            if config.method != DeployMethod::Symlink {
+                plan.bytes += size;
+            }
+        }
+        root.delete.extend(
+            dels.iter()
+                .filter(filter_xbootup)
+                .filter(|file| deployed(file) && !syncs.contains(*file))
+                .cloned(),
+        );
+    }
+    plan
+}
+
+/// Files already in a deploy target's archive, relative to the base game and
+/// DLC folders inside it.
+fn archive_entries(config: &DeployConfig, endian: uk_content::prelude::Endian) -> Result<Manifest> {
+    let archive = config.archive_path();
+    if !archive.exists() {
+        return Ok(Manifest::default());
+    }
+    let zip = ZipArchive::new(BufReader::new(fs::File::open(&archive)?))
+        .context("Failed to open deploy archive")?;
+    let (content, aoc) = config.archive_paths(endian);
+    let content = format!("{}/", content.to_slash_lossy());
+    let aoc = format!("{}/", aoc.to_slash_lossy());
+    let mut entries = Manifest::default();
+    for name in zip.file_names() {
+        if let Some(file) = name.strip_prefix(content.as_str()) {
+            entries.content_files.insert(file.into());
+        } else if let Some(file) = name.strip_prefix(aoc.as_str()) {
+            entries.aoc_files.insert(file.into());
+        }
+    }
+    Ok(entries)
+}
+
+/// Compare the merged folder with a deploy target's archive to find every
+/// file which changed since the archive was last written.
+fn archive_changes(settings: &Settings, config: &DeployConfig) -> TargetState {
+    let lang = settings
+        .platform_config()
+        .map(|c| c.language)
+        .unwrap_or(Language::USen);
+    let filter_xbootup = |file: &String| -> bool {
+        !file.starts_with("Pack/Bootup_") || *file == lang.bootup_path()
+    };
+    let written = config
+        .archive_path()
+        .metadata()
+        .and_then(|meta| meta.modified())
+        .ok();
+    let entries = archive_entries(config, settings.current_mode.into()).unwrap_or_else(|e| {
+        log::warn!("Could not read deploy archive: {e:?}");
+        Default::default()
+    });
+    let (content, aoc) = platform_prefixes(settings.current_mode.into());
+
+    let collect_files = |root: &str, entries: &BTreeSet<String>| -> BTreeSet<String> {
+        let source = settings.merged_dir().join(root);
+        jwalk::WalkDir::new(&source)
+            .into_iter()
+            .filter_map(|file| {
+                let file = file.ok()?;
+                let meta = file.metadata().ok()?;
+                if meta.is_dir() {
+                    return None;
+                }
+                let path = file.path();
+                let rel: String = path.strip_prefix(&source).unwrap().to_slash_lossy().into();
+                let changed = match written {
+                    Some(written) => meta.modified().map(|m| m > written).unwrap_or(true),
+                    None => true,
+                };
+                (filter_xbootup(&rel) && (changed || !entries.contains(&rel))).then_some(rel)
+            })
+            .collect()
+    };
+
+    let collect_deletes = |root: &str, entries: &BTreeSet<String>| -> BTreeSet<String> {
+        let source = settings.merged_dir().join(root);
+        entries
+            .iter()
+            .filter(|file| !filter_xbootup(file) || !source.join(file.as_str()).exists())
+            .cloned()
+            .collect()
+    };
+
+    TargetState {
+        files: Manifest {
+            content_files: collect_files(content, &entries.content_files),
+            aoc_files:     collect_files(aoc, &entries.aoc_files),
+        },
+        delete: Manifest {
+            content_files: collect_deletes(content, &entries.content_files),
+            aoc_files:     collect_deletes(aoc, &entries.aoc_files),
+        },
+        ..Default::default()
+    }
+}
+
+/// Pack everything in the merged folder into a deploy target's archive,
+/// including the merged RSTB but only the bootup pack for the current
+/// language, and the Cemu rules.txt if given. The archive is written to a
+/// temporary file first, so the last one is kept if this fails.
+fn write_archive(
+    settings: &Settings,
+    config: &DeployConfig,
+    lang: Language,
+    rules: Option<&str>,
+) -> Result<()> {
+    let endian = settings.current_mode.into();
+    let archive = config.archive_path();
+    let temp = archive.with_extension("zip.tmp");
+    fs::create_dir_all(&config.output)?;
+    let write = || -> Result<usize> {
+        let mut zip = ZipWriter::new(fs::File::create(&temp)?);
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        let (content, aoc) = platform_prefixes(endian);
+        let (archive_content, archive_aoc) = config.archive_paths(endian);
+        let mut count = 0;
+        for (root, dest) in [(content, &archive_content), (aoc, &archive_aoc)] {
+            let source = settings.merged_dir().join(root);
+            for file in jwalk::WalkDir::new(&source)
+                .sort(true)
+                .into_iter()
+                .filter_map(|file| file.ok())
+                .filter(|file| file.file_type().is_file())
+            {
+                let path = file.path();
+                let rel = path.strip_prefix(&source).unwrap().to_slash_lossy();
+                if rel.starts_with("Pack/Bootup_") && *rel != *lang.bootup_path() {
+                    continue;
+                }
+                zip.start_file(dest.join(&*rel).to_slash_lossy(), options)?;
+                zip.write_all(&fs::read(&path)?)?;
+                count += 1;
+            }
+        }
+        if let Some(rules) = rules {
+            let rules_path = archive_content.parent().unwrap().join("rules.txt");
+            zip.start_file(rules_path.to_slash_lossy(), options)?;
+            zip.write_all(rules.as_bytes())?;
+        }
+        zip.finish()?;
+        Ok(count)
+    };
+    match write() {
+        Ok(count) => {
+            fs::rename(&temp, &archive)?;
+            log::info!("Wrote {count} files to {}", archive.display());
+            Ok(())
+        }
+        Err(e) => {
+            fs::remove_file(&temp).unwrap_or(());
+            Err(e)
+        }
+    }
+}
+
+type SyncDirs<'a> = (PathBuf, PathBuf, &'a BTreeSet<String>, &'a BTreeSet<String>);
+
+/// Remove the pending deletions from each output folder and copy or link the
+/// pending files into it, moving anything removed or replaced into the
+/// journal's backup.
+fn sync_files(
+    config: &DeployConfig,
+    journal: &Journal,
+    deployed: &mut FileHashes,
+    filter_xbootup: impl Fn(&&String) -> bool + Copy + Send + Sync,
+    dirs: [SyncDirs<'_>; 2],
+) -> Result<()> {
+    let [content, aoc] = dirs;
+    for ((source, dest, dels, syncs), hashes) in [
+        (content, &mut deployed.content_files),
+        (aoc, &mut deployed.aoc_files),
+    ] {
+        dels.par_iter()
+            .filter(filter_xbootup)
+            .try_for_each(|f| -> Result<()> { journal.remove(&dest.join(f.as_str())) })?;
+        for f in dels.iter() {
+            hashes.remove(f);
+        }
+
+        let recorded: DashMap<String, u64> = DashMap::new();
+        syncs
+            .par_iter()
+            .filter(filter_xbootup)
+            .try_for_each(|f: &String| -> Result<()> {
+                let from = source.join(f.as_str());
+                let out = dest.join(f.as_str());
+                if config.content_hash && from.exists() && out.exists() {
+                    let hash = hash_file(&from)?;
+                    let current = hash_file(&out).ok();
+                    if let (Some(current), Some(last)) = (current, hashes.get(f)) {
+                        if current != *last {
+                            log::warn!(
+                                "{} was changed outside UKMM since the last deploy, overwriting it",
+                                out.display()
+                            );
+                        }
+                    }
+                    if current == Some(hash) {
+                        log::trace!("{} is unchanged, skipping", f);
+                        recorded.insert(f.clone(), hash);
+                        return Ok(());
+                    }
+                }
+                journal.remove(&out)?;
+                if from.exists() {
+                    out.parent().map(fs::create_dir_all).transpose()?;
+                    match config.method {
+                        DeployMethod::Copy => fs::copy(from, &out).map(|_| ()),
+                        DeployMethod::HardLink => fs::hard_link(from, &out),
+                        DeployMethod::Reflink => {
+                            reflink_copy::reflink_or_copy(&from, &out).map(|copied| {
+                                if copied.is_some() {
+                                    log::trace!("Could not reflink {}, copied it instead", f);
+                                }
+                            })
+                        }
+                        DeployMethod::Symlink | DeployMethod::Archive => unreachable!(),
+                    }
+                    .with_context(|| format!("Failed to deploy {} to {}", f, out.display()))
+                    .map_err(|e| {
+                        if e.root_cause().to_string().contains("os error 17") {
+                            e.context(
+                                "Hard linking failed because the output folder is on a different \
+                                 disk or partition than the storage folder.",
+                            )
+                        } else {
+                            e
+                        }
+                    })?;
+                    if config.content_hash {
+                        recorded.insert(f.clone(), hash_file(&out)?);
+                    }
+                    Ok(())
+                } else {
+                    log::warn!(
+                        "Source file {} missing, we're assuming it was a deletion lost track of",
+                        from.display()
+                    );
+                    Ok(())
+                }
+            })?;
+        hashes.extend(recorded);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[test]
+fn rstb_updates() {
+    let mut table = ResourceSizeTable::new_from_stock(Platform::Switch.into());
+    let updates = DashMap::new();
+    updates.insert("Actor/Pack/UKMM_Test.sbactorpack".into(), Some(1024));
+    updates.insert("UKMM/Missing.bfres".into(), None);
+    let changes = update_rstb(&mut table, updates);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes["Actor/Pack/UKMM_Test.sbactorpack"], RstbChange {
+        old: None,
+        new: Some(1024),
+    });
+    assert_eq!(table.get("Actor/Pack/UKMM_Test.sbactorpack"), Some(1024));
+    let updates = DashMap::new();
+    updates.insert("Actor/Pack/UKMM_Test.sbactorpack".into(), Some(512));
+    assert!(update_rstb(&mut table, updates).is_empty());
+}
+
+#[cfg(test)]
+#[test]
+fn archive_layout() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = DeployConfig {
+        output: dir.path().to_path_buf(),
+        method: DeployMethod::Archive,
+        layout: DeployLayout::SdRoot,
+        ..Default::default()
+    };
+    let endian = Platform::Switch.into();
+    let (content, aoc) = config.archive_paths(endian);
+    assert_eq!(
+        content,
+        Path::new("atmosphere/contents/01007EF00011E000/romfs")
+    );
+    let mut zip = ZipWriter::new(fs::File::create(config.archive_path()).unwrap());
+    for file in [
+        content.join("Actor/ActorInfo.product.sbyml"),
+        aoc.join("Pack/AocMainField.pack"),
+    ] {
+        zip.start_file(file.to_slash_lossy(), SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"UKMM").unwrap();
+    }
+    zip.finish().unwrap();
+    let Manifest {
+        content_files,
+        aoc_files,
+    } = archive_entries(&config, endian).unwrap();
+    assert_eq!(
+        content_files,
+        BTreeSet::from([String::from("Actor/ActorInfo.product.sbyml")])
+    );
+    assert_eq!(
+        aoc_files,
+        BTreeSet::from([String::from("Pack/AocMainField.pack")])
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn content_hashes() {
+    let dir = tempfile::tempdir().unwrap();
+    let content = dir.path().join("content");
+    let aoc = dir.path().join("aoc");
+    fs::create_dir_all(&content).unwrap();
+    fs::create_dir_all(&aoc).unwrap();
+    for (file, data) in [
+        ("Same.bin", b"UKMM".as_slice()),
+        ("Copy.bin", b"UKMM"),
+        ("SameSize.bin", b"MMKU"),
+        ("Longer.bin", b"UKMM!"),
+    ] {
+        fs::write(content.join(file), data).unwrap();
+    }
+    fs::write(aoc.join("Aoc.bin"), b"UKMM").unwrap();
+    // The hashes are kept between runs, so they must not change between builds
+    assert_eq!(
+        hash_file(&content.join("Same.bin")).unwrap(),
+        0x45b4f0ab056b47d2
+    );
+    assert!(!contents_differ(
+        &content.join("Same.bin"),
+        &content.join("Copy.bin")
+    ));
+    assert!(contents_differ(
+        &content.join("Same.bin"),
+        &content.join("SameSize.bin")
+    ));
+    assert!(contents_differ(
+        &content.join("Same.bin"),
+        &content.join("Longer.bin")
+    ));
+    assert!(contents_differ(
+        &content.join("Same.bin"),
+        &content.join("Missing.bin")
+    ));
+
+    let mut deployed = FileHashes::default();
+    for file in ["Same.bin", "Copy.bin", "SameSize.bin"] {
+        deployed
+            .content_files
+            .insert(file.into(), hash_file(&content.join(file)).unwrap());
+    }
+    deployed
+        .aoc_files
+        .insert("Aoc.bin".into(), hash_file(&aoc.join("Aoc.bin")).unwrap());
+    assert!(modified_since_deploy(&deployed, &content, &aoc).is_empty());
+    fs::write(content.join("SameSize.bin"), b"UKMM").unwrap();
+    fs::remove_file(content.join("Copy.bin")).unwrap();
+    let modified = modified_since_deploy(&deployed, &content, &aoc);
+    assert_eq!(
+        modified.content_files,
+        BTreeSet::from([String::from("Copy.bin"), String::from("SameSize.bin")])
+    );
+    assert!(modified.aoc_files.is_empty());
+
+    let legacy: FileHashes =
+        serde_yaml::from_str("content_files:\n  Same.bin: 1\naoc_files: {}").unwrap();
+    assert_ne!(legacy.algorithm, HASH_ALGORITHM);
+}
+
+/// A deployment manager for Switch with one deploy target, storing its data
+/// in `dir`. The settings have to be kept alive for as long as it is used.
+#[cfg(test)]
+fn test_manager(dir: &Path, config: &DeployConfig) -> (Arc<RwLock<Settings>>, Manager) {
+    use crate::settings::PlatformSettings;
+
+    let settings = Arc::new(RwLock::new(Settings {
+        current_mode: Platform::Switch,
+        storage_dir: dir.join("storage"),
+        switch_config: Some(PlatformSettings {
+            language: Language::USen,
+            profile: "Default".into(),
+            dump: Arc::new(uk_reader::ResourceReader::from_unpacked_mod(dir).unwrap()),
+            deploy_targets: vec![config.clone()],
+        }),
+        ..Default::default()
+    }));
+    fs::create_dir_all(settings.read().platform_dir()).unwrap();
+    let manager = Manager {
+        settings:    Arc::downgrade(&settings),
+        mod_manager: Weak::new(),
+        targets:     Default::default(),
+    };
+    (settings, manager)
+}
+
+#[cfg(test)]
+#[test]
+fn rollback_failed_sync() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = DeployConfig {
+        output: dir.path().join("output"),
+        ..Default::default()
+    };
+    let (settings, manager) = test_manager(dir.path(), &config);
+
+    let endian = Platform::Switch.into();
+    let (content, aoc) = platform_prefixes(endian);
+    let (dest_content, dest_aoc) = config.final_output_paths(endian);
+    let source = settings.read().merged_dir();
+    fs::create_dir_all(dest_content.join("Actor")).unwrap();
+    fs::write(dest_content.join("Actor/Replaced.bin"), b"old").unwrap();
+    fs::write(dest_content.join("Actor/Deleted.bin"), b"old").unwrap();
+    // A file where a folder should be, so deploying into it fails
+    fs::write(dest_content.join("Blocked"), b"old").unwrap();
+    for file in [
+        "Actor/Replaced.bin",
+        "Model/UKMM/Added.bin",
+        "Blocked/Failed.bin",
+    ] {
+        let file = source.join(content).join(file);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, b"new").unwrap();
+    }
+    let deletes = BTreeSet::from([String::from("Actor/Deleted.bin")]);
+    let syncs = BTreeSet::from([
+        String::from("Actor/Replaced.bin"),
+        String::from("Model/UKMM/Added.bin"),
+        String::from("Blocked/Failed.bin"),
+    ]);
+
+    let journal = Journal::begin(
+        &Manager::journal_dir(&settings.read(), &config.name),
+        deletes
+            .iter()
+            .chain(syncs.iter())
+            .map(|f| dest_content.join(f.as_str())),
+    )
+    .unwrap();
+    let empty = BTreeSet::new();
+    assert!(sync_files(
+        &config,
+        &journal,
+        &mut FileHashes::default(),
+        |_: &&String| true,
+        [
+            (source.join(content), dest_content.clone(), &deletes, &syncs),
+            (source.join(aoc), dest_aoc, &empty, &empty),
+        ]
+    )
+    .is_err());
+    drop(journal);
+    assert!(!dest_content.join("Actor/Deleted.bin").exists());
+    assert!(manager.interrupted());
+
+    assert!(manager.rollback().unwrap());
+    assert!(!manager.interrupted());
+    assert_eq!(
+        fs::read(dest_content.join("Actor/Replaced.bin")).unwrap(),
+        b"old"
+    );
+    assert_eq!(
+        fs::read(dest_content.join("Actor/Deleted.bin")).unwrap(),
+        b"old"
+    );
+    assert_eq!(fs::read(dest_content.join("Blocked")).unwrap(), b"old");
+    assert!(!dest_content.join("Model").exists());
+    assert!(!manager.rollback().unwrap());
+}
+
+#[cfg(test)]
+#[test]
+fn refuse_unmanaged() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = DeployConfig {
+        output: dir.path().join("sd"),
+        layout: DeployLayout::SdRoot,
+        ..Default::default()
+    };
+    let (settings, manager) = test_manager(dir.path(), &config);
+    let endian = Platform::Switch.into();
+    let (content, _) = platform_prefixes(endian);
+    let (dest_content, _) = config.final_output_paths(endian);
+    let merged = settings.read().merged_dir().join(content);
+    fs::create_dir_all(dest_content.join("Actor")).unwrap();
+    fs::create_dir_all(merged.join("Actor")).unwrap();
+    fs::write(dest_content.join("Actor/Deployed.bin"), b"old").unwrap();
+    // Being merged as well does not make a file UKMM's
+    fs::write(dest_content.join("Actor/Unmanaged.bin"), b"old").unwrap();
+    fs::write(merged.join("Actor/Unmanaged.bin"), b"new").unwrap();
+    manager
+        .targets
+        .write()
+        .insert(config.name.clone(), TargetState {
+            files: Manifest {
+                content_files: [String::from("Actor/Unmanaged.bin")].into(),
+                ..Default::default()
+            },
+            managed: Manifest {
+                content_files: [String::from("Actor/Deployed.bin")].into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+    let unmanaged = manager.unmanaged_files(&config.name).unwrap();
+    assert_eq!(
+        unmanaged.content_files,
+        BTreeSet::from([String::from("Actor/Unmanaged.bin")])
+    );
+    assert!(unmanaged.aoc_files.is_empty());
+    let err = manager.deploy_target(&config.name).unwrap_err();
+    assert!(format!("{err:?}").contains("1 game files which UKMM did not deploy"));
+    assert_eq!(
+        fs::read(dest_content.join("Actor/Unmanaged.bin")).unwrap(),
+        b"old"
+    );
+
+    manager.allow_unmanaged(&config.name).unwrap();
+    assert!(manager.unmanaged_files(&config.name).unwrap().is_empty());
+    manager.deploy_target(&config.name).unwrap();
+    assert_eq!(
+        fs::read(dest_content.join("Actor/Unmanaged.bin")).unwrap(),
+        b"new"
+    );
+    assert_eq!(
+        manager.targets.read()[&config.name].managed.content_files,
+        BTreeSet::from([
+            String::from("Actor/Deployed.bin"),
+            String::from("Actor/Unmanaged.bin"),
+        ])
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn reset_sd_root() {
+    let dir = tempfile::tempdir().unwrap();
+    for content_hash in [false, true] {
+        let config = DeployConfig {
+            output: dir.path().join("sd"),
+            layout: DeployLayout::SdRoot,
+            content_hash,
+            ..Default::default()
+        };
+        let (settings, manager) = test_manager(dir.path(), &config);
+        let endian = Platform::Switch.into();
+        let (content, aoc) = platform_prefixes(endian);
+        let (dest_content, dest_aoc) = config.final_output_paths(endian);
+        let merged = settings.read().merged_dir();
+        for (source, dest, file) in [
+            (
+                merged.join(content),
+                dest_content,
+                "Actor/ActorInfo.product.sbyml",
+            ),
+            (merged.join(aoc), dest_aoc, "Pack/AocMainField.pack"),
+        ] {
+            // Deployed after merging, so the deployed copies are no older
+            for root in [source, dest] {
+                let file = root.join(file);
+                fs::create_dir_all(file.parent().unwrap()).unwrap();
+                fs::write(file, b"UKMM").unwrap();
+            }
+        }
+        manager.reset_pending().unwrap();
+        assert!(manager.targets.read().contains_key(&config.name));
+        assert!(!manager.target_pending(&config.name));
+    }
 }
@@ -0,0 +1,248 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use anyhow_ext::{Context, Result};
+use fs_err as fs;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::util;
+
+static JOURNAL: &str = "journal.yml";
+static BACKUP: &str = "backup";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    /// Whether the file was in the output folder before deploying.
+    existed: bool,
+    /// Name of the file's backup in the journal's backup folder.
+    backup:  usize,
+}
+
+/// Record of every file in the output folder which a deployment is about to
+/// remove or replace. Replaced files are moved into a backup folder instead
+/// of being deleted, so that if the deployment fails or is interrupted, the
+/// output folder can be put back the way it was after the last successful
+/// one.
+///
+/// Only files passed to [`Journal::begin`] are covered. The merged RSTB is
+/// deployed like any other merged file, so it always is, but anything else
+/// written to the output folder, like the Cemu rules.txt, has to be added by
+/// the caller.
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct Journal {
+    #[serde(skip)]
+    dir:     PathBuf,
+    entries: BTreeMap<PathBuf, Entry>,
+    /// Folders which did not exist before deploying, removed again when
+    /// rolling back if nothing else was put in them.
+    #[serde(default)]
+    dirs:    BTreeSet<PathBuf>,
+}
+
+/// Move a file, falling back to copying it when the destination is on
+/// another drive.
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if fs::rename(from, to).is_err() {
+        copy_file(from, to)?;
+    }
+    Ok(())
+}
+
+/// Copy a file and remove the original, for moving it between drives.
+fn copy_file(from: &Path, to: &Path) -> Result<()> {
+    fs::copy(from, to)?;
+    fs::remove_file(from)?;
+    Ok(())
+}
+
+impl Journal {
+    /// Whether a journal was left behind by a deployment which never
+    /// finished.
+    #[inline]
+    pub(super) fn exists(dir: &Path) -> bool {
+        dir.join(JOURNAL).exists()
+    }
+
+    /// Load the journal left behind by an unfinished deployment, if any.
+    pub(super) fn open(dir: &Path) -> Result<Option<Self>> {
+        if !Self::exists(dir) {
+            return Ok(None);
+        }
+        let mut journal: Self = serde_yaml::from_str(&fs::read_to_string(dir.join(JOURNAL))?)
+            .context("Failed to parse deployment journal")?;
+        journal.dir = dir.to_path_buf();
+        Ok(Some(journal))
+    }
+
+    /// Start a journal for the given output files and write it to disk
+    /// before anything in the output folder is touched.
+    pub(super) fn begin(dir: &Path, files: impl IntoIterator<Item = PathBuf>) -> Result<Self> {
+        if dir.exists() {
+            util::remove_dir_all(dir).context("Failed to clear old deployment journal")?;
+        }
+        fs::create_dir_all(dir.join(BACKUP))?;
+        let entries: BTreeMap<PathBuf, Entry> = files
+            .into_iter()
+            .enumerate()
+            .map(|(i, file)| {
+                let existed = file.is_file();
+                (file, Entry { existed, backup: i })
+            })
+            .collect();
+        let dirs = entries
+            .keys()
+            .flat_map(|file| file.ancestors().skip(1).take_while(|dir| !dir.exists()))
+            .map(Path::to_path_buf)
+            .collect();
+        let journal = Self {
+            dir: dir.to_path_buf(),
+            entries,
+            dirs,
+        };
+        fs::write(dir.join(JOURNAL), serde_yaml::to_string(&journal)?)
+            .context("Failed to write deployment journal")?;
+        log::debug!(
+            "Started deployment journal with {} files",
+            journal.entries.len()
+        );
+        Ok(journal)
+    }
+
+    #[inline]
+    fn backup_path(&self, entry: &Entry) -> PathBuf {
+        self.dir.join(BACKUP).join(entry.backup.to_string())
+    }
+
+    /// Remove a file from the output folder, keeping a backup of it if it is
+    /// part of the journal.
+    pub(super) fn remove(&self, file: &Path) -> Result<()> {
+        if !file.exists() {
+            return Ok(());
+        }
+        match self.entries.get(file) {
+            Some(entry) => {
+                move_file(file, &self.backup_path(entry))
+                    .with_context(|| format!("Failed to back up {}", file.display()))
+            }
+            None => Ok(fs::remove_file(file)?),
+        }
+    }
+
+    /// The deployment succeeded, so the backups are no longer needed.
+    pub(super) fn finish(self) -> Result<()> {
+        util::remove_dir_all(&self.dir).context("Failed to clear deployment journal")
+    }
+
+    /// Restore every file in the journal to how it was before deploying.
+    pub(super) fn rollback(self) -> Result<()> {
+        log::info!("Rolling back {} deployed files", self.entries.len());
+        self.entries
+            .par_iter()
+            .try_for_each(|(file, entry)| -> Result<()> {
+                let backup = self.backup_path(entry);
+                if backup.exists() {
+                    if file.exists() {
+                        fs::remove_file(file)?;
+                    }
+                    move_file(&backup, file)
+                        .with_context(|| format!("Failed to restore {}", file.display()))?;
+                } else if !entry.existed && file.exists() {
+                    fs::remove_file(file)?;
+                }
+                Ok(())
+            })?;
+        // Subfolders sort after their parents, so they are removed first
+        for dir in self.dirs.iter().rev() {
+            if std::fs::read_dir(dir)
+                .map(|mut d| d.next().is_none())
+                .unwrap_or(false)
+            {
+                fs::remove_dir(dir)?;
+            }
+        }
+        self.finish()
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn journal_rollback() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("out");
+    let journal_dir = dir.path().join("journal");
+    fs::create_dir_all(out.join("Actor")).unwrap();
+    let replaced = out.join("Actor/Replaced.bin");
+    let deleted = out.join("Actor/Deleted.bin");
+    let added = out.join("Model/UKMM/Added.bin");
+    fs::write(&replaced, b"old").unwrap();
+    fs::write(&deleted, b"old").unwrap();
+    let journal = Journal::begin(&journal_dir, [
+        replaced.clone(),
+        deleted.clone(),
+        added.clone(),
+    ])
+    .unwrap();
+    assert!(Journal::exists(&journal_dir));
+    journal.remove(&replaced).unwrap();
+    fs::write(&replaced, b"new").unwrap();
+    journal.remove(&deleted).unwrap();
+    fs::create_dir_all(added.parent().unwrap()).unwrap();
+    fs::write(&added, b"new").unwrap();
+    assert!(!deleted.exists());
+
+    // Interrupted, so the journal is loaded back from disk
+    drop(journal);
+    let journal = Journal::open(&journal_dir).unwrap().unwrap();
+    journal.rollback().unwrap();
+    assert_eq!(fs::read(&replaced).unwrap(), b"old");
+    assert_eq!(fs::read(&deleted).unwrap(), b"old");
+    assert!(!added.exists());
+    assert!(!out.join("Model").exists());
+    assert!(out.join("Actor").exists());
+    assert!(!Journal::exists(&journal_dir));
+    assert!(Journal::open(&journal_dir).unwrap().is_none());
+}
+
+#[cfg(test)]
+#[test]
+fn journal_finish() {
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("out");
+    let journal_dir = dir.path().join("journal");
+    fs::create_dir_all(&out).unwrap();
+    let replaced = out.join("Replaced.bin");
+    let untracked = out.join("Untracked.bin");
+    fs::write(&replaced, b"old").unwrap();
+    fs::write(&untracked, b"old").unwrap();
+    let journal = Journal::begin(&journal_dir, [replaced.clone()]).unwrap();
+    journal.remove(&replaced).unwrap();
+    assert!(journal_dir.join(BACKUP).join("0").exists());
+    fs::write(&replaced, b"new").unwrap();
+    journal.remove(&untracked).unwrap();
+    assert!(!untracked.exists());
+    journal.finish().unwrap();
+    assert_eq!(fs::read(&replaced).unwrap(), b"new");
+    assert!(!journal_dir.exists());
+}
+
+#[cfg(test)]
+#[test]
+fn move_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let from = dir.path().join("from.bin");
+    let to = dir.path().join("backup/to.bin");
+    fs::write(&from, b"UKMM").unwrap();
+    move_file(&from, &to).unwrap();
+    assert!(!from.exists());
+    assert_eq!(fs::read(&to).unwrap(), b"UKMM");
+    // Renaming between drives fails, so those files are copied instead
+    copy_file(&to, &from).unwrap();
+    assert!(!to.exists());
+    assert_eq!(fs::read(&from).unwrap(), b"UKMM");
+}
@@ -0,0 +1,172 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use serde::{Deserialize, Serialize};
+use smartstring::alias::String;
+use uk_mod::Manifest;
+
+/// Files a deployment would change in one output folder.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RootPlan {
+    /// Files which are not in the output folder yet.
+    pub add:     BTreeSet<String>,
+    /// Files which would overwrite ones already in the output folder.
+    pub replace: BTreeSet<String>,
+    /// Files which would be removed from the output folder.
+    pub delete:  BTreeSet<String>,
+}
+
+impl RootPlan {
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.add.len() + self.replace.len() + self.delete.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// How one entry of the merged RSTB would change. `None` means the entry
+/// does not exist before or after the change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RstbChange {
+    pub old: Option<u32>,
+    pub new: Option<u32>,
+}
+
+/// Changes deploying would make to one deploy target.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TargetPlan {
+    /// Name of the deploy target.
+    pub name:    String,
+    /// Changes to the base game output folder.
+    pub content: RootPlan,
+    /// Changes to the DLC output folder.
+    pub aoc:     RootPlan,
+    /// Total size in bytes of the files which would be written to the output
+    /// folder. Nothing is written when deploying by symlink.
+    pub bytes:   u64,
+}
+
+impl TargetPlan {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.content.is_empty() && self.aoc.is_empty()
+    }
+
+    /// Total size of the files to write, in human readable units.
+    pub fn size(&self) -> std::string::String {
+        format_size(self.bytes)
+    }
+}
+
+/// Everything applying changes and deploying would do, worked out by a dry
+/// run without touching the merged or output folders.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeployPlan {
+    /// Changes to each deploy target.
+    pub targets: Vec<TargetPlan>,
+    /// RSTB entries which would be changed, by canonical path.
+    pub rstb:    BTreeMap<String, RstbChange>,
+    /// Files which no enabled mod supplies anymore, and which would be
+    /// removed from the merged folder.
+    pub orphans: Manifest,
+}
+
+impl DeployPlan {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.targets.iter().all(|t| t.is_empty()) && self.rstb.is_empty() && self.orphans.is_empty()
+    }
+
+    /// Total size in bytes of the files which would be written to every
+    /// deploy target.
+    #[inline]
+    pub fn bytes(&self) -> u64 {
+        self.targets.iter().map(|t| t.bytes).sum()
+    }
+
+    /// Total size of the files to write, in human readable units.
+    pub fn size(&self) -> std::string::String {
+        format_size(self.bytes())
+    }
+}
+
+fn format_size(bytes: u64) -> std::string::String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+fn format_rstb(value: Option<u32>) -> std::string::String {
+    value
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| "none".into())
+}
+
+impl fmt::Display for DeployPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No changes");
+        }
+        for target in self.targets.iter().filter(|t| !t.is_empty()) {
+            writeln!(f, "{}: {} to write", target.name, target.size())?;
+            for (name, root) in [("content", &target.content), ("aoc", &target.aoc)] {
+                if root.is_empty() {
+                    continue;
+                }
+                writeln!(
+                    f,
+                    "  {name}: {} to add, {} to replace, {} to delete",
+                    root.add.len(),
+                    root.replace.len(),
+                    root.delete.len()
+                )?;
+                for (sign, files) in [("+", &root.add), ("~", &root.replace), ("-", &root.delete)] {
+                    for file in files {
+                        writeln!(f, "    {sign} {file}")?;
+                    }
+                }
+            }
+        }
+        if !self.rstb.is_empty() {
+            writeln!(f, "RSTB: {} entries to change", self.rstb.len())?;
+            for (file, change) in &self.rstb {
+                writeln!(
+                    f,
+                    "  {file}: {} -> {}",
+                    format_rstb(change.old),
+                    format_rstb(change.new)
+                )?;
+            }
+        }
+        if !self.orphans.is_empty() {
+            writeln!(
+                f,
+                "Orphans: {} files to remove from the merged folder",
+                self.orphans.content_files.len() + self.orphans.aoc_files.len()
+            )?;
+            for file in self
+                .orphans
+                .content_files
+                .iter()
+                .chain(&self.orphans.aoc_files)
+            {
+                writeln!(f, "  - {file}")?;
+            }
+        }
+        writeln!(f, "Total: {} to write", self.size())
+    }
+}
@@ -0,0 +1,84 @@
+use std::hash::{Hash, Hasher};
+
+use crate::mods::Mod;
+
+static DESCRIPTION: &str = "Provides U-King Mod Manager integration. Disable to turn off all UKMM \
+                            mods. Do not use alongside BCML or file replacement graphic packs.";
+
+/// Identify a merge by the enabled mods, their options, and their load order,
+/// so the rules.txt changes whenever what is merged changes.
+fn merge_id(mods: &[Mod]) -> u64 {
+    let mut hasher = rustc_hash::FxHasher::default();
+    for mod_ in mods {
+        mod_.hash().hash(&mut hasher);
+        for option in &mod_.enabled_options {
+            option.path.hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+/// Cemu only shows the description on one line, except where it finds a `|`.
+#[inline]
+fn one_line(text: &str) -> std::string::String {
+    text.replace(['\r', '\n', '|'], " ")
+}
+
+/// Generate the Cemu graphic pack rules.txt for a profile and its enabled
+/// mods in load order.
+pub(super) fn cemu_rules(profile: &str, mods: &[Mod]) -> std::string::String {
+    render(
+        profile,
+        mods.iter()
+            .map(|m| (m.meta.name.as_str(), m.meta.version.as_str())),
+        merge_id(mods),
+    )
+}
+
+fn render<'a>(
+    profile: &str,
+    mods: impl Iterator<Item = (&'a str, &'a str)>,
+    merge: u64,
+) -> std::string::String {
+    let profile = one_line(profile);
+    let mut description = format!("{DESCRIPTION}||Profile: {profile}|");
+    let mut empty = true;
+    for (name, version) in mods {
+        description.push_str(&format!("|{} ({})", one_line(name), one_line(version)));
+        empty = false;
+    }
+    if empty {
+        description.push_str("|No mods enabled");
+    }
+    description.push_str(&format!("||Merge {merge:016x}"));
+    // Cemu reads `version` as the version of the rules.txt format and ignores
+    // packs with one it does not support, so it has to stay at 7. The merge
+    // ID in the description is what changes the file for each merge instead.
+    [
+        "[Definition]".into(),
+        "titleIds = 00050000101C9300,00050000101C9400,00050000101C9500".into(),
+        format!("name = UKMM ({profile})"),
+        "path = The Legend of Zelda: Breath of the Wild/Mods/UKMM".into(),
+        format!("description = {description}"),
+        "version = 7".into(),
+        "default = true".into(),
+        "fsPriority = 9999".into(),
+        std::string::String::new(),
+    ]
+    .join("\n")
+}
+
+#[cfg(test)]
+#[test]
+fn rules_listing() {
+    let rules = render(
+        "Main",
+        [("First Mod", "1.0.0"), ("Second\nMod", "2.1")].into_iter(),
+        0xabc,
+    );
+    assert!(rules.starts_with("[Definition]\n"));
+    assert!(rules.contains("\nname = UKMM (Main)\n"));
+    assert!(rules.contains("|First Mod (1.0.0)|Second Mod (2.1)||Merge 0000000000000abc\n"));
+    assert!(rules.contains("\nversion = 7\n"));
+    assert_ne!(rules, render("Main", std::iter::empty(), 0xabc));
+}
@@ -0,0 +1,333 @@
+//! Development mode: an installed mod linked to the source folder it is
+//! developed in, which is repackaged and remerged as the folder changes.
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use anyhow_ext::{Context, Result};
+use fs_err as fs;
+use join_str::jstr;
+use jwalk::WalkDir;
+use uk_content::{platform_prefixes, prelude::Endian};
+use uk_mod::{pack::ModPacker, unpack::ModReader, Manifest, Meta};
+use uk_util::PathExt;
+
+use crate::{core::Manager, mods::Mod, util::HashMap};
+
+/// How often a linked source folder is checked for changes.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The result of repackaging a linked mod.
+#[derive(Debug, Clone)]
+pub struct DevUpdate {
+    /// The mod as installed after the update.
+    pub mod_:  Mod,
+    /// Files whose resources were added, changed, or removed, which need to
+    /// be remerged.
+    pub dirty: Manifest,
+    /// Whether the whole mod was repackaged, rather than only the changed
+    /// files.
+    pub full:  bool,
+}
+
+/// Link an installed mod to the folder it is developed in, so it can be
+/// repackaged from there with [`update`] and [`watch`].
+pub fn link(core: &Manager, mod_: &Mod, source: impl AsRef<Path>) -> Result<PathBuf> {
+    let source = source.as_ref();
+    if !source.is_dir() {
+        anyhow_ext::bail!("Source folder {} does not exist", source.display());
+    }
+    let source = std::path::absolute(source)?;
+    restore_previous(&mod_.path)?;
+    let manager = core.mod_manager();
+    manager.set_dev_source(mod_, Some(source.clone()), None)?;
+    manager.save()?;
+    log::info!("Linked {} to {}", mod_.meta.name, source.display());
+    Ok(source)
+}
+
+/// Whether a file in a source folder belongs to the content or DLC folder of
+/// the base mod or one of its options, so it can be repackaged on its own.
+fn is_resource(file: &Path) -> bool {
+    let file = match file.strip_prefix("options") {
+        Ok(option_file) => {
+            let mut components = option_file.components();
+            components.next();
+            components.as_path()
+        }
+        Err(_) => file,
+    };
+    [Endian::Big, Endian::Little].into_iter().any(|endian| {
+        let (content, aoc) = platform_prefixes(endian);
+        file.starts_with(content) || file.starts_with(aoc)
+    })
+}
+
+/// Put back the previous package of a mod if its last update never finished,
+/// for instance because UKMM was killed while packaging, which leaves the
+/// mod's own package missing or half written.
+fn restore_previous(path: &Path) -> Result<()> {
+    let previous = path.with_extension("dev.zip");
+    if previous.exists() {
+        log::warn!(
+            "The last update of {} did not finish, restoring the previous package",
+            path.display()
+        );
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        fs::rename(&previous, path).context("Failed to restore previous package")?;
+    }
+    Ok(())
+}
+
+/// Repackage a linked mod from its source folder, replacing the installed
+/// package and keeping its enabled options.
+///
+/// If `changed` lists the files which changed since the last update,
+/// relative to the source folder, and they are all resources, only those
+/// files are repackaged. Otherwise, for instance when the meta or thumbnail
+/// changed, the whole mod is. Merging the changes is left to the caller.
+pub fn update(
+    core: &Manager,
+    mod_: &Mod,
+    changed: Option<&BTreeSet<PathBuf>>,
+) -> Result<DevUpdate> {
+    let source = mod_
+        .dev_source
+        .as_deref()
+        .with_context(|| jstr!("{&mod_.meta.name} is not linked to a source folder"))?;
+    restore_previous(&mod_.path)?;
+    if !mod_.path.is_file() {
+        anyhow_ext::bail!("Only packaged mods can be updated from a source folder");
+    }
+    let dump = core
+        .settings()
+        .dump()
+        .context("No dump available for current platform")?;
+    let meta = if let Some(meta) = source.join("meta.yml").exists_then() {
+        Some(Meta::parse(meta)?)
+    } else if source.join("rules.txt").exists() || source.join("info.json").exists() {
+        None
+    } else {
+        Some(mod_.meta.clone())
+    };
+    let changed = changed.filter(|changed| changed.iter().all(|file| is_resource(file)));
+    log::info!("Updating {} from {}", mod_.meta.name, source.display());
+
+    // The new package is written to the mod's path, and the previous one is
+    // kept next to it to copy unchanged resources from, or to restore if
+    // anything goes wrong.
+    let previous = mod_.path.with_extension("dev.zip");
+    fs::rename(&mod_.path, &previous)?;
+    let result = (|| -> Result<Manifest> {
+        let packer = ModPacker::new(source, &mod_.path, meta, vec![dump])
+            .context("Failed to initialize mod packager")?;
+        match changed {
+            Some(changed) => packer.update(&previous, changed),
+            None => {
+                let mut dirty = Manifest::default();
+                dirty.extend(
+                    &ModReader::open_peek(&previous, mod_.enabled_options.clone())?.manifest,
+                );
+                packer.pack()?;
+                dirty.extend(
+                    &ModReader::open_peek(&mod_.path, mod_.enabled_options.clone())?.manifest,
+                );
+                Ok(dirty)
+            }
+        }
+    })();
+    let dirty = match result {
+        Ok(dirty) => {
+            fs::remove_file(&previous)?;
+            dirty
+        }
+        Err(e) => {
+            if mod_.path.exists() {
+                fs::remove_file(&mod_.path)?;
+            }
+            fs::rename(&previous, &mod_.path)?;
+            return Err(e).context("Failed to package mod");
+        }
+    };
+
+    let mut new_mod = Mod::from_reader(ModReader::open_peek(&mod_.path, vec![])?);
+    let options = mod_
+        .enabled_options
+        .iter()
+        .filter_map(|opt| new_mod.meta.get_option(&opt.path).cloned())
+        .collect();
+    new_mod.enabled_options = options;
+    let manager = core.mod_manager();
+    let new_mod = manager.replace(new_mod, mod_.hash())?;
+    manager.save()?;
+    Ok(DevUpdate {
+        mod_: new_mod,
+        dirty,
+        full: changed.is_none(),
+    })
+}
+
+/// Polls a folder for changed files. Comparing modification times and sizes
+/// is crude, but it is plenty for a mod folder and behaves the same on every
+/// platform and file system.
+#[derive(Debug)]
+pub struct SourceWatcher {
+    root:  PathBuf,
+    files: HashMap<PathBuf, (Option<SystemTime>, u64)>,
+}
+
+impl SourceWatcher {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        let root = root.into();
+        let files = snapshot(&root);
+        Self { root, files }
+    }
+
+    /// The files added, changed, or removed since the last check, relative to
+    /// the folder.
+    pub fn changes(&mut self) -> BTreeSet<PathBuf> {
+        let files = snapshot(&self.root);
+        let mut changed = files
+            .iter()
+            .filter(|(file, state)| self.files.get(*file) != Some(*state))
+            .map(|(file, _)| file.clone())
+            .collect::<BTreeSet<_>>();
+        changed.extend(
+            self.files
+                .keys()
+                .filter(|file| !files.contains_key(*file))
+                .cloned(),
+        );
+        self.files = files;
+        changed
+    }
+}
+
+fn snapshot(root: &Path) -> HashMap<PathBuf, (Option<SystemTime>, u64)> {
+    WalkDir::new(root)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            let file = entry.path().strip_prefix(root).ok()?.to_path_buf();
+            Some((file, (meta.modified().ok(), meta.len())))
+        })
+        .collect()
+}
+
+/// Watch a linked mod's source folder until `stop` is set. Whenever files
+/// change, the mod is updated with them, the changes are merged if the mod
+/// is enabled, and `on_update` is called with the result.
+///
+/// Errors are passed to `on_update` rather than ending the watch, since they
+/// are usually fixed by the next edit. The files which failed are retried
+/// along with it.
+pub fn watch(
+    core: &Manager,
+    mod_: &Mod,
+    stop: &AtomicBool,
+    mut on_update: impl FnMut(Result<DevUpdate>),
+) -> Result<()> {
+    let source = mod_
+        .dev_source
+        .clone()
+        .with_context(|| jstr!("{&mod_.meta.name} is not linked to a source folder"))?;
+    log::info!("Watching {} for changes", source.display());
+    let mut watcher = SourceWatcher::new(&source);
+    let mut mod_ = mod_.clone();
+    let mut pending = BTreeSet::new();
+    while !stop.load(Ordering::Relaxed) {
+        thread::sleep(POLL_INTERVAL);
+        let mut changed = watcher.changes();
+        if changed.is_empty() {
+            continue;
+        }
+        // Saving can touch several files, or write one in several steps, so
+        // wait until the folder settles.
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let more = watcher.changes();
+            if more.is_empty() {
+                break;
+            }
+            changed.extend(more);
+        }
+        changed.append(&mut pending);
+        log::info!("{} files changed in {}", changed.len(), source.display());
+        let result = update(core, &mod_, Some(&changed)).and_then(|update| {
+            if update.mod_.enabled {
+                core.deploy_manager()
+                    .apply(Some(update.dirty.clone()))
+                    .context("Failed to merge changes")?;
+            }
+            Ok(update)
+        });
+        match &result {
+            Ok(update) => mod_ = update.mod_.clone(),
+            Err(_) => pending = changed,
+        }
+        on_update(result);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[test]
+fn source_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    fs::create_dir_all(root.join("content/Actor")).unwrap();
+    fs::write(root.join("content/Actor/ActorInfo.product.sbyml"), b"one").unwrap();
+    fs::write(root.join("rules.txt"), b"[Definition]").unwrap();
+    let mut watcher = SourceWatcher::new(root);
+    assert!(watcher.changes().is_empty());
+
+    fs::write(root.join("content/Actor/ActorInfo.product.sbyml"), b"three").unwrap();
+    fs::remove_file(root.join("rules.txt")).unwrap();
+    let changes = watcher.changes();
+    assert_eq!(
+        changes,
+        BTreeSet::from([
+            PathBuf::from("content/Actor/ActorInfo.product.sbyml"),
+            PathBuf::from("rules.txt"),
+        ])
+    );
+    assert!(watcher.changes().is_empty());
+
+    assert!(is_resource(Path::new(
+        "content/Actor/ActorInfo.product.sbyml"
+    )));
+    assert!(is_resource(Path::new(
+        "options/option1/aoc/0010/Pack/AocMainField.pack"
+    )));
+    assert!(!is_resource(Path::new("rules.txt")));
+    assert!(!is_resource(Path::new("options/option1/thumb.png")));
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[test]
+fn restore_interrupted() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Test Mod.zip");
+    // Killed after moving the package aside, or while writing the new one
+    fs::write(path.with_extension("dev.zip"), b"previous").unwrap();
+    restore_previous(&path).unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"previous");
+    fs::write(path.with_extension("dev.zip"), b"previous").unwrap();
+    fs::write(&path, b"partial").unwrap();
+    restore_previous(&path).unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"previous");
+    assert!(!path.with_extension("dev.zip").exists());
+    // Nothing to do after a finished update
+    restore_previous(&path).unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"previous");
+}
@@ -1,8 +1,10 @@
 #![deny(clippy::unwrap_used)]
 
 pub mod bnp;
+pub mod conflicts;
 pub mod core;
 pub mod deploy;
+pub mod dev;
 pub mod localization;
 pub mod mods;
 pub mod settings;
@@ -1,4 +1,6 @@
 use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
     hash::{Hash, Hasher},
     io::BufReader,
     ops::Deref,
@@ -12,6 +14,7 @@ use fs_err as fs;
 use lenient_semver::Version;
 use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 use sanitise_file_name as sfn;
+use semver::{Comparator, Op, VersionReq};
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
 use smartstring::alias::String;
@@ -25,6 +28,8 @@ use crate::{
 
 type ManifestCache = LazyLock<RwLock<HashMap<(usize, Vec<PathBuf>), Result<Arc<Manifest>>>>>;
 
+static MANIFEST_CACHE: ManifestCache = LazyLock::new(|| RwLock::new(HashMap::default()));
+
 #[serde_as]
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Mod {
@@ -32,6 +37,9 @@ pub struct Mod {
     pub enabled_options: Vec<ModOption>,
     pub enabled: bool,
     pub path: PathBuf,
+    /// The source folder the mod is repackaged from in development mode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dev_source: Option<PathBuf>,
     #[serde_as(as = "DisplayFromStr")]
     pub(crate) hash: usize,
 }
@@ -43,6 +51,7 @@ impl std::fmt::Debug for Mod {
             .field("enabled_options", &self.enabled_options)
             .field("enabled", &self.enabled)
             .field("path", &self.path)
+            .field("dev_source", &self.dev_source)
             .field("hash", &self.hash)
             .finish()
     }
@@ -69,6 +78,7 @@ impl Mod {
             meta: reader.meta,
             enabled_options: vec![],
             path: reader.path,
+            dev_source: None,
             enabled: false,
         }
     }
@@ -78,7 +88,6 @@ impl Mod {
     }
 
     pub fn manifest_with_options(&self, options: impl AsRef<[ModOption]>) -> Result<Arc<Manifest>> {
-        static MANIFEST_CACHE: ManifestCache = LazyLock::new(|| RwLock::new(HashMap::default()));
         match MANIFEST_CACHE
             .write()
             .entry((
@@ -102,6 +111,22 @@ impl Mod {
         self.hash
     }
 
+    /// Iterate the mods this mod depends on.
+    pub fn dependencies(&self) -> impl Iterator<Item = Dependency> + '_ {
+        self.meta.masters.iter().map(|(hash, (name, version))| {
+            Dependency {
+                hash:    *hash,
+                name:    name.clone(),
+                version: version.clone(),
+            }
+        })
+    }
+
+    /// Whether this mod depends on the other, regardless of version.
+    pub fn depends_on(&self, other: &Mod) -> bool {
+        self.hash != other.hash && self.dependencies().any(|dep| dep.refers_to(other))
+    }
+
     pub fn enable_default_options(&mut self) {
         if !self.meta.options.is_empty() {
             for group in self.meta.options.iter_mut() {
@@ -120,6 +145,119 @@ impl Mod {
                     }
                 };
             }
+            match self.meta.with_requirements(self.enabled_options.clone()) {
+                Ok(options) => self.enabled_options = options,
+                Err(e) => log::warn!("Default options of {} are invalid: {e}", self.meta.name),
+            }
+        }
+    }
+}
+
+/// A mod's dependency on another mod, as recorded in its meta `masters`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    /// Hash of the master's meta when the dependent mod was packaged.
+    pub hash:    usize,
+    pub name:    String,
+    /// Version requirement for the master. A bare version, which is what the
+    /// packager records, is treated as a minimum; anything else is parsed as
+    /// a semver range, e.g. `^1.2` or `>=1.0, <2.0`.
+    pub version: String,
+}
+
+impl Dependency {
+    /// The parsed version requirement, or `None` if it cannot be parsed and
+    /// so any version is accepted.
+    pub fn requirement(&self) -> Option<VersionReq> {
+        let version = self.version.trim();
+        if version.is_empty() || version == "*" {
+            return None;
+        }
+        let bare = version.trim_start_matches(['v', 'V']);
+        if bare.starts_with(|c: char| c.is_ascii_digit()) {
+            if let Ok(min) = lenient_semver::parse(bare) {
+                return Some(VersionReq {
+                    comparators: vec![Comparator {
+                        op:    Op::GreaterEq,
+                        major: min.major,
+                        minor: Some(min.minor),
+                        patch: Some(min.patch),
+                        pre:   min.pre,
+                    }],
+                });
+            }
+        }
+        VersionReq::parse(version).ok()
+    }
+
+    /// Whether the mod is the one this dependency refers to, regardless of
+    /// version. Masters are matched by name, falling back to the meta hash
+    /// recorded at packaging time.
+    pub fn refers_to(&self, mod_: &Mod) -> bool {
+        mod_.hash == self.hash || mod_.meta.name.trim() == self.name.trim()
+    }
+
+    /// Whether the mod's version satisfies this dependency. Versions which
+    /// cannot be parsed are accepted, as there is nothing to check them
+    /// against.
+    pub fn accepts(&self, mod_: &Mod) -> bool {
+        if mod_.hash == self.hash {
+            return true;
+        }
+        match (
+            self.requirement(),
+            lenient_semver::parse(mod_.meta.version.as_str()),
+        ) {
+            (Some(req), Ok(version)) => req.matches(&version),
+            _ => true,
+        }
+    }
+}
+
+/// Why one of a mod's dependencies is not satisfied by the current profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DependencyIssue {
+    /// No mod with the master's name is installed.
+    Missing(Dependency),
+    /// A suitable version of the master is installed but disabled.
+    Disabled(Dependency),
+    /// The master is installed, but not in a version the requirement accepts.
+    Version {
+        dependency: Dependency,
+        installed:  String,
+    },
+}
+
+impl DependencyIssue {
+    pub fn dependency(&self) -> &Dependency {
+        match self {
+            Self::Missing(dep) | Self::Disabled(dep) => dep,
+            Self::Version { dependency, .. } => dependency,
+        }
+    }
+}
+
+impl std::fmt::Display for DependencyIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing(dep) => {
+                write!(
+                    f,
+                    "Could not find required mod dependency {} (version {})",
+                    dep.name, dep.version
+                )
+            }
+            Self::Disabled(dep) => write!(f, "Required mod dependency {} is disabled", dep.name),
+            Self::Version {
+                dependency,
+                installed,
+            } => {
+                write!(
+                    f,
+                    "Required mod dependency {} must be version {}, but {} is installed",
+                    dependency.name, dependency.version, installed
+                )
+            }
         }
     }
 }
@@ -265,13 +403,40 @@ impl Manager {
         let path = self.dir.join(profile);
         if !path.exists() {
             log::info!("Profile {profile} does not exist, creating it now");
-            fs::create_dir_all(path)?;
-            self.profiles.insert(profile.into(), Default::default());
+            fs::create_dir_all(&path)?;
+            let data = Profile::default();
+            fs::write(path.join("profile.yml"), serde_yaml::to_string(&data)?)?;
+            self.profiles.insert(profile.into(), data);
             self.save()?;
         }
         Ok(())
     }
 
+    /// Delete a profile other than the current one, along with the files of
+    /// any of its mods which no other profile uses.
+    pub fn delete_profile(&self, profile: &str) -> Result<()> {
+        if profile == self.current_profile.as_str() {
+            anyhow_ext::bail!("Cannot delete the current profile");
+        }
+        let (_, data) = self
+            .profiles
+            .remove(profile)
+            .with_context(|| format!("Profile {profile} does not exist"))?;
+        fs::remove_dir_all(self.dir.join(profile))?;
+        for (hash, mod_) in data.mods().iter() {
+            if !self.profiles.iter().any(|p| p.mods().contains_key(hash)) {
+                log::info!("Removing mod {}, which no profile uses", mod_.meta.name);
+                if mod_.path.is_dir() {
+                    util::remove_dir_all(&mod_.path)?;
+                } else {
+                    fs::remove_file(&mod_.path)?;
+                }
+            }
+        }
+        log::info!("Deleted profile {profile}");
+        Ok(())
+    }
+
     pub fn set_profile(&mut self, profile: &str) -> Result<()> {
         self.current_profile = profile.into();
         self.create_profile_if(profile)?;
@@ -355,6 +520,87 @@ impl Manager {
         })
     }
 
+    /// Check a mod's dependencies against the mods installed in a profile,
+    /// returning any which are not satisfied.
+    pub fn dependency_issues(&self, mod_: &Mod, profile: Option<&String>) -> Vec<DependencyIssue> {
+        let installed = Profile::iter(self.get_profile(profile))
+            .filter(|m| m.hash != mod_.hash)
+            .collect::<Vec<_>>();
+        mod_.dependencies()
+            .filter_map(|dep| {
+                let candidates = installed
+                    .iter()
+                    .filter(|m| dep.refers_to(m))
+                    .collect::<Vec<_>>();
+                if candidates.iter().any(|m| m.enabled && dep.accepts(m)) {
+                    None
+                } else if candidates.iter().any(|m| dep.accepts(m)) {
+                    Some(DependencyIssue::Disabled(dep))
+                } else if let Some(master) = candidates.first() {
+                    Some(DependencyIssue::Version {
+                        installed:  master.meta.version.clone(),
+                        dependency: dep,
+                    })
+                } else {
+                    Some(DependencyIssue::Missing(dep))
+                }
+            })
+            .collect()
+    }
+
+    /// Get the enabled mods in a profile which depend on the given mod, in
+    /// load order.
+    pub fn dependents(&self, mod_: impl LookupMod, profile: Option<&String>) -> Vec<Mod> {
+        let hash = mod_.as_map_id();
+        let all = Profile::iter(self.get_profile(profile)).collect::<Vec<_>>();
+        match all.iter().find(|m| m.hash == hash) {
+            Some(master) => {
+                all.iter()
+                    .filter(|m| m.enabled && m.depends_on(master))
+                    .cloned()
+                    .collect()
+            }
+            None => vec![],
+        }
+    }
+
+    /// Make sure that uninstalling or disabling all of the given mods at once
+    /// would not leave any other enabled mod without one of its masters.
+    pub fn check_removal(&self, mods: &[usize], profile: Option<&String>) -> Result<()> {
+        let all = Profile::iter(self.get_profile(profile)).collect::<Vec<_>>();
+        let blocked = all
+            .iter()
+            .filter(|master| mods.contains(&master.hash))
+            .filter_map(|master| {
+                let dependents = all
+                    .iter()
+                    .filter(|m| m.enabled && !mods.contains(&m.hash) && m.depends_on(master))
+                    .map(|m| m.meta.name.as_str())
+                    .collect::<Vec<_>>();
+                (!dependents.is_empty()).then(|| {
+                    format!(
+                        "{} is required by {}",
+                        master.meta.name,
+                        dependents.join(", ")
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+        if !blocked.is_empty() {
+            anyhow_ext::bail!(
+                "Cannot remove or disable a mod other enabled mods depend on: {}",
+                blocked.join("; ")
+            );
+        }
+        Ok(())
+    }
+
+    fn warn_dependencies(&self, mod_: &Mod, profile: Option<&String>) {
+        for issue in self.dependency_issues(mod_, profile) {
+            log::warn!("{}: {issue}", mod_.meta.name);
+        }
+    }
+
     /// Add a mod to the list of installed mods. This function assumes that the
     /// mod at the provided path has already been validated.
     pub fn add(&self, mod_path: &Path, profile: Option<&String>) -> Result<Mod> {
@@ -424,12 +670,38 @@ impl Manager {
                 profile.unwrap_or(&self.current_profile).as_str()
             );
         }
+        drop(profile_data);
+        self.warn_dependencies(&mod_, profile);
         log::debug!("{:#?}", mod_);
         Ok(mod_)
     }
 
+    /// Uninstall a mod from a profile. Fails if any other enabled mod depends
+    /// on it.
     pub fn del(&self, mod_: impl LookupMod, profile: Option<&String>) -> Result<Arc<Manifest>> {
         let hash = mod_.as_map_id();
+        self.check_removal(&[hash], profile)?;
+        self.remove(hash, profile)
+    }
+
+    /// Uninstall several mods from a profile at once. Dependencies are only
+    /// checked against the mods which remain, so a master can be removed
+    /// together with everything that depends on it.
+    pub fn del_many(
+        &self,
+        mods: impl IntoIterator<Item = impl LookupMod>,
+        profile: Option<&String>,
+    ) -> Result<Manifest> {
+        let hashes = mods.into_iter().map(|m| m.as_map_id()).collect::<Vec<_>>();
+        self.check_removal(&hashes, profile)?;
+        let mut manifest = Manifest::default();
+        for hash in hashes {
+            manifest.extend(self.remove(hash, profile)?.as_ref());
+        }
+        Ok(manifest)
+    }
+
+    fn remove(&self, hash: usize, profile: Option<&String>) -> Result<Arc<Manifest>> {
         let profile_data = self.get_profile(profile);
         let mod_ = profile_data.mods_mut().remove(&hash);
         if let Some(mod_) = mod_ {
@@ -467,6 +739,12 @@ impl Manager {
             .expect("The old mod has to be in the profile");
         mod_.enabled = old_mod.enabled;
         mod_.path = old_mod.path;
+        mod_.dev_source = old_mod.dev_source;
+        // The new mod can have the same hash as the old one if only its
+        // contents changed, so cached manifests of either could be stale.
+        MANIFEST_CACHE
+            .write()
+            .retain(|(hash, _), _| *hash != old_hash && *hash != mod_.hash);
         profile_data.mods_mut().insert(mod_.hash, mod_.clone());
         let mut load_order = profile_data.load_order_mut();
         if let Some(idx) = load_order.iter().position(|m| *m == old_hash) {
@@ -475,6 +753,8 @@ impl Manager {
         Ok(mod_)
     }
 
+    /// Enable or disable a mod. Disabling fails if any other enabled mod
+    /// depends on it, and enabling warns about any unsatisfied dependencies.
     pub fn set_enabled(
         &self,
         mod_: impl LookupMod,
@@ -482,9 +762,41 @@ impl Manager {
         profile: Option<&String>,
     ) -> Result<Arc<Manifest>> {
         let hash = mod_.as_map_id();
+        if !enabled {
+            self.check_removal(&[hash], profile)?;
+        }
+        self.toggle(hash, enabled, profile)
+    }
+
+    /// Enable or disable several mods at once. As with
+    /// [`Manager::del_many`], dependencies are checked against the resulting
+    /// state rather than one mod at a time.
+    pub fn set_enabled_many(
+        &self,
+        states: &[(usize, bool)],
+        profile: Option<&String>,
+    ) -> Result<Manifest> {
+        let disabling = states
+            .iter()
+            .filter_map(|(hash, enabled)| (!enabled).then_some(*hash))
+            .collect::<Vec<_>>();
+        self.check_removal(&disabling, profile)?;
+        let mut manifest = Manifest::default();
+        for (hash, enabled) in states {
+            manifest.extend(self.toggle(*hash, *enabled, profile)?.as_ref());
+        }
+        Ok(manifest)
+    }
+
+    fn toggle(
+        &self,
+        hash: usize,
+        enabled: bool,
+        profile: Option<&String>,
+    ) -> Result<Arc<Manifest>> {
         let manifest;
         let profile_data = self.get_profile(profile);
-        if let Some(mod_) = profile_data.mods_mut().get_mut(&hash) {
+        let mod_ = if let Some(mod_) = profile_data.mods_mut().get_mut(&hash) {
             mod_.enabled = enabled;
             manifest = mod_.manifest()?;
             log::info!(
@@ -493,21 +805,31 @@ impl Manager {
                 mod_.meta.name,
                 profile.unwrap_or(&self.current_profile).as_str()
             );
+            mod_.clone()
         } else {
             log::warn!("Mod with ID {} does not exist, doing nothing", hash);
             return Ok(Default::default());
+        };
+        drop(profile_data);
+        if enabled {
+            self.warn_dependencies(&mod_, profile);
         }
         Ok(manifest)
     }
 
+    /// Set the enabled options of a mod, along with any options they require.
+    /// Fails if the options break the rules of the mod's option groups.
     pub fn set_enabled_options(
         &self,
         mod_: impl LookupMod,
         options: Vec<ModOption>,
+        profile: Option<&String>,
     ) -> Result<Arc<Manifest>> {
         let hash = mod_.as_map_id();
         let manifest;
-        if let Some(mod_) = self.profile().mods_mut().get_mut(&hash) {
+        if let Some(mod_) = self.get_profile(profile).mods_mut().get_mut(&hash) {
+            let options = mod_.meta.with_requirements(options)?;
+            mod_.meta.check_options(&options)?;
             manifest = mod_.manifest_with_options(&options)?;
             mod_.enabled_options = options;
         } else {
@@ -517,6 +839,45 @@ impl Manager {
         Ok(manifest)
     }
 
+    /// Link a mod to the source folder it is developed in, or remove the link.
+    pub fn set_dev_source(
+        &self,
+        mod_: impl LookupMod,
+        source: Option<PathBuf>,
+        profile: Option<&String>,
+    ) -> Result<()> {
+        let hash = mod_.as_map_id();
+        match self.get_profile(profile).mods_mut().get_mut(&hash) {
+            Some(mod_) => {
+                mod_.dev_source = source;
+                Ok(())
+            }
+            None => anyhow_ext::bail!("Mod with ID {} does not exist", hash),
+        }
+    }
+
+    /// Sort a profile's load order by dependencies and load order hints with
+    /// [`sort_load_order`], returning the mods whose position changed.
+    pub fn sort_load_order(&self, profile: Option<&String>) -> Result<Vec<Mod>> {
+        let mods = Profile::iter(self.get_profile(profile)).collect::<Vec<_>>();
+        let sorted = sort_load_order(&mods)?;
+        let moved = mods
+            .iter()
+            .zip(sorted.iter())
+            .filter(|(old, new)| old != new)
+            .map(|(_, new)| new.clone())
+            .collect::<Vec<_>>();
+        if !moved.is_empty() {
+            *self.get_profile(profile).load_order_mut() = sorted.iter().map(|m| m.hash).collect();
+            log::info!(
+                "Sorted load order of profile {}, moving {} mod(s)",
+                profile.unwrap_or(&self.current_profile).as_str(),
+                moved.len()
+            );
+        }
+        Ok(moved)
+    }
+
     pub fn set_order(&self, order: Vec<usize>) {
         *self.profile().load_order_mut() = order;
     }
@@ -526,10 +887,125 @@ impl Manager {
     }
 }
 
+/// Sort mods so that each one loads after its masters and follows the
+/// `load_after` and `load_before` hints in its meta. Mods keep their existing
+/// order wherever it is not constrained. Fails if the constraints form a
+/// cycle, naming the mods involved.
+pub fn sort_load_order(mods: &[Mod]) -> Result<Vec<Mod>> {
+    let position = |name: &String| mods.iter().position(|m| m.meta.name.trim() == name.trim());
+    let mut edges = vec![];
+    for (i, mod_) in mods.iter().enumerate() {
+        for dep in mod_.dependencies() {
+            edges.extend(
+                mods.iter()
+                    .position(|m| m.hash != mod_.hash && dep.refers_to(m))
+                    .map(|master| (master, i)),
+            );
+        }
+        edges.extend(
+            mod_.meta
+                .load_after
+                .iter()
+                .filter_map(position)
+                .filter(|j| *j != i)
+                .map(|j| (j, i)),
+        );
+        edges.extend(
+            mod_.meta
+                .load_before
+                .iter()
+                .filter_map(position)
+                .filter(|j| *j != i)
+                .map(|j| (i, j)),
+        );
+    }
+    match topological_order(mods.len(), &edges) {
+        Ok(order) => Ok(order.into_iter().map(|i| mods[i].clone()).collect()),
+        Err(cycle) => {
+            anyhow_ext::bail!(
+                "Cannot sort load order because these mods must each load after the other: {}",
+                cycle
+                    .iter()
+                    .chain(cycle.first())
+                    .map(|i| mods[*i].meta.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" -> ")
+            )
+        }
+    }
+}
+
+/// Topologically sort the nodes `0..len`, where each edge `(a, b)` places `a`
+/// before `b`. Of the nodes which are free to go next, the lowest one is
+/// always taken, so the result stays as close to the original order as the
+/// edges allow. If the edges contain a cycle, returns the nodes of one.
+fn topological_order(
+    len: usize,
+    edges: &[(usize, usize)],
+) -> std::result::Result<Vec<usize>, Vec<usize>> {
+    let mut successors = vec![vec![]; len];
+    let mut in_degree = vec![0usize; len];
+    for &(from, to) in edges {
+        successors[from].push(to);
+        in_degree[to] += 1;
+    }
+    let mut ready = (0..len)
+        .filter(|i| in_degree[*i] == 0)
+        .map(Reverse)
+        .collect::<BinaryHeap<_>>();
+    let mut order = Vec::with_capacity(len);
+    while let Some(Reverse(node)) = ready.pop() {
+        order.push(node);
+        for &next in &successors[node] {
+            in_degree[next] -= 1;
+            if in_degree[next] == 0 {
+                ready.push(Reverse(next));
+            }
+        }
+    }
+    if order.len() == len {
+        return Ok(order);
+    }
+    // Every node left over still has a predecessor which is also left over,
+    // so walking backwards through them has to come back around eventually.
+    let mut predecessor = vec![None; len];
+    for &(from, to) in edges {
+        if in_degree[from] > 0 && in_degree[to] > 0 {
+            predecessor[to] = Some(from);
+        }
+    }
+    let mut visited = vec![None; len];
+    let mut path = vec![];
+    let mut node = (0..len)
+        .find(|i| in_degree[*i] > 0)
+        .expect("Unsorted nodes should remain");
+    while visited[node].is_none() {
+        visited[node] = Some(path.len());
+        path.push(node);
+        node = predecessor[node].expect("Unsorted node should have an unsorted predecessor");
+    }
+    let mut cycle = path.split_off(visited[node].expect("Node was just visited"));
+    cycle.reverse();
+    Err(cycle)
+}
+
 pub fn convert_gfx(
     core: &crate::core::Manager,
     path: &Path,
     meta: Option<Meta>,
+) -> Result<PathBuf> {
+    let temp = util::get_temp_folder().clone();
+    log::debug!("Temp folder: {}", temp.display());
+    convert_gfx_to(core, path, meta, &temp)
+}
+
+/// Convert a graphic pack or RomFS mod to a UKMM mod at `dest`, which is
+/// either the file to write or a folder to write it to under the mod's name.
+pub fn convert_gfx_to(
+    core: &crate::core::Manager,
+    path: &Path,
+    meta: Option<Meta>,
+    dest: &Path,
 ) -> Result<PathBuf> {
     log::info!("Attempting to convert mod at {}", path.display());
     let path = if path.is_file() {
@@ -599,10 +1075,8 @@ pub fn convert_gfx(
         log::info!("Unpacked mod, that's easy");
         path.to_path_buf()
     };
-    let temp = util::get_temp_folder();
-    log::debug!("Temp folder: {}", temp.display());
     log::info!("Attempting to convert mod...");
-    let packer = ModPacker::new(path, &*temp, meta, vec![
+    let packer = ModPacker::new(path, dest, meta, vec![
         core.settings()
             .dump()
             .context("No dump available for current platform")?,
@@ -629,3 +1103,136 @@ fn san_test() {
     );
     dbg!(sanitized);
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[test]
+fn dependency_requirements() {
+    let dep = |version: &str| {
+        Dependency {
+            hash:    0,
+            name:    "Master".into(),
+            version: version.into(),
+        }
+    };
+    let matches = |version: &str, installed: &str| {
+        dep(version)
+            .requirement()
+            .map(|req| req.matches(&lenient_semver::parse(installed).unwrap()))
+            .unwrap_or(true)
+    };
+    assert!(matches("1.2.0", "1.2.0"));
+    assert!(matches("1.2", "2.0.0"));
+    assert!(matches("v1.2", "1.3.1"));
+    assert!(!matches("1.2.0", "1.1.9"));
+    assert!(matches("^1.2", "1.9.0"));
+    assert!(!matches("^1.2", "2.0.0"));
+    assert!(!matches(">=1.0, <2.0", "2.1.0"));
+    assert!(matches("*", "0.1.0"));
+    assert!(dep("not a version").requirement().is_none());
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+#[test]
+fn topological_sort() {
+    // Unconstrained mods keep their order
+    assert_eq!(topological_order(4, &[]).unwrap(), vec![0, 1, 2, 3]);
+    // A dependent ahead of its master moves to just after it
+    assert_eq!(topological_order(4, &[(2, 0)]).unwrap(), vec![1, 2, 0, 3]);
+    assert_eq!(topological_order(5, &[(3, 1), (1, 4)]).unwrap(), vec![
+        0, 2, 3, 1, 4
+    ]);
+    let mut cycle = topological_order(4, &[(0, 1), (1, 3), (3, 1), (2, 0)]).unwrap_err();
+    cycle.sort();
+    assert_eq!(cycle, vec![1, 3]);
+}
+
+/// Whether a file or folder looks like a mod [`convert_mod`] can convert.
+fn is_convertible(path: &Path) -> bool {
+    if path.is_dir() {
+        path.join("rules.txt").exists() || path.join("info.json").exists()
+    } else {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .map(|e| ["bnp", "zip", "7z"].contains(&e.to_lowercase().as_str()))
+            .unwrap_or(false)
+    }
+}
+
+/// Convert a BNP or a graphic pack or RomFS mod, as a 7z or ZIP archive, a
+/// folder, or its `rules.txt`, to a UKMM mod at `dest`. `dest` is either the
+/// file to write or a folder to write it to under the mod's name. No profile
+/// is changed.
+pub fn convert_mod(core: &crate::core::Manager, path: &Path, dest: &Path) -> Result<PathBuf> {
+    if ModReader::open_peek(path, vec![]).is_ok() {
+        anyhow_ext::bail!("{} is already a UKMM mod", path.display());
+    }
+    let is_bnp = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.eq_ignore_ascii_case("bnp"))
+        .unwrap_or(false)
+        || path.join("info.json").exists();
+    if is_bnp {
+        crate::bnp::convert_bnp_to(core, path, dest)
+            .with_context(|| format!("Failed to convert BNP {}", path.display()))
+    } else {
+        convert_gfx_to(core, path, None, dest)
+            .with_context(|| format!("Failed to convert {}", path.display()))
+    }
+}
+
+/// Convert every mod in a folder with [`convert_mod`], writing them to
+/// `dest_dir`. Returns each mod found with the path it was converted to,
+/// `None` if it is already a UKMM mod and so was skipped, or why it could not
+/// be converted. Each mod is written under its name, so a mod with the same
+/// name as one converted before it fails rather than replacing it.
+pub fn convert_mods(
+    core: &crate::core::Manager,
+    dir: &Path,
+    dest_dir: &Path,
+) -> Result<Vec<(PathBuf, Result<Option<PathBuf>>)>> {
+    let mut paths = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    paths.retain(|path| is_convertible(path));
+    paths.sort();
+    fs::create_dir_all(dest_dir)?;
+    let mut written: HashMap<PathBuf, PathBuf> = HashMap::default();
+    Ok(paths
+        .into_iter()
+        .map(|path| {
+            if ModReader::open_peek(&path, vec![]).is_ok() {
+                log::info!("Skipping {}, which is already a UKMM mod", path.display());
+                return (path, Ok(None));
+            }
+            // Each mod is converted on its own first, since where it goes
+            // depends on its name.
+            let result = (|| {
+                let temp = util::get_temp_folder().clone();
+                let converted = convert_mod(core, &path, &temp)?;
+                let dest = dest_dir.join(
+                    converted
+                        .file_name()
+                        .context("Converted mod has no file name")?,
+                );
+                if let Some(other) = written.get(&dest) {
+                    anyhow_ext::bail!(
+                        "{} has the same name as {}, which was already converted to {}",
+                        path.display(),
+                        other.display(),
+                        dest.display()
+                    );
+                }
+                fs::copy(&converted, &dest)?;
+                written.insert(dest.clone(), path.clone());
+                Ok(Some(dest))
+            })();
+            if let Err(e) = &result {
+                log::error!("{e:?}");
+            }
+            (path, result)
+        })
+        .collect())
+}
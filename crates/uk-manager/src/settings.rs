@@ -98,8 +98,18 @@ impl From<Platform> for rstb::Endian {
         }
     }
 }
+#[inline]
+fn default_target_name() -> String {
+    "Default".into()
+}
+
+/// One place to deploy the merged mods of a platform to, such as an emulator
+/// mod folder or an SD card.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DeployConfig {
+    /// Unique name of the deploy target, used to track its pending changes.
+    #[serde(default = "default_target_name")]
+    pub name: String,
     pub output: PathBuf,
     pub method: DeployMethod,
     pub auto: bool,
@@ -109,38 +119,67 @@ pub struct DeployConfig {
     pub executable: Option<std::string::String>,
     #[serde(default)]
     pub layout: DeployLayout,
+    /// Decide which files to redeploy by comparing their contents instead of
+    /// their modification times.
+    #[serde(default)]
+    pub content_hash: bool,
 }
 
 impl DeployConfig {
     pub fn final_output_paths(&self, endian: Endian) -> (PathBuf, PathBuf) {
+        self.output_paths_in(&self.output, endian)
+    }
+
+    /// Path of the archive written by the archive deploy method.
+    #[inline]
+    pub fn archive_path(&self) -> PathBuf {
+        self.output.join("BreathOfTheWild_UKMM.zip")
+    }
+
+    /// Folders for the base game and DLC files inside the deploy archive.
+    /// With the SD root layout they start from `atmosphere/contents`, so the
+    /// archive can be extracted straight onto the root of an SD card.
+    #[inline]
+    pub fn archive_paths(&self, endian: Endian) -> (PathBuf, PathBuf) {
+        self.output_paths_in(Path::new(""), endian)
+    }
+
+    fn output_paths_in(&self, root: &Path, endian: Endian) -> (PathBuf, PathBuf) {
         match endian {
             Endian::Little => {
                 match self.layout {
                     DeployLayout::WithoutName => (
-                        self.output.join("01007EF00011E000").join("romfs"),
-                        self.output.join("01007EF00011F001").join("romfs"),
+                        root.join("01007EF00011E000").join("romfs"),
+                        root.join("01007EF00011F001").join("romfs"),
                     ),
                     DeployLayout::WithName => (
-                        self.output
+                        root
                             .join("01007EF00011E000")
                             .join("BreathOfTheWild_UKMM")
                             .join("romfs"),
-                        self.output
+                        root
                             .join("01007EF00011F001")
                             .join("BreathOfTheWild_UKMM")
                             .join("romfs"),
                     ),
+                    DeployLayout::SdRoot => {
+                        let contents = root.join("atmosphere").join("contents");
+                        (
+                            contents.join("01007EF00011E000").join("romfs"),
+                            contents.join("01007EF00011F001").join("romfs"),
+                        )
+                    }
                 }
             }
             Endian::Big => {
                 match self.layout {
-                    DeployLayout::WithoutName => (
-                        self.output.join("content"),
-                        self.output.join("aoc").join("0010"),
+                    DeployLayout::WithoutName | DeployLayout::SdRoot => (
+                        root.join("content"),
+                        root.join("aoc").join("0010"),
                     ),
                     DeployLayout::WithName => (
-                        self.output.join("BreathOfTheWild_UKMM").join("content"),
-                        self.output
+                        root.join("BreathOfTheWild_UKMM").join("content"),
+                        root
                             .join("BreathOfTheWild_UKMM")
                             .join("aoc")
                             .join("0010"),
@@ -149,17 +188,67 @@ impl DeployConfig {
             }
         }
     }
+
+    /// Remove everything deployed to this target.
+    pub fn wipe_output(&self, endian: Endian) -> Result<()> {
+        if self.method == DeployMethod::Archive {
+            let archive = self.archive_path();
+            if archive.exists() {
+                fs::remove_file(archive)?;
+            }
+            return Ok(());
+        }
+        let (content, aoc) = self.final_output_paths(endian);
+        if util::is_symlink(content.as_ref()) {
+            util::remove_symlink(content)?;
+        } else if content.exists() {
+            util::remove_dir_all(content)?;
+        }
+        if util::is_symlink(aoc.as_ref()) {
+            util::remove_symlink(aoc)?;
+        } else if aoc.exists() {
+            util::remove_dir_all(aoc)?;
+        }
+        self.clean_title_dirs(endian)
+    }
+
+    /// With the SD root layout, remove the game and DLC title folders once
+    /// they no longer contain any files. Title folders holding anything else,
+    /// such as exefs patches, are left alone.
+    pub fn clean_title_dirs(&self, endian: Endian) -> Result<()> {
+        if endian != Endian::Little || self.layout != DeployLayout::SdRoot {
+            return Ok(());
+        }
+        let (content, aoc) = self.final_output_paths(endian);
+        for romfs in [content, aoc] {
+            if util::is_symlink(&romfs) {
+                continue;
+            }
+            if romfs.exists() && !util::has_files(&romfs) {
+                log::info!("Removing empty folder {}", romfs.display());
+                util::remove_dir_all(&romfs)?;
+            }
+            let title = romfs.parent().unwrap();
+            if title.exists() && !util::has_files(title) {
+                log::info!("Removing stale title folder {}", title.display());
+                util::remove_dir_all(title)?;
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Default for DeployConfig {
     fn default() -> Self {
         DeployConfig {
+            name: default_target_name(),
             output: "".into(),
             method: DeployMethod::Copy,
             auto: false,
             cemu_rules: false,
             executable: None,
             layout: DeployLayout::WithoutName,
+            content_hash: false,
         }
     }
 }
@@ -169,6 +258,11 @@ pub enum DeployMethod {
     Copy,
     HardLink,
     Symlink,
+    /// Clone files using copy-on-write where the filesystem supports it
+    /// (e.g. btrfs, XFS, APFS, or ReFS), falling back to copying them.
+    Reflink,
+    /// Pack the merged files into a single zip archive in the output folder.
+    Archive,
 }
 
 impl DeployMethod {
@@ -178,6 +272,8 @@ impl DeployMethod {
             DeployMethod::Copy => "Settings_Platform_Deploy_Method_Copy",
             DeployMethod::HardLink => "Settings_Platform_Deploy_Method_HardLink",
             DeployMethod::Symlink => "Settings_Platform_Deploy_Method_Symlink",
+            DeployMethod::Reflink => "Settings_Platform_Deploy_Method_Reflink",
+            DeployMethod::Archive => "Settings_Platform_Deploy_Method_Archive",
         }
     }
 }
@@ -186,7 +282,10 @@ impl DeployMethod {
 pub enum DeployLayout {
     #[default]
     WithoutName,
-    WithName
+    WithName,
+    /// The full `atmosphere/contents/<title ID>/romfs` tree, for deploying
+    /// straight to the root of a Switch SD card.
+    SdRoot,
 }
 
 impl DeployLayout {
@@ -195,16 +294,81 @@ impl DeployLayout {
         match self {
             DeployLayout::WithoutName => "SD Card",
             DeployLayout::WithName => "Emulator",
+            DeployLayout::SdRoot => "SD Card Root",
         }
     }
 }
 
+/// Deploy targets as saved now, or the single optional deploy config saved by
+/// older versions.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DeployTargets {
+    Many(Vec<DeployConfig>),
+    One(Option<DeployConfig>),
+}
+
+fn deserialize_targets<'de, D>(deserializer: D) -> std::result::Result<Vec<DeployConfig>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(match DeployTargets::deserialize(deserializer)? {
+        DeployTargets::Many(targets) => targets,
+        DeployTargets::One(target) => target.into_iter().collect(),
+    })
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PlatformSettings {
     pub language: Language,
     pub profile: String,
     pub dump: Arc<ResourceReader>,
-    pub deploy_config: Option<DeployConfig>,
+    /// Every place the merged mods are deployed to, each with its own pending
+    /// changes. The first target is used by default.
+    #[serde(
+        default,
+        alias = "deploy_config",
+        deserialize_with = "deserialize_targets"
+    )]
+    pub deploy_targets: Vec<DeployConfig>,
+}
+
+impl PlatformSettings {
+    /// The default deploy target, if any are configured.
+    #[inline]
+    pub fn deploy_config(&self) -> Option<&DeployConfig> {
+        self.deploy_targets.first()
+    }
+
+    #[inline]
+    pub fn deploy_target(&self, name: &str) -> Option<&DeployConfig> {
+        self.deploy_targets.iter().find(|t| t.name.as_str() == name)
+    }
+}
+
+/// Check that every deploy target has a name, and that no two targets would
+/// share pending changes or a deploy journal. Journals are kept in folders
+/// named after their targets with any characters file names cannot have
+/// replaced, so names are compared that way, ignoring case.
+pub fn validate_deploy_targets(targets: &[DeployConfig]) -> Result<()> {
+    let folder = |target: &DeployConfig| uk_mod::pack::sanitise(&target.name).to_lowercase();
+    for (i, target) in targets.iter().enumerate() {
+        if target.name.trim().is_empty() {
+            anyhow_ext::bail!("Deploy targets must have a name");
+        }
+        if let Some(other) = targets[..i].iter().find(|t| folder(t) == folder(target)) {
+            if other.name == target.name {
+                anyhow_ext::bail!("There is more than one deploy target named {}", target.name);
+            }
+            anyhow_ext::bail!(
+                "The deploy targets {} and {} are too similar. Names must still differ when case \
+                 and characters not allowed in file names are ignored.",
+                other.name,
+                target.name
+            );
+        }
+    }
+    Ok(())
 }
 
 #[inline]
@@ -239,6 +403,7 @@ pub struct Settings {
     pub wiiu_config: Option<PlatformSettings>,
     pub switch_config: Option<PlatformSettings>,
     pub lang: LocLang,
+    pub rehash_map_objects: bool,
 }
 
 impl Default for Settings {
@@ -253,6 +418,7 @@ impl Default for Settings {
             show_changelog: true,
             last_version: None,
             lang: LocLang::English,
+            rehash_map_objects: false,
         }
     }
 }
@@ -334,36 +500,6 @@ impl Settings {
         Ok(())
     }
 
-    pub fn wipe_output(&self, endian: Endian) -> Result<()> {
-        let (content, aoc) = match endian {
-            Endian::Big => self.wiiu_config
-                .as_ref()
-                .unwrap()
-                .deploy_config
-                .as_ref()
-                .unwrap()
-                .final_output_paths(endian),
-            Endian::Little => self.switch_config
-                .as_ref()
-                .unwrap()
-                .deploy_config
-                .as_ref()
-                .unwrap()
-                .final_output_paths(endian),
-        };
-        if util::is_symlink(content.as_ref()) {
-            util::remove_symlink(content)?;
-        } else if content.exists() {
-            util::remove_dir_all(content)?;
-        }
-        if util::is_symlink(aoc.as_ref()) {
-            util::remove_symlink(aoc)?;
-        } else if aoc.exists() {
-            util::remove_dir_all(aoc)?;
-        }
-        Ok(())
-    }
-
     #[inline]
     pub fn platform_dir(&self) -> PathBuf {
         self.get_platform_dir(self.current_mode)
@@ -445,7 +581,7 @@ impl Settings {
     pub fn deploy_dir(&self) -> Option<&Path> {
         let config = self.platform_config();
         config
-            .and_then(|c| c.deploy_config.as_ref())
+            .and_then(|c| c.deploy_config())
             .map(|c| c.output.as_ref())
     }
 
@@ -459,3 +595,66 @@ impl Settings {
         self.storage_dir.join("projects")
     }
 }
+
+#[cfg(test)]
+#[test]
+fn title_dir_cleanup() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = DeployConfig {
+        output: dir.path().to_path_buf(),
+        layout: DeployLayout::SdRoot,
+        ..Default::default()
+    };
+    let (content, aoc) = config.final_output_paths(Endian::Little);
+    assert_eq!(
+        content,
+        dir.path()
+            .join("atmosphere/contents/01007EF00011E000/romfs")
+    );
+    let exefs = content.parent().unwrap().join("exefs");
+    fs::create_dir_all(content.join("Actor")).unwrap();
+    fs::create_dir_all(&exefs).unwrap();
+    fs::write(exefs.join("main.npdm"), b"UKMM").unwrap();
+    fs::create_dir_all(aoc.join("Pack")).unwrap();
+    fs::write(aoc.join("Pack/AocMainField.pack"), b"UKMM").unwrap();
+    config.clean_title_dirs(Endian::Little).unwrap();
+    // The empty romfs goes, but the exefs patch keeps its title folder
+    assert!(!content.exists());
+    assert!(exefs.join("main.npdm").exists());
+    assert!(aoc.join("Pack/AocMainField.pack").exists());
+
+    fs::remove_file(aoc.join("Pack/AocMainField.pack")).unwrap();
+    config.clean_title_dirs(Endian::Little).unwrap();
+    assert!(!aoc.parent().unwrap().exists());
+
+    // Other layouts deploy into folders of their own
+    let config = DeployConfig {
+        layout: DeployLayout::WithoutName,
+        ..config
+    };
+    let (content, _) = config.final_output_paths(Endian::Little);
+    fs::create_dir_all(&content).unwrap();
+    config.clean_title_dirs(Endian::Little).unwrap();
+    assert!(content.exists());
+}
+
+#[cfg(test)]
+#[test]
+fn deploy_target_names() {
+    let targets = |names: &[&str]| {
+        names
+            .iter()
+            .map(|name| {
+                DeployConfig {
+                    name: (*name).into(),
+                    ..Default::default()
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+    assert!(validate_deploy_targets(&targets(&["Cemu", "SD card"])).is_ok());
+    assert!(validate_deploy_targets(&targets(&["Cemu", " "])).is_err());
+    assert!(validate_deploy_targets(&targets(&["Cemu", "Cemu"])).is_err());
+    assert!(validate_deploy_targets(&targets(&["a/b", "a_b"])).is_err());
+    assert!(validate_deploy_targets(&targets(&["SD", "sd"])).is_err());
+}
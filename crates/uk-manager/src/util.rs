@@ -31,6 +31,14 @@ pub fn remove_dir_all(dir: impl AsRef<std::path::Path>) -> anyhow_ext::Result<()
     inner(dir.as_ref())
 }
 
+/// Whether there is anything other than empty folders inside a folder.
+pub fn has_files(dir: &Path) -> bool {
+    jwalk::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .any(|entry| !entry.file_type().is_dir())
+}
+
 #[inline(always)]
 pub fn is_symlink(link: &Path) -> bool {
     #[cfg(windows)]
@@ -0,0 +1,477 @@
+use std::{
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow_ext::{Context, Result};
+use fs_err as fs;
+use join_str::jstr;
+use roead::{
+    byml::Byml,
+    sarc::{Sarc, SarcWriter},
+    yaz0::{compress, decompress_if},
+};
+use serde::Serialize;
+use smartstring::alias::String;
+use uk_content::{
+    message::{Endianness, Msyt},
+    prelude::Endian,
+    resource::{MergeableResource, ResourceData},
+};
+use zip::{write::SimpleFileOptions, ZipArchive, ZipWriter};
+
+use crate::{Meta, ModPlatform};
+
+/// A file in a mod which could not be converted to another platform.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Unconvertible {
+    /// Canonical path of the file. Files in a SARC are listed as the path of
+    /// the SARC and the name of the file, separated by `//`.
+    pub file:   String,
+    pub reason: String,
+}
+
+/// What converting a mod to another platform did.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversionReport {
+    pub output: PathBuf,
+    pub platform: ModPlatform,
+    /// Binary files whose byte order was converted.
+    pub converted: Vec<String>,
+    /// Binary files which are copied unchanged and will most likely not work
+    /// on the new platform. They need to be replaced by hand.
+    pub unconvertible: Vec<Unconvertible>,
+}
+
+impl ConversionReport {
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        self.unconvertible.is_empty()
+    }
+}
+
+enum Conversion {
+    /// The file does not depend on the platform.
+    Same,
+    Converted(Vec<u8>),
+    Failed(&'static str),
+}
+
+/// Convert a single binary file to the byte order of another platform,
+/// recursing into SARCs. Files in a SARC which cannot be converted are added
+/// to `unconvertible` and the rest of the SARC is still converted.
+fn convert_binary(
+    name: &str,
+    data: &[u8],
+    endian: Endian,
+    unconvertible: &mut Vec<Unconvertible>,
+) -> Result<Conversion> {
+    let extension = name.rsplit('.').next().unwrap_or_default();
+    let magic = data.get(..4).unwrap_or_default();
+    Ok(match magic {
+        b"BY\0\x02" | b"YB\x02\0" | b"BY\0\x03" | b"YB\x03\0" => {
+            Conversion::Converted(Byml::from_binary(data)?.to_binary(endian.into()))
+        }
+        // AAMP files are little endian on both platforms.
+        b"AAMP" => Conversion::Same,
+        b"SARC" => {
+            let sarc = Sarc::new(data)?;
+            let mut writer =
+                SarcWriter::new(endian.into()).with_min_alignment(sarc.guess_min_alignment());
+            for file in sarc.files() {
+                let file_name = file.name().context("SARC file missing name")?;
+                let path = jstr!("{name}//{file_name}");
+                let file_data = decompress_if(file.data);
+                let converted = convert_binary(&path, &file_data, endian, unconvertible)
+                    .with_context(|| jstr!("Failed to convert {&path}"))?;
+                let file_data = match converted {
+                    Conversion::Converted(data) if file.data.starts_with(b"Yaz0") => compress(data),
+                    Conversion::Converted(data) => data,
+                    Conversion::Same => file.data.to_vec(),
+                    Conversion::Failed(reason) => {
+                        unconvertible.push(Unconvertible {
+                            file:   path,
+                            reason: reason.into(),
+                        });
+                        file.data.to_vec()
+                    }
+                };
+                writer.add_file(file_name, file_data);
+            }
+            Conversion::Converted(writer.to_binary())
+        }
+        b"MsgS" if data.starts_with(b"MsgStdBn") => {
+            Conversion::Converted(Msyt::from_msbt_bytes(data)?.into_msbt_bytes(match endian {
+                Endian::Little => Endianness::Little,
+                Endian::Big => Endianness::Big,
+            })?)
+        }
+        b"BARS" => {
+            match convert_bars(name, data, endian, unconvertible) {
+                Some(data) => Conversion::Converted(data),
+                None => Conversion::Failed("Sound archive could not be read"),
+            }
+        }
+        b"FRES" => {
+            Conversion::Failed(
+                "Models and textures use different graphics formats on each platform",
+            )
+        }
+        b"FSTM" | b"BSTM" | b"FWAV" | b"BWAV" => {
+            Conversion::Failed("Audio is encoded separately for each platform")
+        }
+        _ if extension
+            .strip_prefix('s')
+            .unwrap_or(extension)
+            .starts_with("hk") =>
+        {
+            Conversion::Failed("Havok physics data is built separately for each platform")
+        }
+        _ if name.contains("tera_resource") => {
+            Conversion::Failed("Shaders are compiled separately for each platform")
+        }
+        _ if data.is_empty() => Conversion::Same,
+        _ => Conversion::Failed("Unknown file format"),
+    })
+}
+
+/// Reverse the byte order of a field in place.
+fn swap_field(data: &mut [u8], offset: usize, size: usize) -> Option<()> {
+    data.get_mut(offset..offset + size)?.reverse();
+    Some(())
+}
+
+fn read_u32(data: &[u8], offset: usize, big: bool) -> Option<usize> {
+    let bytes: [u8; 4] = data.get(offset..offset + 4)?.try_into().ok()?;
+    Some(if big {
+        u32::from_be_bytes(bytes)
+    } else {
+        u32::from_le_bytes(bytes)
+    } as usize)
+}
+
+/// Convert the byte order of a sound archive's header and of the AMTA
+/// metadata of each of its sounds. The audio streams embedded in it are
+/// encoded for one platform, so they are copied unchanged and added to
+/// `unconvertible` by the names of their sounds. Returns `None` if the
+/// archive is not laid out as expected.
+///
+/// The header is followed by a hash for each sound, then the offsets of each
+/// sound's AMTA and audio stream. An AMTA has offsets to its `DATA`, `MARK`,
+/// `EXT_`, and `STRG` sections, each of which is a magic and a size followed
+/// by 32-bit fields, except for the sound's name in `STRG` and a few single
+/// bytes in `DATA`.
+fn convert_bars(
+    name: &str,
+    data: &[u8],
+    endian: Endian,
+    unconvertible: &mut Vec<Unconvertible>,
+) -> Option<Vec<u8>> {
+    let big = match data.get(8..10)? {
+        [0xFE, 0xFF] => true,
+        [0xFF, 0xFE] => false,
+        _ => return None,
+    };
+    let mut out = data.to_vec();
+    if big == (endian == Endian::Big) {
+        return Some(out);
+    }
+    let count = read_u32(data, 12, big)?;
+    // File size, byte order mark, version, and sound count
+    for (offset, size) in [(4, 4), (8, 2), (10, 2), (12, 4)] {
+        swap_field(&mut out, offset, size)?;
+    }
+    // Sound hashes and offsets
+    for i in 0..count * 3 {
+        swap_field(&mut out, 16 + i * 4, 4)?;
+    }
+    let table = 16 + count * 4;
+    // AMTAs and their sections, which sounds could share
+    let mut converted = std::collections::BTreeSet::new();
+    for i in 0..count {
+        let amta = read_u32(data, table + i * 8, big)?;
+        let audio = read_u32(data, table + i * 8 + 4, big)?;
+        if data.get(amta..amta + 4)? != b"AMTA" {
+            return None;
+        }
+        let mut sound = String::new();
+        for (j, magic) in [b"DATA", b"MARK", b"EXT_", b"STRG"].into_iter().enumerate() {
+            let section = amta + read_u32(data, amta + 12 + j * 4, big)?;
+            if data.get(section..section + 4)? != magic {
+                return None;
+            }
+            let size = read_u32(data, section + 4, big)?;
+            let payload = data.get(section + 8..section + 8 + size)?;
+            let first = converted.insert(section);
+            if first {
+                swap_field(&mut out, section + 4, 4)?;
+            }
+            if magic == b"STRG" {
+                sound = std::str::from_utf8(payload.split(|b| *b == 0).next()?)
+                    .ok()?
+                    .into();
+            } else if first {
+                for word in 0..size / 4 {
+                    // The sound's type, channel count, stream track count,
+                    // and flags are single bytes.
+                    if magic == b"DATA" && word == 2 {
+                        continue;
+                    }
+                    swap_field(&mut out, section + 8 + word * 4, 4)?;
+                }
+            }
+        }
+        if converted.insert(amta) {
+            // Byte order mark, version, size, and section offsets
+            for (offset, size) in [(4, 2), (6, 2), (8, 4), (12, 4), (16, 4), (20, 4), (24, 4)] {
+                swap_field(&mut out, amta + offset, size)?;
+            }
+        }
+        if matches!(data.get(audio..audio + 4), Some(b"FWAV" | b"BWAV")) {
+            unconvertible.push(Unconvertible {
+                file:   jstr!("{name}//{&sound}"),
+                reason: "Audio is encoded separately for each platform".into(),
+            });
+        }
+    }
+    Some(out)
+}
+
+/// Convert a packaged mod for one platform to the other platform, writing
+/// the converted mod to `dest`.
+///
+/// Mergeable resources are stored independently of the platform and are
+/// serialized for the right one when deployed, and files in a mod are
+/// stored by their canonical paths rather than under the content and DLC
+/// folders of a platform, so only the mod's meta and its binary resources
+/// need to change. Binary resources in a known format with a byte order
+/// (BYML, SARC, MSBT, and the metadata in sound archives) are converted.
+/// Everything else is copied unchanged and listed in the report, as it will
+/// need to be replaced to work on the new platform.
+pub fn convert_platform(source: &Path, dest: &Path, endian: Endian) -> Result<ConversionReport> {
+    if source.is_dir() {
+        anyhow_ext::bail!("Only packaged mods can be converted to another platform");
+    }
+    let mut zip = ZipArchive::new(fs::File::open(source)?)
+        .with_context(|| jstr!("Failed to open mod at {&source.display().to_string()}"))?;
+    let mut meta: Meta = {
+        let mut file = zip.by_name("meta.yml").context("Mod missing meta file")?;
+        let mut meta = std::string::String::new();
+        file.read_to_string(&mut meta)?;
+        serde_yaml::from_str(&meta).context("Failed to parse meta file from mod")?
+    };
+    match meta.platform {
+        ModPlatform::Universal => {
+            anyhow_ext::bail!("{} already works on any platform", meta.name)
+        }
+        ModPlatform::Specific(current) if current == endian => {
+            anyhow_ext::bail!("{} is already a {} mod", meta.name, meta.platform)
+        }
+        ModPlatform::Specific(_) => meta.platform = ModPlatform::Specific(endian),
+    }
+    log::info!("Converting {} to a {} mod", meta.name, meta.platform);
+
+    let mut compressor = zstd::bulk::Compressor::with_dictionary(8, super::DICTIONARY)?;
+    let mut decompressor = zstd::bulk::Decompressor::with_dictionary(super::DICTIONARY)?;
+    let opts = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    let mut out = ZipWriter::new(fs::File::create(dest)?);
+    let mut report = ConversionReport {
+        output: dest.to_path_buf(),
+        platform: meta.platform,
+        converted: vec![],
+        unconvertible: vec![],
+    };
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        if file.is_dir() {
+            continue;
+        }
+        let name = file.name().to_owned();
+        let mut data = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut data)?;
+        drop(file);
+        out.start_file(name.as_str(), opts)?;
+        if name == "meta.yml" {
+            out.write_all(serde_yaml::to_string(&meta)?.as_bytes())?;
+            continue;
+        } else if name.ends_with("manifest.yml") || name.starts_with("thumb.") {
+            out.write_all(&data)?;
+            continue;
+        }
+
+        // Files in options are stored under `options/<option>/`, followed by
+        // the canonical path.
+        let canon = match name.strip_prefix("options/") {
+            Some(rest) => rest.split_once('/').map(|(_, c)| c).unwrap_or(rest),
+            None => name.as_str(),
+        };
+        let size = zstd::bulk::Decompressor::upper_bound(&data).unwrap_or(data.len() * 1024);
+        let data = decompressor
+            .decompress(&data, size)
+            .or_else(|e| zstd::decode_all(data.as_slice()).context(e))
+            .with_context(|| jstr!("Failed to decompress {&name}"))?;
+        let resource: ResourceData = minicbor_ser::from_slice(&data)
+            .map_err(|e| anyhow::format_err!("{:?}", e))
+            .with_context(|| jstr!("Failed to parse {&name}"))?;
+        let binary = match &resource {
+            ResourceData::Binary(data) => Some(data.as_slice()),
+            ResourceData::Mergeable(MergeableResource::BinaryOverride(bin)) => {
+                Some(bin.0.as_slice())
+            }
+            _ => None,
+        };
+        let resource = match binary {
+            Some(binary) => {
+                let converted = convert_binary(canon, binary, endian, &mut report.unconvertible)
+                    .with_context(|| jstr!("Failed to convert {canon}"))?;
+                match (converted, resource) {
+                    (Conversion::Converted(data), ResourceData::Binary(_)) => {
+                        report.converted.push(canon.into());
+                        ResourceData::Binary(data)
+                    }
+                    (
+                        Conversion::Converted(data),
+                        ResourceData::Mergeable(MergeableResource::BinaryOverride(bin)),
+                    ) => {
+                        report.converted.push(canon.into());
+                        ResourceData::Mergeable(MergeableResource::BinaryOverride(Box::new((
+                            data, bin.1,
+                        ))))
+                    }
+                    (Conversion::Failed(reason), resource) => {
+                        report.unconvertible.push(Unconvertible {
+                            file:   canon.into(),
+                            reason: reason.into(),
+                        });
+                        resource
+                    }
+                    (_, resource) => resource,
+                }
+            }
+            None => resource,
+        };
+        let data = minicbor_ser::to_vec(&resource)
+            .map_err(|e| anyhow::format_err!("{:?}", e))
+            .with_context(|| jstr!("Failed to serialize {&name}"))?;
+        out.write_all(&compressor.compress(&data)?)?;
+    }
+    out.finish()?;
+    if !report.unconvertible.is_empty() {
+        log::warn!(
+            "{} files in {} could not be converted:\n{}",
+            report.unconvertible.len(),
+            meta.name,
+            report
+                .unconvertible
+                .iter()
+                .map(|u| jstr!("{&u.file}: {&u.reason}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+    }
+    log::info!("Converted {} to a {} mod", meta.name, meta.platform);
+    Ok(report)
+}
+
+#[cfg(test)]
+#[test]
+fn convert_sarc() {
+    let byml = Byml::Map([("Key".into(), Byml::I32(1))].into_iter().collect());
+    let sarc = SarcWriter::new(roead::Endian::Big)
+        .with_files([
+            ("Map.byml", byml.to_binary(roead::Endian::Big)),
+            ("Model.bfres", b"FRES    ".to_vec()),
+        ])
+        .to_binary();
+    let mut unconvertible = vec![];
+    let Conversion::Converted(data) =
+        convert_binary("Pack/Test.sarc", &sarc, Endian::Little, &mut unconvertible).unwrap()
+    else {
+        panic!("SARC was not converted");
+    };
+    assert_eq!(unconvertible, vec![Unconvertible {
+        file:   "Pack/Test.sarc//Model.bfres".into(),
+        reason: "Models and textures use different graphics formats on each platform".into(),
+    }]);
+    let sarc = Sarc::new(&data).unwrap();
+    assert_eq!(sarc.endian(), roead::Endian::Little);
+    let map = sarc.get_data("Map.byml").unwrap();
+    assert!(map.starts_with(b"YB"));
+    assert_eq!(Byml::from_binary(map).unwrap(), byml);
+}
+
+#[cfg(test)]
+#[test]
+fn convert_bars_metadata() {
+    let mut bars = vec![];
+    let mut push = |bytes: &[u8]| bars.extend_from_slice(bytes);
+    // Header, one sound's hash, and the offsets of its AMTA and audio
+    push(b"BARS");
+    push(&128u32.to_be_bytes());
+    push(&[0xFE, 0xFF, 0x01, 0x01]);
+    push(&1u32.to_be_bytes());
+    push(&0x12345678u32.to_be_bytes());
+    push(&28u32.to_be_bytes());
+    push(&120u32.to_be_bytes());
+    // AMTA header and section offsets
+    push(b"AMTA");
+    push(&[0xFE, 0xFF, 0x04, 0x00]);
+    for value in [92u32, 28, 52, 64, 76] {
+        push(&value.to_be_bytes());
+    }
+    push(b"DATA");
+    push(&16u32.to_be_bytes());
+    push(&0u32.to_be_bytes());
+    push(&7u32.to_be_bytes());
+    push(&[1, 2, 1, 0]);
+    push(&1.0f32.to_be_bytes());
+    push(b"MARK");
+    push(&4u32.to_be_bytes());
+    push(&0u32.to_be_bytes());
+    push(b"EXT_");
+    push(&4u32.to_be_bytes());
+    push(&0u32.to_be_bytes());
+    push(b"STRG");
+    push(&8u32.to_be_bytes());
+    push(b"Test\0\0\0\0");
+    push(b"FWAV\xFE\xFF\x01\x00");
+    assert_eq!(bars.len(), 128);
+
+    let mut unconvertible = vec![];
+    let Conversion::Converted(data) = convert_binary(
+        "Sound/Resource/Test.bars",
+        &bars,
+        Endian::Little,
+        &mut unconvertible,
+    )
+    .unwrap() else {
+        panic!("Sound archive was not converted");
+    };
+    assert_eq!(unconvertible, vec![Unconvertible {
+        file:   "Sound/Resource/Test.bars//Test".into(),
+        reason: "Audio is encoded separately for each platform".into(),
+    }]);
+    assert_eq!(&data[8..10], &[0xFF, 0xFE]);
+    assert_eq!(read_u32(&data, 16, false), Some(0x12345678));
+    assert_eq!(read_u32(&data, 20, false), Some(28));
+    assert_eq!(read_u32(&data, 28 + 24, false), Some(76));
+    assert_eq!(read_u32(&data, 56 + 12, false), Some(7));
+    assert_eq!(&data[56 + 16..56 + 20], &[1, 2, 1, 0]);
+    assert_eq!(&data[56 + 20..56 + 24], &1.0f32.to_le_bytes());
+    assert_eq!(&data[104 + 8..104 + 12], b"Test");
+    assert_eq!(&data[120..], &bars[120..]);
+
+    let back = convert_bars("Test.bars", &data, Endian::Big, &mut vec![]).unwrap();
+    assert_eq!(back, bars);
+
+    assert!(matches!(
+        convert_binary(
+            "Physics/Test.shksc",
+            b"\x57\xE0\xE0\x57",
+            Endian::Little,
+            &mut vec![]
+        )
+        .unwrap(),
+        Conversion::Failed("Havok physics data is built separately for each platform")
+    ));
+}
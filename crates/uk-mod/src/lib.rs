@@ -12,8 +12,10 @@ use uk_content::{
     prelude::Endian,
     util::{HashSet, IndexMap},
 };
+pub mod convert;
 pub mod pack;
 pub mod unpack;
+pub mod validate;
 pub use zstd;
 
 static DICTIONARY: &[u8] = include_bytes!("../data/zsdic");
@@ -66,6 +68,9 @@ pub struct ModOption {
     pub name: String,
     pub description: String,
     pub path: PathBuf,
+    /// Paths of other options in the same mod which must be enabled for this
+    /// one to work.
+    #[serde(default)]
     pub requires: Vec<PathBuf>,
 }
 
@@ -368,6 +373,14 @@ pub struct Meta {
     #[serde(rename = "option_groups")]
     pub options: Vec<OptionGroup>,
     pub masters: IndexMap<usize, (String, String)>,
+    /// Names of mods this mod should be loaded after, and so take priority
+    /// over, when sorting the load order automatically.
+    #[serde(default)]
+    pub load_after: Vec<String>,
+    /// Names of mods this mod should be loaded before when sorting the load
+    /// order automatically.
+    #[serde(default)]
+    pub load_before: Vec<String>,
 }
 
 #[allow(clippy::derived_hash_with_manual_eq)]
@@ -402,12 +415,206 @@ impl Meta {
             .context("Failed to read meta file")
             .and_then(|s| serde_yaml::from_str(&s).context("Failed to parse meta file"))
     }
+
+    /// Iterate every option in all of the mod's option groups.
+    pub fn all_options(&self) -> impl Iterator<Item = &ModOption> {
+        self.options.iter().flat_map(|group| group.options().iter())
+    }
+
+    #[inline]
+    pub fn get_option(&self, path: impl AsRef<Path>) -> Option<&ModOption> {
+        let path = path.as_ref();
+        self.all_options().find(|opt| opt.path == path)
+    }
+
+    /// The options required by an option, taken from the mod's own option
+    /// definitions when available, since stored selections may predate them.
+    fn requirements<'a>(&'a self, option: &'a ModOption) -> &'a [PathBuf] {
+        self.get_option(&option.path)
+            .map(|opt| opt.requires.as_slice())
+            .unwrap_or(option.requires.as_slice())
+    }
+
+    /// Checks that every option requirement names another option of this mod.
+    pub fn validate_requirements(&self) -> anyhow_ext::Result<()> {
+        for option in self.all_options() {
+            for required in &option.requires {
+                if required == &option.path {
+                    anyhow_ext::bail!("Option {} requires itself", option.name);
+                } else if self.get_option(required).is_none() {
+                    anyhow_ext::bail!(
+                        "Option {} requires {}, which is not an option of this mod",
+                        option.name,
+                        required.display()
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every option required by the selected options is also
+    /// selected.
+    pub fn check_requirements(&self, enabled: &[ModOption]) -> anyhow_ext::Result<()> {
+        for option in enabled {
+            for required in self.requirements(option) {
+                if !enabled.iter().any(|opt| &opt.path == required) {
+                    anyhow_ext::bail!(
+                        "Option {} of {} requires option {}, which is not enabled",
+                        option.name,
+                        self.name,
+                        self.get_option(required)
+                            .map(|opt| opt.name.as_str())
+                            .unwrap_or_else(|| required.to_str().unwrap_or_default())
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the selected options include at most one option from each
+    /// exclusive group and at least one from each required group.
+    pub fn check_options(&self, enabled: &[ModOption]) -> anyhow_ext::Result<()> {
+        for group in &self.options {
+            let selected = group
+                .options()
+                .iter()
+                .filter(|opt| enabled.iter().any(|e| e.path == opt.path))
+                .collect::<Vec<_>>();
+            if group.required() && selected.is_empty() {
+                anyhow_ext::bail!(
+                    "The option group {} of {} requires a selection, one of: {}",
+                    group.name(),
+                    self.name,
+                    group
+                        .options()
+                        .iter()
+                        .map(|opt| opt.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+            if let (OptionGroup::Exclusive(group), [first, second, ..]) =
+                (group, selected.as_slice())
+            {
+                anyhow_ext::bail!(
+                    "Options {} and {} of {} cannot both be enabled, as only one option can be \
+                     selected from the group {}",
+                    first.name,
+                    second.name,
+                    self.name,
+                    group.name
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds every option required by the selected options, directly or
+    /// through other required options. Fails if a required option does not
+    /// exist, or if enabling it would select two options from an exclusive
+    /// group.
+    pub fn with_requirements(
+        &self,
+        mut enabled: Vec<ModOption>,
+    ) -> anyhow_ext::Result<Vec<ModOption>> {
+        let mut i = 0;
+        while i < enabled.len() {
+            for required in self.requirements(&enabled[i]).to_vec() {
+                if enabled.iter().any(|opt| opt.path == required) {
+                    continue;
+                }
+                let option = self.get_option(&required).with_context(|| {
+                    format!(
+                        "Option {} of {} requires {}, which is not an option of this mod",
+                        enabled[i].name,
+                        self.name,
+                        required.display()
+                    )
+                })?;
+                let exclusive = self.options.iter().find_map(|group| {
+                    match group {
+                        OptionGroup::Exclusive(group)
+                            if group.options.iter().any(|opt| opt.path == required) =>
+                        {
+                            Some(group)
+                        }
+                        _ => None,
+                    }
+                });
+                if let Some(group) = exclusive {
+                    if let Some(other) = enabled
+                        .iter()
+                        .find(|opt| group.options.iter().any(|o| o.path == opt.path))
+                    {
+                        anyhow_ext::bail!(
+                            "Option {} of {} requires {}, but {} is already selected from the \
+                             group {}",
+                            enabled[i].name,
+                            self.name,
+                            option.name,
+                            other.name,
+                            group.name
+                        );
+                    }
+                }
+                log::info!(
+                    "Enabling option {} of {}, which is required by {}",
+                    option.name,
+                    self.name,
+                    enabled[i].name
+                );
+                enabled.push(option.clone());
+            }
+            i += 1;
+        }
+        Ok(enabled)
+    }
 }
 
+/// Sample mods shared by the tests in this crate.
 #[cfg(test)]
-mod tests {
+pub(crate) mod fixtures {
     use super::*;
 
+    /// An option at `path`, named after it, which requires the options at
+    /// `requires`.
+    pub(crate) fn option(path: &str, requires: &[&str]) -> ModOption {
+        ModOption {
+            name: path.into(),
+            description: Default::default(),
+            path: path.into(),
+            requires: requires.iter().map(PathBuf::from).collect(),
+        }
+    }
+
+    /// A universal sample mod with the given option groups.
+    pub(crate) fn meta(options: Vec<OptionGroup>) -> Meta {
+        Meta {
+            api: env!("CARGO_PKG_VERSION").into(),
+            name: "Test Mod".into(),
+            description: "A sample UKMM mod".into(),
+            category: ModCategory::Other,
+            author: "Nicene Nerd".into(),
+            platform: ModPlatform::Universal,
+            url: None,
+            version: "1.0.0".into(),
+            masters: Default::default(),
+            load_after: Default::default(),
+            load_before: Default::default(),
+            options,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        fixtures::{meta, option},
+        *,
+    };
+
     #[test]
     fn read_meta() {
         dbg!(Meta::from_mod("test/wiiu.zip").unwrap());
@@ -427,9 +634,73 @@ mod tests {
                 url: None,
                 version: "1.0.0".into(),
                 masters: Default::default(),
+                load_after: Default::default(),
+                load_before: Default::default(),
                 options: Default::default(),
             })
             .unwrap()
         );
     }
+
+    #[test]
+    fn option_requirements() {
+        let meta = meta(vec![
+            OptionGroup::Multiple(MultipleOptionGroup {
+                name: "Extras".into(),
+                options: vec![
+                    option("extra", &["base"]),
+                    option("more", &["extra"]),
+                    option("hard", &["easy"]),
+                ],
+                ..Default::default()
+            }),
+            OptionGroup::Exclusive(ExclusiveOptionGroup {
+                name: "Difficulty".into(),
+                description: Default::default(),
+                required: false,
+                default: None,
+                options: vec![option("base", &[]), option("easy", &[])],
+            }),
+        ]);
+        meta.validate_requirements().unwrap();
+        let more = meta.get_option("more").unwrap().clone();
+        assert!(meta.check_requirements(&[more.clone()]).is_err());
+        let enabled = meta.with_requirements(vec![more]).unwrap();
+        assert_eq!(
+            enabled
+                .iter()
+                .map(|o| o.path.to_str().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["more", "extra", "base"]
+        );
+        meta.check_requirements(&enabled).unwrap();
+        let hard = meta.get_option("hard").unwrap().clone();
+        assert!(meta
+            .with_requirements(enabled.into_iter().chain([hard]).collect())
+            .is_err());
+    }
+
+    #[test]
+    fn option_selection() {
+        let meta = meta(vec![
+            OptionGroup::Multiple(MultipleOptionGroup {
+                name: "Extras".into(),
+                options: vec![option("extra", &[]), option("more", &[])],
+                ..Default::default()
+            }),
+            OptionGroup::Exclusive(ExclusiveOptionGroup {
+                name: "Difficulty".into(),
+                description: Default::default(),
+                required: true,
+                default: None,
+                options: vec![option("easy", &[]), option("hard", &[])],
+            }),
+        ]);
+        let get = |path: &str| meta.get_option(path).unwrap().clone();
+        meta.check_options(&[get("easy")]).unwrap();
+        meta.check_options(&[get("extra"), get("more"), get("hard")])
+            .unwrap();
+        assert!(meta.check_options(&[get("extra")]).is_err());
+        assert!(meta.check_options(&[get("easy"), get("hard")]).is_err());
+    }
 }
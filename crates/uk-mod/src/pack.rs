@@ -1,6 +1,6 @@
 use std::{
     collections::{BTreeSet, HashSet},
-    io::Write,
+    io::{Read, Write},
     path::{Path, PathBuf},
     sync::{atomic::AtomicUsize, Arc, LazyLock},
 };
@@ -22,13 +22,13 @@ use uk_content::{
     canonicalize,
     constants::Language,
     platform_prefixes,
-    prelude::{Endian, Mergeable},
-    resource::{is_mergeable_sarc, ResourceData},
+    prelude::{Endian, Mergeable, TryMergeable},
+    resource::{is_mergeable_sarc, ResourceData, SarcMap},
 };
 use uk_util::PathExt as UkPathExt;
 use zip::{
     write::{FileOptions, SimpleFileOptions},
-    ZipWriter as ZipW,
+    ZipArchive, ZipWriter as ZipW,
 };
 
 use crate::{
@@ -96,10 +96,12 @@ struct BnpOptions {
 
 #[derive(Debug, Deserialize)]
 struct BnpOption {
-    name:    String,
-    desc:    String,
-    folder:  PathBuf,
-    default: Option<bool>,
+    name:     String,
+    desc:     String,
+    folder:   PathBuf,
+    default:  Option<bool>,
+    #[serde(default)]
+    requires: Vec<PathBuf>,
 }
 
 impl From<BnpOption> for ModOption {
@@ -108,7 +110,7 @@ impl From<BnpOption> for ModOption {
             name: opt.name,
             description: opt.desc,
             path: opt.folder,
-            requires: vec![],
+            requires: opt.requires,
         }
     }
 }
@@ -183,6 +185,8 @@ impl ModPacker {
             category: crate::ModCategory::Other,
             author: Default::default(),
             masters: Default::default(),
+            load_after: Default::default(),
+            load_before: Default::default(),
             options: vec![],
             platform: if parent.join("content").exists() || parent.join("aoc").exists() {
                 ModPlatform::Specific(Endian::Big)
@@ -203,6 +207,8 @@ impl ModPacker {
             category: crate::ModCategory::Other,
             author: Default::default(),
             masters: Default::default(),
+            load_after: Default::default(),
+            load_before: Default::default(),
             options: (!info.options.multi.is_empty())
                 .then(|| multi_from_bnp_multi(info.options.multi))
                 .into_iter()
@@ -255,6 +261,8 @@ impl ModPacker {
             } else {
                 anyhow_ext::bail!("No meta info provided or meta file available");
             };
+            meta.validate_requirements()
+                .context("Invalid option requirements")?;
             let ((content_u, dlc_u), (content_nx, dlc_nx)) = (
                 platform_prefixes(Endian::Big),
                 platform_prefixes(Endian::Little),
@@ -335,9 +343,15 @@ impl ModPacker {
                     .and_then(|f| f.file_type().is_file().then(|| f.path()))
             })
             .collect::<Vec<PathBuf>>();
+        log::debug!("Resources found in root {}:\n{:#?}", root.display(), &files);
+        self.collect_files(&root, files)
+    }
+
+    /// Process the given files from `root`, returning the manifest entries
+    /// for the ones which are modded.
+    fn collect_files(&self, root: &Path, files: Vec<PathBuf>) -> Result<BTreeSet<String>> {
         let total_files = files.len();
         let current_file = AtomicUsize::new(0);
-        log::debug!("Resources found in root {}:\n{:#?}", root.display(), &files);
         Ok(files
             .into_par_iter()
             .map(|path| -> Result<Option<String>> {
@@ -417,7 +431,7 @@ impl ModPacker {
                 }
 
                 Ok(Some(
-                    path.strip_prefix(&root).unwrap().to_slash_lossy().into(),
+                    path.strip_prefix(root).unwrap().to_slash_lossy().into(),
                 ))
             })
             .collect::<Result<Vec<Option<_>>>>()?
@@ -494,7 +508,11 @@ impl ModPacker {
                 return Ok(());
             }
             log::trace!("Diffing {}", &canon);
-            resource = ResourceData::Mergeable(ref_res.diff(res));
+            resource = ResourceData::Mergeable(
+                ref_res
+                    .try_diff(res)
+                    .map_err(|e| e.with_path(canon.as_str()))?,
+            );
         } else if let (Some(sarc), Some(ref_sarc)) = (
             resource.as_sarc(),
             reference.as_ref().and_then(|rrd| rrd.as_sarc()),
@@ -603,37 +621,212 @@ impl ModPacker {
                 .inspect(|_| log::info!("Finished collecting DLC resources"))
                 .unwrap_or_default();
             log::info!("Generating manifest");
-            let mut manifest = Manifest {
+            let manifest = Manifest {
                 content_files,
                 aoc_files,
             };
             log::trace!("CLEARPROGRESS");
-            if manifest
-                .aoc_files
-                .iter()
-                .any(|f| f.contains("Map/MainField"))
-                && !manifest.aoc_files.contains("Pack/AocMainField.pack")
+            self_.write_manifest(root, manifest)
+        }
+        inner(self, root.as_ref())
+    }
+
+    fn write_manifest(&self, root: &Path, mut manifest: Manifest) -> Result<()> {
+        if manifest
+            .aoc_files
+            .iter()
+            .any(|f| f.contains("Map/MainField"))
+            && !manifest.aoc_files.contains("Pack/AocMainField.pack")
+        {
+            self.write_resource(
+                "Aoc/0010/Pack/AocMainField.pack",
+                &ResourceData::Sarc(Default::default()),
+            )?;
+            manifest.aoc_files.insert("Pack/AocMainField.pack".into());
+        }
+        let manifest = serde_yaml::to_string(&manifest)?;
+        log::info!("Writing manifest");
+        let mut zip = self.zip.lock();
+        zip.start_file(
+            root.strip_prefix(&self.source_dir)
+                .unwrap()
+                .join("manifest.yml")
+                .to_slash_lossy(),
+            self._zip_opts,
+        )?;
+        zip.write_all(manifest.as_bytes())?;
+        Ok(())
+    }
+
+    /// Repackage only the resources from the given files of the source
+    /// folder, copying every other resource from a previous package of the
+    /// same mod without processing it again. This is a lot faster than a full
+    /// [`pack`](Self::pack) while developing a mod, when only a few files
+    /// change at a time.
+    ///
+    /// `changed` holds the paths of added, changed, and deleted files,
+    /// relative to the source folder. The previous package must be a
+    /// different file from the destination. Only resources are updated; if
+    /// the meta, options, or thumbnail change, use a full `pack` instead.
+    ///
+    /// Returns a manifest of the files whose resources were added, changed,
+    /// or removed, for a partial deploy.
+    pub fn update(
+        mut self,
+        previous: impl AsRef<Path>,
+        changed: &BTreeSet<PathBuf>,
+    ) -> Result<Manifest> {
+        let previous = previous.as_ref();
+        let mut previous = ZipArchive::new(fs::File::open(previous)?).with_context(|| {
+            jstr!("Failed to open previous package at {&previous.display().to_string()}")
+        })?;
+        let mut dirty = Manifest::default();
+        let base_changed = changed
+            .iter()
+            .filter(|file| !file.starts_with("options"))
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        self.update_root(&mut previous, &base_changed, &mut dirty)
+            .with_context(|| {
+                format!(
+                    "Failed to update mod root at {} for mod {}",
+                    self.source_dir.display(),
+                    self.meta.name
+                )
+            })?;
+        if self.source_dir.join("options").exists() {
+            self.masters
+                .push(Arc::new(uk_reader::ResourceReader::from_unpacked_mod(
+                    &self.source_dir,
+                )?));
+            for root in self.collect_roots() {
+                let option_dir = root.strip_prefix(&self.source_dir).unwrap().to_path_buf();
+                // Options are diffed against the base mod, so they also need
+                // updating when the base mod's copy of a file changes.
+                let root_changed = changed
+                    .iter()
+                    .filter_map(|file| file.strip_prefix(&option_dir).ok())
+                    .map(Path::to_path_buf)
+                    .chain(base_changed.iter().cloned())
+                    .collect();
+                self.current_root.clone_from(&root);
+                self.update_root(&mut previous, &root_changed, &mut dirty)
+                    .with_context(|| {
+                        format!(
+                            "Failed to update mod root at {} for mod {}",
+                            self.current_root.display(),
+                            self.meta.name
+                        )
+                    })?;
+            }
+        }
+        self.pack_thumbnail()?;
+        self.finish()?;
+        Ok(dirty)
+    }
+
+    fn update_root(
+        &self,
+        previous: &mut ZipArchive<fs::File>,
+        changed: &BTreeSet<PathBuf>,
+        dirty: &mut Manifest,
+    ) -> Result<()> {
+        let root = self.current_root.as_path();
+        log::debug!("Updating root of {}", root.display());
+        self.built_resources.clear();
+        let prefix = root.strip_prefix(&self.source_dir).unwrap();
+        let mut manifest: Manifest =
+            match previous.by_name(&prefix.join("manifest.yml").to_slash_lossy()) {
+                Ok(file) => {
+                    serde_yaml::from_reader(file).context("Failed to parse previous manifest")?
+                }
+                Err(zip::result::ZipError::FileNotFound) => Default::default(),
+                Err(e) => return Err(e.into()),
+            };
+        let (content, aoc) = platform_prefixes(self.endian);
+        // Every resource the changed files could have written before, which
+        // must not be copied from the previous package.
+        let mut replaced = HashSet::new();
+        for (dir, files, dirty_files) in [
+            (
+                content,
+                &mut manifest.content_files,
+                &mut dirty.content_files,
+            ),
+            (aoc, &mut manifest.aoc_files, &mut dirty.aoc_files),
+        ] {
+            let mut existing = vec![];
+            for file in changed.iter().filter_map(|f| f.strip_prefix(dir).ok()) {
+                let name: String = file.to_slash_lossy().into();
+                if files.remove(&name) {
+                    dirty_files.insert(name);
+                }
+                let path = root.join(dir).join(file);
+                resource_names(
+                    &path,
+                    canonicalize(Path::new(dir).join(file)),
+                    &mut replaced,
+                );
+                if path.is_file() {
+                    existing.push(path);
+                }
+            }
+            if !existing.is_empty() {
+                let built = self.collect_files(&root.join(dir), existing)?;
+                dirty_files.extend(built.iter().cloned());
+                files.extend(built);
+            }
+        }
+        // A changed SARC may no longer have some of the files it had before,
+        // so the resources the previous package has for those go as well.
+        let mut decompressor = zstd::bulk::Decompressor::with_dictionary(super::DICTIONARY)?;
+        let mut sarcs = replaced.iter().cloned().collect::<Vec<_>>();
+        while let Some(canon) = sarcs.pop() {
+            let Some(sarc) =
+                previous_sarc(previous, &prefix.join(canon.as_str()), &mut decompressor)
+            else {
+                continue;
+            };
+            for (file, _) in sarc.files.iter_full() {
+                let mut nested = canonicalize(file.as_str());
+                if canon.starts_with("Aoc") {
+                    nested.insert_str(0, "Aoc/0010/");
+                }
+                if replaced.insert(nested.clone()) {
+                    sarcs.push(nested);
+                }
+            }
+        }
+
+        let prefix = prefix.to_slash_lossy();
+        for i in 0..previous.len() {
+            let file = previous.by_index_raw(i)?;
+            let canon = match file.name().strip_prefix(&*prefix) {
+                Some(_) if file.is_dir() => continue,
+                Some(canon) if prefix.is_empty() && canon.starts_with("options/") => continue,
+                Some(canon) if prefix.is_empty() => canon,
+                Some(canon) => {
+                    match canon.strip_prefix('/') {
+                        Some(canon) => canon,
+                        None => continue,
+                    }
+                }
+                None => continue,
+            };
+            if canon == "manifest.yml"
+                || canon == "meta.yml"
+                || canon.starts_with("thumb.")
+                || replaced.contains(canon)
+                || self.built_resources.contains(canon)
             {
-                self_.write_resource(
-                    "Aoc/0010/Pack/AocMainField.pack",
-                    &ResourceData::Sarc(Default::default()),
-                )?;
-                manifest.aoc_files.insert("Pack/AocMainField.pack".into());
+                continue;
             }
-            let manifest = serde_yaml::to_string(&manifest)?;
-            log::info!("Writing manifest");
-            let mut zip = self_.zip.lock();
-            zip.start_file(
-                root.strip_prefix(&self_.source_dir)
-                    .unwrap()
-                    .join("manifest.yml")
-                    .to_slash_lossy(),
-                self_._zip_opts,
-            )?;
-            zip.write_all(manifest.as_bytes())?;
-            Ok(())
+            let canon: String = canon.into();
+            log::trace!("Copying {} from previous package", canon);
+            self.zip.lock().raw_copy_file(file)?;
+            self.built_resources.insert(canon);
         }
-        inner(self, root.as_ref())
+        self.write_manifest(root, manifest)
     }
 
     fn collect_roots(&self) -> Vec<PathBuf> {
@@ -688,6 +881,10 @@ impl ModPacker {
             }
         }
         self.pack_thumbnail()?;
+        self.finish()
+    }
+
+    fn finish(self) -> Result<PathBuf> {
         match Arc::try_unwrap(self.zip).map(|z| z.into_inner()) {
             Ok(mut zip) => {
                 log::info!("Writing meta");
@@ -704,13 +901,69 @@ impl ModPacker {
     }
 }
 
+/// Add the canonical path of a resource file and, if it is a mergeable SARC,
+/// of every file nested in it, which are all the resources packaging the
+/// file can write.
+fn resource_names(path: &Path, canon: String, names: &mut HashSet<String>) {
+    if let Ok(data) = fs::read(path) {
+        let data = decompress_if(&data);
+        if is_mergeable_sarc(canon.as_str(), data.as_ref()) {
+            sarc_resource_names(data.as_ref(), canon.starts_with("Aoc"), names);
+        }
+    }
+    names.insert(canon);
+}
+
+/// The file list of a SARC resource in a previous package, if it has one.
+fn previous_sarc(
+    previous: &mut ZipArchive<fs::File>,
+    path: &Path,
+    decompressor: &mut zstd::bulk::Decompressor,
+) -> Option<SarcMap> {
+    let mut data = vec![];
+    previous
+        .by_name(&path.to_slash_lossy())
+        .ok()?
+        .read_to_end(&mut data)
+        .ok()?;
+    let size = zstd::bulk::Decompressor::upper_bound(&data).unwrap_or(data.len() * 1024);
+    let data = decompressor.decompress(&data, size).ok()?;
+    match minicbor_ser::from_slice(&data).ok()? {
+        ResourceData::Sarc(sarc) => Some(sarc),
+        _ => None,
+    }
+}
+
+fn sarc_resource_names(data: &[u8], is_aoc: bool, names: &mut HashSet<String>) {
+    let Ok(sarc) = Sarc::new(data) else {
+        return;
+    };
+    for file in sarc.files() {
+        let Some(name) = file.name() else {
+            continue;
+        };
+        let mut canon = canonicalize(name);
+        if is_aoc {
+            canon.insert_str(0, "Aoc/0010/");
+        }
+        let data = decompress_if(file.data);
+        if is_mergeable_sarc(canon.as_str(), data.as_ref()) {
+            sarc_resource_names(data.as_ref(), is_aoc, names);
+        }
+        names.insert(canon);
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
+
     use indexmap::IndexMap;
+    use roead::sarc::SarcWriter;
     use uk_reader::ResourceReader;
 
     use super::*;
-    use crate::{ModOption, MultipleOptionGroup, OptionGroup};
+    use crate::{fixtures::meta, ModOption, MultipleOptionGroup, OptionGroup};
     #[test]
     fn pack_mod() {
         env_logger::init();
@@ -732,6 +985,8 @@ mod tests {
                 author: "Lord Caleb".into(),
                 description: "A test mod".into(),
                 masters: IndexMap::default(),
+                load_after: Default::default(),
+                load_before: Default::default(),
                 url: None,
                 options: vec![OptionGroup::Multiple(MultipleOptionGroup {
                     name: "Test Option Group".into(),
@@ -753,4 +1008,94 @@ mod tests {
         .unwrap();
         builder.pack().unwrap();
     }
+
+    /// Every file in a mod package with its contents.
+    fn package_files(path: &Path) -> BTreeMap<std::string::String, Vec<u8>> {
+        let mut zip = ZipArchive::new(fs::File::open(path).unwrap()).unwrap();
+        (0..zip.len())
+            .map(|i| {
+                let mut file = zip.by_index(i).unwrap();
+                let mut data = vec![];
+                file.read_to_end(&mut data).unwrap();
+                (file.name().to_owned(), data)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn update_mod() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("source");
+        let content = source.join("content");
+        let dump = dir.path().join("dump");
+        fs::create_dir_all(content.join("Model")).unwrap();
+        fs::create_dir_all(content.join("Pack")).unwrap();
+        fs::create_dir_all(&dump).unwrap();
+        let sarc = |files: &[&str]| {
+            let mut sarc = SarcWriter::new(roead::Endian::Big);
+            for file in files {
+                sarc.add_file(*file, format!("UKMM test file {file}").as_bytes());
+            }
+            sarc.to_binary()
+        };
+        fs::write(content.join("Model/Changed.bfres"), b"UKMM original").unwrap();
+        fs::write(content.join("Model/Deleted.bfres"), b"UKMM original").unwrap();
+        fs::write(content.join("Model/Unchanged.bfres"), b"UKMM original").unwrap();
+        fs::write(
+            content.join("Pack/UKMM.pack"),
+            sarc(&["Model/Nested.bfres", "Model/Removed.bfres"]),
+        )
+        .unwrap();
+        let meta = Meta {
+            platform: ModPlatform::Specific(Endian::Big),
+            ..meta(vec![])
+        };
+        let dump = Arc::new(ResourceReader::from_unpacked_mod(&dump).unwrap());
+        let pack = |dest: &Path| {
+            ModPacker::new(&source, dest, Some(meta.clone()), vec![dump.clone()]).unwrap()
+        };
+        let previous = dir.path().join("previous.zip");
+        pack(&previous).pack().unwrap();
+
+        fs::write(content.join("Model/Changed.bfres"), b"UKMM changed").unwrap();
+        fs::remove_file(content.join("Model/Deleted.bfres")).unwrap();
+        fs::write(content.join("Model/Added.bfres"), b"UKMM added").unwrap();
+        fs::write(
+            content.join("Pack/UKMM.pack"),
+            sarc(&["Model/Nested.bfres"]),
+        )
+        .unwrap();
+        let changed_files = [
+            "Model/Added.bfres",
+            "Model/Changed.bfres",
+            "Model/Deleted.bfres",
+            "Pack/UKMM.pack",
+        ];
+        let updated = dir.path().join("updated.zip");
+        let dirty = pack(&updated)
+            .update(
+                &previous,
+                &changed_files
+                    .iter()
+                    .map(|file| Path::new("content").join(file))
+                    .collect(),
+            )
+            .unwrap();
+        assert_eq!(
+            dirty.content_files,
+            changed_files
+                .into_iter()
+                .map(String::from)
+                .collect::<BTreeSet<_>>()
+        );
+        assert!(dirty.aoc_files.is_empty());
+
+        let packed = dir.path().join("packed.zip");
+        pack(&packed).pack().unwrap();
+        let files = package_files(&updated);
+        assert!(files.contains_key("Model/Unchanged.bfres"));
+        assert!(!files.contains_key("Model/Deleted.bfres"));
+        assert!(!files.contains_key("Model/Removed.bfres"));
+        assert_eq!(files, package_files(&packed));
+    }
 }
@@ -1,4 +1,5 @@
 mod de;
+mod maps;
 use std::{
     collections::BTreeSet,
     io::{BufReader, Read, Write},
@@ -32,13 +33,15 @@ use uk_content::{
     canonicalize,
     constants::Language,
     platform_content, platform_prefixes,
-    prelude::{Endian, Mergeable, Resource},
-    resource::{MergeableResource, ResourceData, SarcMap},
+    prelude::{Endian, Mergeable, Resource, TryMergeable},
+    resource::{MapUnit, MergeableResource, ResourceData, SarcMap},
     util::{HashMap, IndexSet},
 };
 use uk_reader::{ResourceLoader, ResourceReader};
 use uk_util::PathExt as UkPathExt;
 
+use self::maps::{MapChecker, SectionUnit};
+pub use self::maps::{MapIssue, MapIssueKind};
 use crate::{Manifest, Meta, ModOption};
 
 pub enum ZipData {
@@ -279,24 +282,10 @@ impl ModReader {
             } else {
                 ModReader::open_unzipped(path, options)
             }?;
-            let name = result.meta.name.as_str();
-            let mod_api = Version::parse(&result.meta.api)
-                .map_err(|e| anyhow_ext::anyhow!("{e}"))
-                .context("Invalid API version for mod")?;
-            let current_api = Version::parse(env!("CARGO_PKG_VERSION")).unwrap();
-            if current_api.major != mod_api.major {
-                anyhow_ext::bail!("{name} build with unsupported UKMM version: {mod_api}")
-            } else if current_api.minor > mod_api.minor && current_api.major == 0 {
-                log::warn!(
-                    "{name} is from an older UKMM prerelease (v{mod_api}), compatibility not \
-                     guaranteed"
-                )
-            } else if current_api.minor < mod_api.minor {
-                log::warn!(
-                    "{name} was made with a newer UKMM version (v{mod_api}), might contain \
-                     incompatible features"
-                )
+            if let Some(warning) = check_api(&result.meta.name, &result.meta.api)? {
+                log::warn!("{warning}");
             }
+            result.meta.check_requirements(&result.options)?;
             Ok(result)
         }
         inner(path.as_ref(), options.into())
@@ -310,23 +299,8 @@ impl ModReader {
             } else {
                 ModReader::open_unzipped(path, options)
             }?;
-            let name = result.meta.name.as_str();
-            let mod_api = Version::parse(&result.meta.api)
-                .map_err(|e| anyhow_ext::anyhow!("{e}"))
-                .context("Invalid API version for mod")?;
-            let current_api = Version::parse(env!("CARGO_PKG_VERSION")).unwrap();
-            if current_api.major != mod_api.major {
-                anyhow_ext::bail!("{name} build with unsupported UKMM version: {mod_api}")
-            } else if current_api.minor > mod_api.minor && current_api.major == 0 {
-                log::warn!(
-                    "{name} is from an older UKMM prerelease (v{mod_api}), compatibility not \
-                     guaranteed"
-                )
-            } else if current_api.minor < mod_api.minor {
-                log::warn!(
-                    "{name} was made with a newer UKMM version (v{mod_api}), might contain \
-                     incompatible features"
-                )
+            if let Some(warning) = check_api(&result.meta.name, &result.meta.api)? {
+                log::warn!("{warning}");
             }
             Ok(result)
         }
@@ -429,6 +403,11 @@ impl ModReader {
         &self.manifest
     }
 
+    /// The options the mod was opened with.
+    pub fn options(&self) -> &[ModOption] {
+        &self.options
+    }
+
     pub fn get_versions(&self, name: &Path) -> Result<Vec<Vec<u8>>> {
         let canon = canonicalize(name);
         let mut versions = Vec::with_capacity(1);
@@ -475,16 +454,70 @@ static RSTB_EXCLUDE_EXTS: &[&str] = &[
 ];
 static RSTB_EXCLUDE_NAMES: &[&str] = &["ActorInfo.product.byml"];
 
+/// A value changed differently by two mods which edit the same resource.
+#[derive(Debug, Clone, Serialize)]
+pub struct MergeConflict {
+    /// Path of the value within the resource, e.g. `Normal0/ItemName01`.
+    pub path: String,
+    /// The mod whose change is discarded.
+    pub overridden: String,
+    /// The later mod whose change is kept.
+    pub winner: String,
+}
+
+/// Results of a merge which need to be applied or reported afterwards.
+#[derive(Debug, Default)]
+pub struct MergeLog {
+    /// RSTB values for every file which was built, by canonical path.
+    pub rstb: DashMap<String, Option<u32>>,
+    /// Field-level conflicts between mods, by canonical resource path.
+    pub conflicts: DashMap<String, Vec<MergeConflict>>,
+    /// `HashId` and `SRTHash` collisions and dangling links in merged map
+    /// units.
+    pub map_issues: Vec<MapIssue>,
+    /// Size of every file written to the output folder, or which would have
+    /// been written in a dry run, by output path.
+    pub sizes: DashMap<PathBuf, usize>,
+}
+
+/// Checks that a mod made with the UKMM version `api` can be installed by
+/// this one. Returns a warning if it might not be fully compatible, or an
+/// error if it cannot be installed at all, naming the mod as `name`.
+pub fn check_api(name: &str, api: &str) -> Result<Option<std::string::String>> {
+    let mod_api = Version::parse(api)
+        .map_err(|e| anyhow_ext::anyhow!("{e}"))
+        .with_context(|| jstr!("Invalid API version {api} for {name}"))?;
+    let current_api = Version::parse(env!("CARGO_PKG_VERSION")).unwrap();
+    if current_api.major != mod_api.major {
+        anyhow_ext::bail!("{name} was made with an unsupported UKMM version: {mod_api}")
+    } else if current_api.minor > mod_api.minor && current_api.major == 0 {
+        Ok(Some(format!(
+            "{name} is from an older UKMM prerelease (v{mod_api}), compatibility not guaranteed"
+        )))
+    } else if current_api.minor < mod_api.minor {
+        Ok(Some(format!(
+            "{name} was made with a newer UKMM version (v{mod_api}), and might contain \
+             incompatible features"
+        )))
+    } else {
+        Ok(None)
+    }
+}
+
 // #[derive(Debug)]
 pub struct ModUnpacker {
-    dump:     Arc<ResourceReader>,
+    dump: Arc<ResourceReader>,
     manifest: Option<Manifest>,
-    mods:     Vec<ModReader>,
-    endian:   Endian,
-    lang:     Language,
-    rstb:     DashMap<String, Option<u32>>,
-    hashes:   StockHashTable,
-    out_dir:  PathBuf,
+    mods: Vec<ModReader>,
+    endian: Endian,
+    lang: Language,
+    rstb: DashMap<String, Option<u32>>,
+    conflicts: DashMap<String, Vec<MergeConflict>>,
+    maps: MapChecker,
+    hashes: StockHashTable,
+    out_dir: PathBuf,
+    dry_run: bool,
+    sizes: DashMap<PathBuf, usize>,
 }
 
 impl ModUnpacker {
@@ -502,11 +535,15 @@ impl ModUnpacker {
             lang,
             endian,
             rstb: DashMap::new(),
+            conflicts: DashMap::new(),
+            maps: MapChecker::default(),
             hashes: StockHashTable::new(&match endian {
                 Endian::Little => botw_utils::hashes::Platform::Switch,
                 Endian::Big => botw_utils::hashes::Platform::WiiU,
             }),
             out_dir,
+            dry_run: false,
+            sizes: DashMap::new(),
         }
     }
 
@@ -515,27 +552,104 @@ impl ModUnpacker {
         self
     }
 
-    pub fn unpack(self) -> Result<DashMap<String, Option<u32>>> {
-        if !self.out_dir.exists() {
-            fs::create_dir_all(&self.out_dir)?;
+    /// Give objects which two mods add to the same map unit under the same
+    /// `HashId` a new, unique `HashId` instead of reporting the collision.
+    pub fn with_rehash(mut self, rehash: bool) -> Self {
+        self.maps.rehash = rehash;
+        self
+    }
+
+    /// Build every file without writing anything to the output folder, only
+    /// recording the sizes of the files which would have been written.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    fn files(&self) -> (BTreeSet<&String>, BTreeSet<&String>) {
+        match self.manifest.as_ref() {
+            Some(manifest) => {
+                (
+                    manifest.content_files.iter().collect(),
+                    manifest.aoc_files.iter().collect(),
+                )
+            }
+            None => {
+                (
+                    self.mods
+                        .iter()
+                        .flat_map(|mod_| mod_.manifest.content_files.iter())
+                        .collect(),
+                    self.mods
+                        .iter()
+                        .flat_map(|mod_| mod_.manifest.aoc_files.iter())
+                        .collect(),
+                )
+            }
         }
-        let mut content_files: BTreeSet<&String>;
-        let aoc_files: BTreeSet<&String>;
-        if let Some(manifest) = self.manifest.as_ref() {
-            content_files = manifest.content_files.iter().collect();
-            aoc_files = manifest.aoc_files.iter().collect();
-        } else {
-            content_files = self
-                .mods
-                .iter()
-                .flat_map(|mod_| mod_.manifest.content_files.iter())
-                .collect();
-            aoc_files = self
-                .mods
-                .iter()
-                .flat_map(|mod_| mod_.manifest.aoc_files.iter())
-                .collect();
+    }
+
+    /// Merge only the map units touched by the mods, without writing any
+    /// output, and report any `HashId` or `SRTHash` collisions and dangling
+    /// links.
+    pub fn check_maps(self) -> Result<Vec<MapIssue>> {
+        let (content_files, aoc_files) = self.files();
+        let is_map = |file: &&String| {
+            MapUnit::path_matches(file.as_str())
+                || (file.starts_with("Pack/") && file.ends_with(".pack"))
+        };
+        content_files
+            .into_iter()
+            .filter(is_map)
+            .map(|file| (file, false))
+            .chain(
+                aoc_files
+                    .into_iter()
+                    .filter(is_map)
+                    .map(|file| (file, true)),
+            )
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .try_for_each(|(file, aoc)| -> Result<()> {
+                self.build_file(file.as_str(), aoc)
+                    .with_context(|| jstr!("Failed to merge {file}"))?;
+                Ok(())
+            })?;
+        Ok(self.maps.finish(&self.dump))
+    }
+
+    /// Merge every file the mods change without writing any output, and
+    /// return each file which failed to build, by canonical path, along with
+    /// the error. Game texts are not checked.
+    pub fn check_files(self) -> Vec<(String, anyhow_ext::Error)> {
+        let (mut content_files, aoc_files) = self.files();
+        for lang in self.mods.iter().flat_map(|m| m.manifest.languages()) {
+            content_files.remove(&lang.bootup_path());
         }
+        content_files
+            .into_iter()
+            .map(|file| (file, false))
+            .chain(aoc_files.into_iter().map(|file| (file, true)))
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .filter_map(|(file, aoc)| {
+                self.build_file(file.as_str(), aoc).err().map(|e| {
+                    let canon = if aoc {
+                        canonicalize(jstr!("Aoc/0010/{file}"))
+                    } else {
+                        canonicalize(file.as_str())
+                    };
+                    (canon, e)
+                })
+            })
+            .collect()
+    }
+
+    pub fn unpack(self) -> Result<MergeLog> {
+        if !self.dry_run && !self.out_dir.exists() {
+            fs::create_dir_all(&self.out_dir)?;
+        }
+        let (mut content_files, aoc_files) = self.files();
         let mut modded_langs: IndexSet<Language> = Default::default();
         for lang in self.mods.iter().flat_map(|m| m.manifest.languages()) {
             modded_langs.insert(lang);
@@ -591,7 +705,12 @@ impl ModUnpacker {
             log::trace!("CLEARPROGRESS");
             Ok(())
         })?;
-        Ok(self.rstb)
+        Ok(MergeLog {
+            rstb: self.rstb,
+            conflicts: self.conflicts,
+            map_issues: self.maps.finish(&self.dump),
+            sizes: self.sizes,
+        })
     }
 
     fn unpack_texts(&self, mut langs: IndexSet<Language>) -> Result<()> {
@@ -629,7 +748,6 @@ impl ModUnpacker {
                 .out_dir
                 .join(platform_content(self.endian))
                 .join(self.lang.bootup_path().as_str());
-            out.parent().map(fs::create_dir_all).transpose()?;
             let data = base.into_binary(self.endian);
             self.rstb.insert(
                 format!("Message/Msg_{}.product.sarc", self.lang).into(),
@@ -637,7 +755,12 @@ impl ModUnpacker {
             );
             let mut sarc = SarcWriter::new(self.endian.into())
                 .with_file(self.lang.message_path(), compress(data));
-            fs::write(out, sarc.to_binary())?;
+            let data = sarc.to_binary();
+            if !self.dry_run {
+                out.parent().map(fs::create_dir_all).transpose()?;
+                fs::write(&out, &data)?;
+            }
+            self.sizes.insert(out, data.len());
         }
         Ok(())
     }
@@ -653,9 +776,13 @@ impl ModUnpacker {
         files.into_par_iter().try_for_each(|file| -> Result<()> {
             let data = self.build_file(file.as_str(), aoc)?;
             let out_file = dir.join(file.as_str());
-            out_file.parent().map(fs::create_dir_all).transpose()?;
-            let mut writer = std::io::BufWriter::new(fs::File::create(&out_file)?);
-            writer.write_all(&compress_if(data.as_ref(), &out_file))?;
+            let data = compress_if(data.as_ref(), &out_file);
+            if !self.dry_run {
+                out_file.parent().map(fs::create_dir_all).transpose()?;
+                let mut writer = std::io::BufWriter::new(fs::File::create(&out_file)?);
+                writer.write_all(&data)?;
+            }
+            self.sizes.insert(out_file, data.len());
             let progress = 1 + current_file.load(Ordering::Relaxed);
             current_file.store(progress, Ordering::Relaxed);
             let percent = (progress as f64 / total_files as f64) * 100.0;
@@ -694,6 +821,7 @@ impl ModUnpacker {
                 .and_then(|n| n.to_str())
                 .unwrap_or_default(),
         );
+        let mut sources = std::collections::VecDeque::with_capacity(versions.capacity());
         let mut dump_error: Vec<anyhow_ext::Error> = vec![];
         let res_result = self.dump.get_data(&dump_file).or_else(|e| {
             log::trace!("{e:?}");
@@ -708,6 +836,7 @@ impl ModUnpacker {
                 })
             })
         });
+        let in_dump = res_result.is_ok();
         match res_result {
             Ok(ref_res) => versions.push_back(ref_res),
             Err(e) => {
@@ -727,7 +856,10 @@ impl ModUnpacker {
         {
             let res = minicbor_ser::from_slice(&data);
             match res {
-                Ok(res) => versions.push_back(Arc::new(res)),
+                Ok(res) => {
+                    versions.push_back(Arc::new(res));
+                    sources.push_back(mod_);
+                }
                 Err(e) => {
                     let msg = format!("{}", e);
                     if msg.contains("unknown variant") {
@@ -758,6 +890,9 @@ impl ModUnpacker {
                 }
                 err
             })?;
+        if !in_dump {
+            sources.pop_front();
+        }
         let is_modded = !versions.is_empty() || self.hashes.is_file_new(&canon);
         let data = match base_version.as_ref() {
             ResourceData::Binary(_) => {
@@ -775,14 +910,51 @@ impl ModUnpacker {
                 }
             }
             ResourceData::Mergeable(base_res) => {
-                let merged = versions
-                    .into_iter()
-                    .fold(base_res.clone(), |mut res, version| {
-                        if let Some(mergeable) = version.as_mergeable() {
-                            res = res.merge(mergeable);
+                let map_owners = match base_res {
+                    MergeableResource::MapUnit(base_unit) => {
+                        let order = self.mods.iter().map(|m| &m.meta.name).collect::<Vec<_>>();
+                        Some(self.maps.prepare(
+                            &canon,
+                            base_unit,
+                            &mut versions,
+                            &sources,
+                            &order,
+                            |sibling| self.load_map_unit(sibling, &maps::sibling(&dump_file)?),
+                        ))
+                    }
+                    _ => None,
+                };
+                if versions.len() > 1 {
+                    self.collect_conflicts(&canon, &versions, &sources);
+                }
+                let merged = versions.into_iter().zip(sources).try_fold(
+                    base_res.clone(),
+                    |res, (version, mod_)| -> Result<MergeableResource> {
+                        match version.as_mergeable() {
+                            Some(mergeable) => {
+                                res.try_merge(mergeable)
+                                    .map_err(|e| e.with_path(canon.as_str()))
+                                    .with_context(|| {
+                                        format!(
+                                            "Mod {mod_} supplied an incompatible version of \
+                                             {canon}. The mod may be corrupt or built for a \
+                                             different platform."
+                                        )
+                                    })
+                            }
+                            None => {
+                                log::warn!(
+                                    "Mod {mod_} supplied {canon} as a non-mergeable resource, \
+                                     skipping"
+                                );
+                                Ok(res)
+                            }
                         }
-                        res
-                    });
+                    },
+                )?;
+                if let (Some(owners), MergeableResource::MapUnit(unit)) = (map_owners, &merged) {
+                    self.maps.record(&canon, unit, owners);
+                }
                 let data = merged.into_binary(self.endian);
                 if can_rstb && (is_modded || self.hashes.is_file_modded(&canon, &data, true)) {
                     rstb_val = Some(rstb::calc::estimate_from_slice_and_name(
@@ -824,6 +996,70 @@ impl ModUnpacker {
         Ok(data)
     }
 
+    /// Load a map unit from the game dump along with every mod's diff of it,
+    /// so a map section can be checked before its units are merged.
+    fn load_map_unit(&self, canon: &str, dump_file: &str) -> Option<SectionUnit> {
+        let base = self
+            .dump
+            .get_data(dump_file)
+            .or_else(|_| self.dump.get_resource(canon))
+            .ok()?;
+        let Some(MergeableResource::MapUnit(base)) = base.as_mergeable() else {
+            return None;
+        };
+        let diffs = self
+            .mods
+            .iter()
+            .flat_map(|mod_| {
+                mod_.get_versions(Path::new(dump_file))
+                    .into_iter()
+                    .flatten()
+                    .filter_map(move |data| {
+                        match minicbor_ser::from_slice(&data) {
+                            Ok(ResourceData::Mergeable(MergeableResource::MapUnit(diff))) => {
+                                Some((mod_.meta.name.clone(), *diff))
+                            }
+                            _ => None,
+                        }
+                    })
+            })
+            .collect();
+        Some(SectionUnit {
+            canon: canon.into(),
+            base: (**base).clone(),
+            diffs,
+        })
+    }
+
+    fn collect_conflicts(
+        &self,
+        canon: &String,
+        versions: &std::collections::VecDeque<Arc<ResourceData>>,
+        sources: &std::collections::VecDeque<&String>,
+    ) {
+        let mut conflicts = vec![];
+        for (i, (version, mod_)) in versions.iter().zip(sources).enumerate() {
+            let Some(version) = version.as_mergeable() else {
+                continue;
+            };
+            for (other, other_mod) in versions.iter().zip(sources).skip(i + 1) {
+                if let Some(other) = other.as_mergeable() {
+                    conflicts.extend(version.conflicts(other).into_iter().map(|path| {
+                        MergeConflict {
+                            path,
+                            overridden: (*mod_).clone(),
+                            winner: (*other_mod).clone(),
+                        }
+                    }));
+                }
+            }
+        }
+        if !conflicts.is_empty() {
+            log::debug!("{} conflicts found merging {canon}", conflicts.len());
+            self.conflicts.insert(canon.clone(), conflicts);
+        }
+    }
+
     fn build_sarc(&self, sarc: SarcMap, aoc: bool) -> Result<Vec<u8>> {
         let mut writer = SarcWriter::new(self.endian.into()).with_min_alignment(sarc.alignment);
         for file in sarc.files.into_iter() {
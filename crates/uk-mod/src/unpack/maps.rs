@@ -0,0 +1,548 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    sync::Arc,
+};
+
+use dashmap::{DashMap, DashSet};
+use parking_lot::Mutex;
+use roead::byml::Byml;
+use serde::Serialize;
+use smartstring::alias::String;
+use uk_content::{
+    resource::{MapUnit, MergeableResource, ResourceData},
+    util::{HashMap, HashSet},
+};
+use uk_reader::ResourceReader;
+
+/// A problem with the placed objects of a merged map section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum MapIssueKind {
+    /// The same `HashId` is used by more than one object or rail.
+    DuplicateHashId,
+    /// The same `SRTHash` is used by more than one object.
+    DuplicateSrtHash,
+    /// A `LinksToObj` entry points to a `HashId` which does not exist.
+    DanglingLink,
+}
+
+/// A `HashId` or `SRTHash` collision, or a dangling link, found in the
+/// merged map units of a section. Only problems involving at least one mod
+/// are reported.
+#[derive(Debug, Clone, Serialize)]
+pub struct MapIssue {
+    pub kind:    MapIssueKind,
+    /// The map section, e.g. `Map/MainField/D-3` or
+    /// `Aoc/0010/Map/CDungeon/Dungeon044`.
+    pub section: String,
+    /// The duplicated `HashId` or `SRTHash`, or the missing link destination.
+    pub value:   u32,
+    /// Canonical paths of the map units involved.
+    pub units:   Vec<String>,
+    /// The mods which added, edited, or removed the objects involved.
+    pub mods:    Vec<String>,
+}
+
+impl std::fmt::Display for MapIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            MapIssueKind::DuplicateHashId => {
+                write!(
+                    f,
+                    "HashId {} is used more than once in {}",
+                    self.value,
+                    self.units.join(", ")
+                )?
+            }
+            MapIssueKind::DuplicateSrtHash => {
+                write!(
+                    f,
+                    "SRTHash {} is used by more than one object in {}",
+                    self.value,
+                    self.units.join(", ")
+                )?
+            }
+            MapIssueKind::DanglingLink => {
+                write!(
+                    f,
+                    "An object in {} links to missing HashId {}",
+                    self.units.join(", "),
+                    self.value
+                )?
+            }
+        }
+        write!(f, " (mods: {})", self.mods.join(", "))
+    }
+}
+
+/// The IDs and links of a merged map unit, along with the mods which touched
+/// each entry.
+#[derive(Debug, Default)]
+struct UnitRecord {
+    hash_ids: Vec<u32>,
+    srt_hashes: Vec<(u32, u32)>,
+    links: Vec<(u32, u32)>,
+    owners: HashMap<u32, Vec<String>>,
+}
+
+impl UnitRecord {
+    fn new(unit: &MapUnit, owners: HashMap<u32, Vec<String>>) -> Self {
+        Self {
+            hash_ids: unit.hash_ids().collect(),
+            srt_hashes: unit.srt_hashes().collect(),
+            links: unit.links().collect(),
+            owners,
+        }
+    }
+}
+
+/// A map unit from the game dump along with each mod's diff of it, in load
+/// order.
+#[derive(Debug)]
+pub(crate) struct SectionUnit {
+    pub(crate) canon: String,
+    pub(crate) base:  MapUnit,
+    pub(crate) diffs: Vec<(String, MapUnit)>,
+}
+
+/// A new `HashId` for an object or rail which a mod adds to a map unit.
+#[derive(Debug, Clone, PartialEq)]
+struct Rehash {
+    mod_:  String,
+    canon: String,
+    old:   u32,
+    new:   u32,
+}
+
+/// Collects every merged map unit while unpacking so that each map section
+/// can be validated once all of its units are built.
+#[derive(Debug, Default)]
+pub(crate) struct MapChecker {
+    pub(crate) rehash: bool,
+    units: DashMap<String, UnitRecord>,
+    issues: Mutex<Vec<MapIssue>>,
+    assigned: DashSet<u32>,
+    plans: DashMap<String, Arc<Vec<Rehash>>>,
+}
+
+fn section(canon: &str) -> &str {
+    canon
+        .rsplit_once('/')
+        .map(|(dir, _)| dir)
+        .unwrap_or_default()
+}
+
+pub(crate) fn sibling(canon: &str) -> Option<String> {
+    if canon.contains("_Static") {
+        Some(canon.replace("_Static", "_Dynamic").into())
+    } else if canon.contains("_Dynamic") {
+        Some(canon.replace("_Dynamic", "_Static").into())
+    } else {
+        None
+    }
+}
+
+/// Entries which a mod's diff adds to the base unit, as opposed to partial
+/// diffs of existing entries.
+fn added_entries<'a>(
+    base: &'a MapUnit,
+    diff: &'a MapUnit,
+) -> impl Iterator<Item = (u32, &'a Byml)> {
+    diff.objects
+        .iter()
+        .chain(diff.rails.iter())
+        .filter(|(id, entry)| {
+            base.objects.get(*id).is_none()
+                && base.rails.get(*id).is_none()
+                && entry.as_map().is_ok_and(|e| e.contains_key("HashId"))
+        })
+        .map(|(id, entry)| (*id, entry))
+}
+
+impl MapChecker {
+    /// Checks the mods' versions of the units of a map section for distinct
+    /// objects added under the same `HashId`, which would otherwise be
+    /// silently merged into one, or duplicated across the static and dynamic
+    /// units. If rehashing is enabled, later colliding objects are given a
+    /// new `HashId` unused in either unit, and links to them from the same
+    /// mod's diffs of both units are updated.
+    fn plan_section(&self, units: &[SectionUnit], order: &[&String]) -> Vec<Rehash> {
+        let mut taken: HashSet<u32> = units
+            .iter()
+            .flat_map(|unit| {
+                unit.base
+                    .hash_ids()
+                    .chain(unit.diffs.iter().flat_map(|(_, diff)| diff.hash_ids()))
+            })
+            .collect();
+        let mut entries = units
+            .iter()
+            .flat_map(|unit| {
+                unit.diffs.iter().flat_map(move |(mod_, diff)| {
+                    added_entries(&unit.base, diff)
+                        .map(move |(id, entry)| (mod_, unit.canon.as_str(), id, entry))
+                })
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|(mod_, ..)| {
+            order
+                .iter()
+                .position(|other| *other == *mod_)
+                .unwrap_or(usize::MAX)
+        });
+        let mut added: HashMap<u32, (&String, &str, &Byml)> = HashMap::default();
+        let mut plan = vec![];
+        for (mod_, canon, id, entry) in entries {
+            match added.get(&id) {
+                Some(&(other, other_canon, other_entry))
+                    if other != mod_ && (other_canon != canon || other_entry != entry) =>
+                {
+                    if self.rehash {
+                        let mut new = roead::aamp::hash_name(&format!("{canon}/{mod_}/{id}"));
+                        while taken.contains(&new) || !self.assigned.insert(new) {
+                            new = new.wrapping_add(1);
+                        }
+                        taken.insert(new);
+                        log::info!(
+                            "Changed HashId {id} added by {mod_} in {canon} to {new} to avoid a \
+                             collision with {other}"
+                        );
+                        plan.push(Rehash {
+                            mod_: mod_.clone(),
+                            canon: canon.into(),
+                            old: id,
+                            new,
+                        });
+                    } else if other_canon == canon {
+                        // Objects in different units both survive the merge,
+                        // so those collisions are found by the section check.
+                        self.issues.lock().push(MapIssue {
+                            kind:    MapIssueKind::DuplicateHashId,
+                            section: section(canon).into(),
+                            value:   id,
+                            units:   vec![canon.into()],
+                            mods:    vec![other.clone(), mod_.clone()],
+                        });
+                    }
+                }
+                Some(_) => (),
+                None => {
+                    added.insert(id, (mod_, canon, entry));
+                }
+            }
+        }
+        plan
+    }
+
+    /// Applies the rehashing planned for a map unit's section to the mods'
+    /// versions of it, planning it first if this is the first unit of the
+    /// section to be merged. `load` is given the other unit of the section
+    /// and `order` the names of all mods in load order. Returns the mods
+    /// which touched each entry.
+    pub(crate) fn prepare(
+        &self,
+        canon: &str,
+        base: &MapUnit,
+        versions: &mut VecDeque<Arc<ResourceData>>,
+        sources: &VecDeque<&String>,
+        order: &[&String],
+        load: impl FnOnce(&str) -> Option<SectionUnit>,
+    ) -> HashMap<u32, Vec<String>> {
+        let plan = self
+            .plans
+            .entry(section(canon).into())
+            .or_insert_with(|| {
+                let diffs = versions
+                    .iter()
+                    .zip(sources)
+                    .filter_map(|(version, mod_)| {
+                        match version.as_mergeable() {
+                            Some(MergeableResource::MapUnit(diff)) => {
+                                Some(((*mod_).clone(), (**diff).clone()))
+                            }
+                            _ => None,
+                        }
+                    })
+                    .collect();
+                let mut units = vec![SectionUnit {
+                    canon: canon.into(),
+                    base: base.clone(),
+                    diffs,
+                }];
+                units.extend(sibling(canon).and_then(|sibling| load(&sibling)));
+                units.sort_by(|a, b| a.canon.cmp(&b.canon));
+                Arc::new(self.plan_section(&units, order))
+            })
+            .clone();
+        let mut owners: HashMap<u32, Vec<String>> = HashMap::default();
+        for (version, &mod_) in versions.iter_mut().zip(sources) {
+            if plan.iter().any(|rehash| &rehash.mod_ == mod_) {
+                if let ResourceData::Mergeable(MergeableResource::MapUnit(diff)) =
+                    Arc::make_mut(version)
+                {
+                    for rehash in plan.iter().filter(|rehash| &rehash.mod_ == mod_) {
+                        if rehash.canon == canon {
+                            diff.rehash(rehash.old, rehash.new);
+                        } else {
+                            diff.relink(rehash.old, rehash.new);
+                        }
+                    }
+                }
+            }
+            if let Some(MergeableResource::MapUnit(diff)) = version.as_mergeable() {
+                for id in diff
+                    .objects
+                    .iter_full()
+                    .chain(diff.rails.iter_full())
+                    .map(|(id, _)| *id)
+                {
+                    owners.entry(id).or_default().push(mod_.clone());
+                }
+            }
+        }
+        owners
+    }
+
+    /// Records a merged map unit for validation.
+    pub(crate) fn record(&self, canon: &str, unit: &MapUnit, owners: HashMap<u32, Vec<String>>) {
+        self.units
+            .insert(canon.into(), UnitRecord::new(unit, owners));
+    }
+
+    /// Validates every recorded map section. Units of a section which were
+    /// not rebuilt are read from the game dump.
+    pub(crate) fn finish(self, dump: &ResourceReader) -> Vec<MapIssue> {
+        let mut issues = self.issues.into_inner();
+        let mut sections: BTreeMap<String, Vec<(String, UnitRecord)>> = BTreeMap::new();
+        for (canon, record) in self.units {
+            sections
+                .entry(section(&canon).into())
+                .or_default()
+                .push((canon, record));
+        }
+        for (section, mut units) in sections {
+            let siblings = units
+                .iter()
+                .filter_map(|(canon, _)| sibling(canon))
+                .filter(|sibling| !units.iter().any(|(canon, _)| canon == sibling))
+                .collect::<Vec<_>>();
+            let mut complete = true;
+            for sibling in siblings {
+                match dump
+                    .get_data(sibling.as_str())
+                    .or_else(|_| dump.get_resource(sibling.as_str()))
+                {
+                    Ok(res) => {
+                        if let Some(MergeableResource::MapUnit(unit)) = res.as_mergeable() {
+                            units.push((sibling, UnitRecord::new(unit, Default::default())));
+                        } else {
+                            complete = false;
+                        }
+                    }
+                    Err(e) => {
+                        log::debug!("Could not load {sibling} to validate {section}: {e}");
+                        complete = false;
+                    }
+                }
+            }
+            issues.extend(check_section(&section, &units, complete));
+        }
+        issues
+    }
+}
+
+fn check_section(section: &str, units: &[(String, UnitRecord)], complete: bool) -> Vec<MapIssue> {
+    let mods_of = |entries: &[(usize, u32)]| -> Vec<String> {
+        entries
+            .iter()
+            .flat_map(|(i, id)| units[*i].1.owners.get(id).into_iter().flatten())
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    };
+    let units_of = |entries: &[(usize, u32)]| -> Vec<String> {
+        entries
+            .iter()
+            .map(|(i, _)| units[*i].0.clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    };
+    let mut by_id: BTreeMap<u32, Vec<(usize, u32)>> = BTreeMap::new();
+    let mut by_srt: BTreeMap<u32, Vec<(usize, u32)>> = BTreeMap::new();
+    for (i, (_, unit)) in units.iter().enumerate() {
+        for &id in &unit.hash_ids {
+            by_id.entry(id).or_default().push((i, id));
+        }
+        for &(id, srt) in &unit.srt_hashes {
+            by_srt.entry(srt).or_default().push((i, id));
+        }
+    }
+    let mut issues = vec![];
+    for (kind, found) in [
+        (MapIssueKind::DuplicateHashId, by_id),
+        (MapIssueKind::DuplicateSrtHash, by_srt),
+    ] {
+        for (value, entries) in found.into_iter().filter(|(_, e)| e.len() > 1) {
+            let mods = mods_of(&entries);
+            if !mods.is_empty() {
+                issues.push(MapIssue {
+                    kind,
+                    section: section.into(),
+                    value,
+                    units: units_of(&entries),
+                    mods,
+                });
+            }
+        }
+    }
+    if complete {
+        let ids: HashSet<u32> = units
+            .iter()
+            .flat_map(|(_, unit)| unit.hash_ids.iter().copied())
+            .collect();
+        for (i, (_, unit)) in units.iter().enumerate() {
+            for &(src, dest) in unit.links.iter().filter(|(_, dest)| !ids.contains(dest)) {
+                // Blame both the linking object and any removal of its
+                // destination.
+                let entries = std::iter::once((i, src))
+                    .chain((0..units.len()).map(|j| (j, dest)))
+                    .collect::<Vec<_>>();
+                let mods = mods_of(&entries);
+                if !mods.is_empty() {
+                    issues.push(MapIssue {
+                        kind: MapIssueKind::DanglingLink,
+                        section: section.into(),
+                        value: dest,
+                        units: units_of(&[(i, src)]),
+                        mods,
+                    });
+                }
+            }
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_section() {
+        let owners = |ids: &[u32]| -> HashMap<u32, Vec<String>> {
+            ids.iter()
+                .map(|id| (*id, vec!["Test Mod".into()]))
+                .collect()
+        };
+        let units: Vec<(String, UnitRecord)> = vec![
+            ("Map/MainField/A-1/A-1_Static.mubin".into(), UnitRecord {
+                hash_ids: vec![1, 2],
+                srt_hashes: vec![(1, 10), (2, 20)],
+                links: vec![(1, 2)],
+                owners: Default::default(),
+            }),
+            ("Map/MainField/A-1/A-1_Dynamic.mubin".into(), UnitRecord {
+                hash_ids: vec![2, 3],
+                srt_hashes: vec![(2, 30), (3, 10)],
+                links: vec![(3, 4), (2, 5)],
+                owners: owners(&[2, 3]),
+            }),
+        ];
+        let issues = super::check_section("Map/MainField/A-1", &units, true);
+        let found = issues
+            .iter()
+            .map(|issue| (issue.kind, issue.value))
+            .collect::<Vec<_>>();
+        assert_eq!(found, vec![
+            (MapIssueKind::DuplicateHashId, 2),
+            (MapIssueKind::DuplicateSrtHash, 10),
+            (MapIssueKind::DanglingLink, 4),
+            (MapIssueKind::DanglingLink, 5),
+        ]);
+        assert_eq!(issues[0].units.len(), 2);
+        assert_eq!(issues[0].mods, vec![String::from("Test Mod")]);
+        assert!(super::check_section("Map/MainField/A-1", &units, false)
+            .iter()
+            .all(|issue| issue.kind != MapIssueKind::DanglingLink));
+    }
+
+    #[test]
+    fn rehash_across_units() {
+        use roead::byml::map;
+        let unit = |objs: Vec<Byml>| -> MapUnit {
+            MapUnit::try_from(&map!(
+                "Objs" => Byml::Array(objs),
+                "Rails" => Byml::Array(vec![])
+            ))
+            .unwrap()
+        };
+        let obj = |id: u32, name: &str| -> Byml {
+            map!(
+                "HashId" => Byml::U32(id),
+                "UnitConfigName" => Byml::String(name.into())
+            )
+        };
+        let linked = map!(
+            "HashId" => Byml::U32(4),
+            "LinksToObj" => Byml::Array(vec![map!("DestUnitHashId" => Byml::U32(3))])
+        );
+        let static_canon = "Map/MainField/A-1/A-1_Static.mubin";
+        let static_base = unit(vec![obj(1, "Obj_Base")]);
+        let dynamic_base = unit(vec![obj(2, "Obj_Base")]);
+        let (mod_a, mod_b): (String, String) = ("Mod A".into(), "Mod B".into());
+        let order = [&mod_a, &mod_b];
+        let version = |unit: MapUnit| {
+            Arc::new(ResourceData::Mergeable(MergeableResource::MapUnit(
+                Box::new(unit),
+            )))
+        };
+        // Mod B adds a different object under the same HashId to the other
+        // unit, and links to it from the static unit.
+        let mut static_versions = VecDeque::from([
+            version(unit(vec![obj(3, "Obj_A")])),
+            version(unit(vec![linked])),
+        ]);
+        let mut dynamic_versions = VecDeque::from([version(unit(vec![obj(3, "Obj_B")]))]);
+        let checker = MapChecker {
+            rehash: true,
+            ..Default::default()
+        };
+        checker.prepare(
+            static_canon,
+            &static_base,
+            &mut static_versions,
+            &VecDeque::from([&mod_a, &mod_b]),
+            &order,
+            |canon| {
+                Some(SectionUnit {
+                    canon: canon.into(),
+                    base:  dynamic_base.clone(),
+                    diffs: vec![(mod_b.clone(), unit(vec![obj(3, "Obj_B")]))],
+                })
+            },
+        );
+        checker.prepare(
+            "Map/MainField/A-1/A-1_Dynamic.mubin",
+            &dynamic_base,
+            &mut dynamic_versions,
+            &VecDeque::from([&mod_b]),
+            &order,
+            |_| None,
+        );
+        let Some(MergeableResource::MapUnit(dynamic)) = dynamic_versions[0].as_mergeable() else {
+            panic!("Not a map unit");
+        };
+        let new = dynamic.hash_ids().collect::<Vec<_>>();
+        assert_eq!(new.len(), 1);
+        assert!(![1, 2, 3, 4].contains(&new[0]));
+        let Some(MergeableResource::MapUnit(static_b)) = static_versions[1].as_mergeable() else {
+            panic!("Not a map unit");
+        };
+        assert_eq!(static_b.links().collect::<Vec<_>>(), vec![(4, new[0])]);
+        let Some(MergeableResource::MapUnit(static_a)) = static_versions[0].as_mergeable() else {
+            panic!("Not a map unit");
+        };
+        assert_eq!(static_a.hash_ids().collect::<Vec<_>>(), vec![3]);
+    }
+}
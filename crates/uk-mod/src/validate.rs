@@ -0,0 +1,676 @@
+use std::{
+    collections::BTreeSet,
+    fmt,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow_ext::{Context, Result};
+use join_str::jstr;
+use rayon::prelude::*;
+use serde::Serialize;
+use smartstring::alias::String;
+use uk_content::{
+    canonicalize,
+    constants::Language,
+    prelude::Endian,
+    resource::{MergeableResource, ResourceData},
+};
+use uk_reader::ResourceReader;
+
+use crate::{
+    unpack::{self, init_decompressor, ModReader, ModUnpacker, ParallelZipReader},
+    Manifest, Meta, ModOption, ModOptionGroup, ModPlatform, OptionGroup,
+};
+
+/// The most option combinations to merge with the game dump. Mods with more
+/// combinations than this only have each option checked on its own.
+const MAX_COMBINATIONS: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Something which works, but probably not as the author intended.
+    Warning,
+    /// Something which will fail to install or merge.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Warning => "warning".fmt(f),
+            Self::Error => "error".fmt(f),
+        }
+    }
+}
+
+/// A problem found validating a mod.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Issue {
+    pub severity: Severity,
+    /// Canonical path of the file the problem is in, if any.
+    pub file:     Option<String>,
+    /// Paths of the options enabled when the problem was found, if it only
+    /// happens with some options.
+    pub options:  Option<Vec<PathBuf>>,
+    pub message:  std::string::String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.severity)?;
+        if let Some(file) = self.file.as_ref() {
+            write!(f, "{file}: ")?;
+        }
+        write!(f, "{}", self.message)?;
+        if let Some(options) = self.options.as_ref() {
+            if options.is_empty() {
+                write!(f, " (with no options)")?;
+            } else {
+                write!(
+                    f,
+                    " (with options {})",
+                    options
+                        .iter()
+                        .map(|o| o.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Everything found validating a mod.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ValidationReport {
+    pub name: String,
+    /// How many option combinations were merged with the game dump. None are
+    /// when validating without a dump.
+    pub combinations: usize,
+    pub issues: Vec<Issue>,
+}
+
+impl ValidationReport {
+    #[inline]
+    pub fn errors(&self) -> impl Iterator<Item = &Issue> {
+        self.issues.iter().filter(|i| i.severity == Severity::Error)
+    }
+
+    #[inline]
+    pub fn warnings(&self) -> impl Iterator<Item = &Issue> {
+        self.issues
+            .iter()
+            .filter(|i| i.severity == Severity::Warning)
+    }
+
+    /// Whether the mod has no errors. Warnings do not count.
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    fn error(&mut self, file: Option<&str>, message: impl Into<std::string::String>) {
+        self.push(Severity::Error, file, None, message.into());
+    }
+
+    fn warning(&mut self, file: Option<&str>, message: impl Into<std::string::String>) {
+        self.push(Severity::Warning, file, None, message.into());
+    }
+
+    /// Add an issue unless the same one was already found, e.g. with fewer
+    /// options enabled.
+    fn push(
+        &mut self,
+        severity: Severity,
+        file: Option<&str>,
+        options: Option<Vec<PathBuf>>,
+        message: std::string::String,
+    ) {
+        let file = file.map(String::from);
+        if !self
+            .issues
+            .iter()
+            .any(|i| i.file == file && i.message == message)
+        {
+            self.issues.push(Issue {
+                severity,
+                file,
+                options,
+                message,
+            });
+        }
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in &self.issues {
+            writeln!(f, "{issue}")?;
+        }
+        write!(
+            f,
+            "{}: {} errors, {} warnings",
+            self.name,
+            self.errors().count(),
+            self.warnings().count()
+        )
+    }
+}
+
+/// Checks a packaged mod for problems which would keep it from installing or
+/// merging, so authors can find them before users do.
+pub struct ModValidator {
+    path: PathBuf,
+    dump: Option<(Arc<ResourceReader>, Endian, Language)>,
+}
+
+impl ModValidator {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            dump: None,
+        }
+    }
+
+    /// Also merge every combination of the mod's options with a game dump,
+    /// to check that its changes apply cleanly.
+    pub fn with_dump(mut self, dump: Arc<ResourceReader>, endian: Endian, lang: Language) -> Self {
+        self.dump = Some((dump, endian, lang));
+        self
+    }
+
+    pub fn validate(self) -> Result<ValidationReport> {
+        if !self.path.is_file() {
+            anyhow_ext::bail!("Only packaged mods can be validated");
+        }
+        let zip = ParallelZipReader::open(&self.path, true)
+            .with_context(|| jstr!("Failed to open mod at {&self.path.display().to_string()}"))?;
+        let meta: Meta =
+            serde_yaml::from_slice(&zip.get_file("meta.yml").context("Mod missing meta file")?)
+                .context("Failed to parse meta file from mod")?;
+        let mut report = ValidationReport {
+            name: meta.name.clone(),
+            ..Default::default()
+        };
+        log::info!("Validating {}", &meta.name);
+        check_api(&meta, &mut report);
+        check_options(&meta, &mut report);
+        check_masters(&meta, &mut report);
+        check_thumbnail(&zip, &mut report);
+
+        let mut roots = vec![(None, PathBuf::from("manifest.yml"))];
+        roots.extend(
+            meta.all_options()
+                .map(|opt| (Some(opt), opt.manifest_path())),
+        );
+        for (option, manifest_path) in roots {
+            let manifest = match zip.get_file(&manifest_path).and_then(|data| {
+                serde_yaml::from_slice::<Manifest>(&data).with_context(|| {
+                    jstr!("Failed to parse {&manifest_path.display().to_string()}")
+                })
+            }) {
+                Ok(manifest) => manifest,
+                Err(e) => {
+                    report.error(None, match option {
+                        Some(option) => {
+                            format!(
+                                "Option {} has no valid manifest at {}: {e:#}",
+                                option.name,
+                                manifest_path.display()
+                            )
+                        }
+                        None => format!("Mod has no valid manifest: {e:#}"),
+                    });
+                    continue;
+                }
+            };
+            self.check_resources(&zip, &meta, option, &manifest, &mut report);
+        }
+
+        if let Some((dump, endian, lang)) = self.dump.as_ref() {
+            match meta.platform {
+                ModPlatform::Specific(platform) if platform != *endian => {
+                    report.warning(
+                        None,
+                        format!(
+                            "Mod is for {}, so it was not merged with the game dump for the \
+                             current platform",
+                            meta.platform
+                        ),
+                    );
+                }
+                _ => {
+                    let (combinations, complete) = option_combinations(&meta);
+                    if !complete {
+                        report.warning(
+                            None,
+                            format!(
+                                "Mod has more than {MAX_COMBINATIONS} option combinations, so \
+                                 only each option on its own was merged with the game dump"
+                            ),
+                        );
+                    }
+                    report.combinations = combinations.len();
+                    for options in combinations {
+                        self.check_merge(dump, *endian, *lang, options, &mut report);
+                    }
+                }
+            }
+        }
+        log::info!(
+            "Finished validating {}: {} errors, {} warnings",
+            &meta.name,
+            report.errors().count(),
+            report.warnings().count()
+        );
+        Ok(report)
+    }
+
+    /// Checks that every file in a manifest is in the mod and can be read.
+    fn check_resources(
+        &self,
+        zip: &ParallelZipReader,
+        meta: &Meta,
+        option: Option<&ModOption>,
+        manifest: &Manifest,
+        report: &mut ValidationReport,
+    ) {
+        let root = option
+            .map(|opt| Path::new("options").join(&opt.path))
+            .unwrap_or_default();
+        let decompressor = init_decompressor();
+        let issues = manifest
+            .content_files
+            .iter()
+            .map(|file| file.to_string())
+            .chain(
+                manifest
+                    .aoc_files
+                    .iter()
+                    .map(|file| jstr!("Aoc/0010/{file}")),
+            )
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .filter_map(|file| {
+                let canon = canonicalize(&file);
+                let Ok(data) = zip.get_file(root.join(canon.as_str())) else {
+                    // A file is left out of the mod when the packager finds the
+                    // same version in the game dump or a master, so this is
+                    // only a problem if the dump does not have it either.
+                    return match self.dump.as_ref() {
+                        None => {
+                            Some((
+                                Severity::Warning,
+                                canon,
+                                "File is not in the mod, so it must come from the game dump or a \
+                                 master"
+                                    .into(),
+                            ))
+                        }
+                        Some((dump, ..)) if in_dump(dump, &file, &canon) => None,
+                        Some(_) if meta.masters.is_empty() => {
+                            Some((
+                                Severity::Error,
+                                canon,
+                                "File is not in the mod or the game dump, and the mod has no \
+                                 masters to provide it"
+                                    .into(),
+                            ))
+                        }
+                        Some(_) => {
+                            Some((
+                                Severity::Warning,
+                                canon,
+                                "File is not in the mod or the game dump, so it must come from a \
+                                 master"
+                                    .into(),
+                            ))
+                        }
+                    };
+                };
+                let size =
+                    zstd::bulk::Decompressor::upper_bound(&data).unwrap_or(data.len() * 1024);
+                let data = match decompressor
+                    .lock()
+                    .decompress(&data, size)
+                    .or_else(|_| zstd::decode_all(data.as_slice()))
+                {
+                    Ok(data) => data,
+                    Err(e) => {
+                        return Some((
+                            Severity::Error,
+                            canon,
+                            format!("Failed to decompress: {e}"),
+                        ));
+                    }
+                };
+                match minicbor_ser::from_slice::<ResourceData>(&data) {
+                    Err(e) => {
+                        Some((
+                            Severity::Error,
+                            canon,
+                            format!("Failed to deserialize: {e}"),
+                        ))
+                    }
+                    Ok(ResourceData::Binary(_)) if meta.platform == ModPlatform::Universal => {
+                        Some((
+                            Severity::Error,
+                            canon,
+                            "File is not a mergeable asset, but cross-platform mods must consist \
+                             only of mergeable assets"
+                                .into(),
+                        ))
+                    }
+                    Ok(ResourceData::Mergeable(MergeableResource::BinaryOverride(v))) => {
+                        Some((
+                            Severity::Warning,
+                            canon,
+                            format!(
+                                "File could not be parsed when the mod was packaged, so it will \
+                                 replace every other mod's changes to it: {}",
+                                v.1
+                            ),
+                        ))
+                    }
+                    Ok(_) => None,
+                }
+            })
+            .collect::<Vec<_>>();
+        for (severity, file, message) in issues {
+            report.push(
+                severity,
+                Some(file.as_str()),
+                option.map(|opt| vec![opt.path.clone()]),
+                message,
+            );
+        }
+    }
+
+    /// Merges the mod with some options enabled onto the game dump without
+    /// writing anything.
+    fn check_merge(
+        &self,
+        dump: &Arc<ResourceReader>,
+        endian: Endian,
+        lang: Language,
+        options: Vec<ModOption>,
+        report: &mut ValidationReport,
+    ) {
+        let paths = options.iter().map(|o| o.path.clone()).collect::<Vec<_>>();
+        log::debug!("Merging {} with options {:?}", &report.name, &paths);
+        let reader = match ModReader::open(&self.path, options) {
+            Ok(reader) => reader,
+            Err(e) => {
+                report.push(
+                    Severity::Error,
+                    None,
+                    Some(paths),
+                    format!("Failed to open mod: {e:#}"),
+                );
+                return;
+            }
+        };
+        let failed = ModUnpacker::new(dump.clone(), endian, lang, vec![reader], PathBuf::new())
+            .with_dry_run(true)
+            .check_files();
+        for (file, e) in failed {
+            report.push(
+                Severity::Error,
+                Some(file.as_str()),
+                Some(paths.clone()),
+                format!("Failed to merge with the game dump: {e:#}"),
+            );
+        }
+    }
+}
+
+/// Whether the game dump has a file, by its path in the content or DLC folder
+/// or by its canonical path.
+fn in_dump(dump: &ResourceReader, file: &str, canon: &str) -> bool {
+    dump.get_data(file)
+        .or_else(|_| dump.get_data(canon))
+        .or_else(|_| dump.get_resource(canon))
+        .is_ok()
+}
+
+/// Checks that the mod was made with a version of UKMM this one can install.
+fn check_api(meta: &Meta, report: &mut ValidationReport) {
+    match unpack::check_api(&meta.name, &meta.api) {
+        Ok(Some(warning)) => report.warning(None, warning),
+        Ok(None) => (),
+        Err(e) => report.error(None, format!("{e:#}")),
+    }
+}
+
+/// Checks that option defaults and requirements name real options.
+fn check_options(meta: &Meta, report: &mut ValidationReport) {
+    if let Err(e) = meta.validate_requirements() {
+        report.error(None, e.to_string());
+    }
+    let mut paths = BTreeSet::new();
+    for option in meta.all_options() {
+        if !paths.insert(&option.path) {
+            report.error(
+                None,
+                format!(
+                    "More than one option uses the path {}",
+                    option.path.display()
+                ),
+            );
+        }
+    }
+    for group in &meta.options {
+        let defaults = match group {
+            OptionGroup::Exclusive(group) => group.default.iter().collect::<Vec<_>>(),
+            OptionGroup::Multiple(group) => group.defaults.iter().collect(),
+        };
+        for default in defaults {
+            if !group.options().iter().any(|opt| &opt.path == default) {
+                report.error(
+                    None,
+                    format!(
+                        "The default {} of the option group {} is not one of its options",
+                        default.display(),
+                        group.name()
+                    ),
+                );
+            }
+        }
+        if group.options().is_empty() {
+            report.warning(
+                None,
+                format!("The option group {} has no options", group.name()),
+            );
+        }
+    }
+}
+
+/// Checks that each master has a name and a version requirement UKMM can
+/// parse.
+fn check_masters(meta: &Meta, report: &mut ValidationReport) {
+    for (name, version) in meta.masters.values() {
+        if name.trim().is_empty() {
+            report.error(None, "A master has no name");
+            continue;
+        } else if name.trim() == meta.name.trim() {
+            report.error(None, format!("The mod lists itself as the master {name}"));
+        }
+        let version = version.trim();
+        let bare = version.trim_start_matches(['v', 'V']);
+        if !version.is_empty()
+            && version != "*"
+            && lenient_semver::parse(bare).is_err()
+            && semver::VersionReq::parse(version).is_err()
+        {
+            report.warning(
+                None,
+                format!(
+                    "The version {version} of the master {name} is not a version or version \
+                     requirement, so any version will be accepted"
+                ),
+            );
+        }
+    }
+}
+
+/// Checks that the mod's thumbnail, if it has one, is a valid image.
+fn check_thumbnail(zip: &ParallelZipReader, report: &mut ValidationReport) {
+    for ext in ["jpg", "jpeg", "png", "svg"] {
+        let name = jstr!("thumb.{ext}");
+        let Ok(data) = zip.get_file(&name) else {
+            continue;
+        };
+        let valid = if ext == "svg" {
+            std::str::from_utf8(&data)
+                .map(|svg| svg.contains("<svg"))
+                .unwrap_or(false)
+        } else {
+            image::load_from_memory(&data).is_ok()
+        };
+        if !valid {
+            report.error(Some(&name), "Thumbnail is not a valid image");
+        }
+    }
+}
+
+/// Every valid combination of a mod's options, including their required
+/// options, and whether that is all of them. If there are more than
+/// [`MAX_COMBINATIONS`], only each option with its requirements is returned.
+fn option_combinations(meta: &Meta) -> (Vec<Vec<ModOption>>, bool) {
+    let count = meta.options.iter().fold(1usize, |count, group| {
+        let choices = match group {
+            OptionGroup::Exclusive(group) => group.options.len() + 1,
+            OptionGroup::Multiple(group) => {
+                1usize
+                    .checked_shl(group.options.len() as u32)
+                    .unwrap_or(usize::MAX)
+            }
+        };
+        count.saturating_mul(choices)
+    });
+    let complete = count <= MAX_COMBINATIONS;
+    let candidates: Vec<Vec<&ModOption>> = if complete {
+        meta.options
+            .iter()
+            .fold(vec![vec![]], |combinations, group| {
+                let choices: Vec<Vec<&ModOption>> = match group {
+                    OptionGroup::Exclusive(group) => {
+                        std::iter::once(vec![])
+                            .chain(group.options.iter().map(|opt| vec![opt]))
+                            .collect()
+                    }
+                    OptionGroup::Multiple(group) => {
+                        (0..1usize << group.options.len())
+                            .map(|bits| {
+                                group
+                                    .options
+                                    .iter()
+                                    .enumerate()
+                                    .filter(|(i, _)| bits & (1 << i) != 0)
+                                    .map(|(_, opt)| opt)
+                                    .collect()
+                            })
+                            .collect()
+                    }
+                };
+                combinations
+                    .iter()
+                    .flat_map(|combination| {
+                        choices
+                            .iter()
+                            .map(move |choice| combination.iter().chain(choice).copied().collect())
+                    })
+                    .collect()
+            })
+    } else {
+        std::iter::once(vec![])
+            .chain(meta.all_options().map(|opt| vec![opt]))
+            .collect()
+    };
+    let mut seen = BTreeSet::new();
+    let combinations = candidates
+        .into_iter()
+        .filter_map(|options| {
+            let options = meta
+                .with_requirements(options.into_iter().cloned().collect())
+                .ok()?;
+            meta.check_options(&options).ok()?;
+            seen.insert(
+                options
+                    .iter()
+                    .map(|opt| opt.path.clone())
+                    .collect::<BTreeSet<_>>(),
+            )
+            .then_some(options)
+        })
+        .collect();
+    (combinations, complete)
+}
+
+#[cfg(test)]
+#[test]
+fn combinations() {
+    use crate::{
+        fixtures::{meta, option},
+        ExclusiveOptionGroup, MultipleOptionGroup,
+    };
+    let meta = meta(vec![
+        OptionGroup::Multiple(MultipleOptionGroup {
+            name: "Extras".into(),
+            options: vec![option("extra", &["hard"]), option("more", &[])],
+            ..Default::default()
+        }),
+        OptionGroup::Exclusive(ExclusiveOptionGroup {
+            name: "Difficulty".into(),
+            description: Default::default(),
+            required: true,
+            default: Some("normal".into()),
+            options: vec![option("easy", &[]), option("hard", &[])],
+        }),
+    ]);
+    let (combinations, complete) = option_combinations(&meta);
+    assert!(complete);
+    let mut combinations = combinations
+        .into_iter()
+        .map(|c| {
+            let mut paths = c
+                .iter()
+                .map(|o| o.path.to_str().unwrap().to_owned())
+                .collect::<Vec<_>>();
+            paths.sort();
+            paths.join("+")
+        })
+        .collect::<Vec<_>>();
+    combinations.sort();
+    assert_eq!(combinations, vec![
+        "easy",
+        "easy+more",
+        "extra+hard",
+        "extra+hard+more",
+        "hard",
+        "hard+more",
+    ]);
+    let mut report = ValidationReport::default();
+    check_options(&meta, &mut report);
+    assert!(!report.is_valid());
+    assert_eq!(report.errors().count(), 1);
+}
+
+#[cfg(test)]
+#[test]
+fn api_versions() {
+    let mut meta = crate::fixtures::meta(vec![]);
+    let mut report = ValidationReport::default();
+    meta.api = "not a version".into();
+    check_api(&meta, &mut report);
+    meta.api = "99.0.0".into();
+    check_api(&meta, &mut report);
+    assert_eq!(report.errors().count(), 2);
+    let mut report = ValidationReport::default();
+    meta.api = env!("CARGO_PKG_VERSION").into();
+    check_api(&meta, &mut report);
+    assert!(report.issues.is_empty());
+}
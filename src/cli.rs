@@ -2,12 +2,28 @@ use std::{
     io::{stdin, stdout, Write},
     option::Option,
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 
 use anyhow_ext::{Context, Result};
+use fs_err as fs;
+use serde_json::{json, Value};
 use smartstring::alias::String;
-use uk_manager::{core, mods::LookupMod, settings::Platform};
-use uk_mod::{unpack::ModReader, Manifest, Meta};
+use uk_content::prelude::Endian;
+use uk_manager::{
+    core,
+    deploy::DeployPlan,
+    dev::{self, DevUpdate},
+    mods::{LookupMod, Mod},
+    settings::Platform,
+};
+use uk_mod::{
+    pack::sanitise, unpack::ModReader, validate::ModValidator, Manifest, Meta, ModOption,
+    ModOptionGroup, ModPlatform, OptionGroup,
+};
 
 use crate::gui::{package, tasks};
 
@@ -22,12 +38,16 @@ xflags::xflags! {
         optional -p, --portable
         /// Automatically deploy after running command (redunant with `deploy` command)
         optional -D, --deploy
+        /// Print the result of the command as JSON
+        optional --json
         /// Install a mod
         cmd install {
             /// Path to the mod to install
             required path: PathBuf
             /// The profile to install the mod in
             optional profile: String
+            /// Name or path of an option to enable. Groups without a selected option use their defaults.
+            repeated -o, --option option: String
         }
         /// Package a mod
         cmd package {
@@ -46,9 +66,123 @@ xflags::xflags! {
             optional profile: String
         }
         /// Refresh merge
-        cmd remerge {}
+        cmd remerge {
+            /// Print what remerging and deploying would change without changing anything
+            optional --dry-run
+        }
+        /// Sort the load order by mod dependencies and load order hints
+        cmd sort {
+            /// The profile to sort
+            optional profile: String
+        }
+        /// List the mods in the current profile in load order
+        cmd list {}
+        /// Enable a mod. Run `apply` to apply the change to the merge.
+        cmd enable {
+            /// Name of the mod, or its index in the load order
+            required name: String
+        }
+        /// Disable a mod. Run `apply` to apply the change to the merge.
+        cmd disable {
+            /// Name of the mod, or its index in the load order
+            required name: String
+        }
+        /// Move a mod in the load order. Run `apply` to apply the change to the merge.
+        cmd move {
+            /// Name of the mod, or its index in the load order
+            required name: String
+            /// New index of the mod in the load order, starting from 0
+            required position: usize
+        }
+        /// List or set the enabled options of a mod. Run `apply` to apply the change to the merge.
+        cmd options {
+            /// Name of the mod, or its index in the load order
+            required name: String
+            /// Names or paths of the options to enable, replacing the enabled ones
+            repeated option: String
+            /// Disable all options of the mod
+            optional --clear
+        }
+        /// Apply changes made by the enable, disable, move, options, and profile commands to the merge
+        cmd apply {
+            /// Print what applying and deploying would change without changing anything
+            optional --dry-run
+        }
+        /// List profiles
+        cmd profiles {}
+        /// Manage profiles
+        cmd profile {
+            /// Create a new empty profile
+            cmd create {
+                /// Name of the new profile
+                required name: String
+            }
+            /// Copy a profile
+            cmd duplicate {
+                /// Name of the profile to copy
+                required name: String
+                /// Name of the copy, by default the name of the profile with `_copy` added
+                optional new_name: String
+            }
+            /// Delete a profile, along with any mods no other profile uses
+            cmd delete {
+                /// Name of the profile to delete
+                required name: String
+            }
+            /// Switch to another profile. Run `apply` to apply the change to the merge.
+            cmd switch {
+                /// Name of the profile to switch to
+                required name: String
+            }
+        }
+        /// Convert a BNP, graphic pack, or RomFS mod to a UKMM mod without installing it
+        cmd convert {
+            /// Path to the mod, or to a folder of mods to convert them all
+            required path: PathBuf
+            /// Path to write the UKMM mod to, or a folder to write it to under its name
+            required output: PathBuf
+        }
+        /// Export a UKMM mod as a BNP for BCML
+        cmd export-bnp {
+            /// Name of an installed mod or its index in the load order, or the path to a mod file
+            required name: String
+            /// Path to write the BNP to, or a folder to write it to under the mod's name
+            required output: PathBuf
+            /// Name or path of an option to apply to the BNP's base files. Other options become BNP options.
+            repeated -o, --option option: String
+        }
+        /// Convert a UKMM mod to the other platform (Wii U or Switch)
+        cmd convert-platform {
+            /// Name of an installed mod or its index in the load order, or the path to a mod file
+            required name: String
+            /// Path to write the converted mod to, or a folder to write it to under the mod's name
+            required output: PathBuf
+        }
+        /// Check a mod for problems before releasing it
+        cmd validate {
+            /// Name of an installed mod or its index in the load order, or the path to a mod file
+            required name: String
+            /// Skip merging the mod with the game dump
+            optional --no-merge
+        }
+        /// Check the merged map units of the enabled mods for HashId and SRTHash collisions and dangling links
+        cmd check-maps {}
+        /// Link a mod to its source folder, then repackage and remerge it whenever the folder changes
+        cmd dev {
+            /// Name of the mod, or its index in the load order
+            required name: String
+            /// Source folder to link the mod to. Only needed the first time.
+            optional folder: PathBuf
+        }
         /// Deploy mods
-        cmd deploy {}
+        cmd deploy {
+            /// Only deploy to the deploy target with this name
+            optional -t, --target target: String
+            /// Deploy even if the SD card already has game files UKMM did not deploy
+            optional --allow-unmanaged
+            /// Print what deploying would change without changing anything
+            optional --dry-run
+        }
         /// Change current mode (Switch or Wii U)
         cmd mode {
             /// Mode to activate (Switch or Wii U)
@@ -67,6 +201,7 @@ pub struct Ukmm {
     #[allow(dead_code)]
     pub portable: bool,
     pub deploy: bool,
+    pub json: bool,
     pub subcommand: UkmmCmd,
 }
 
@@ -76,6 +211,21 @@ pub enum UkmmCmd {
     Uninstall(Uninstall),
     Package(Package),
     Remerge(Remerge),
+    Sort(Sort),
+    List(List),
+    Enable(Enable),
+    Disable(Disable),
+    Move(Move),
+    Options(Options),
+    Apply(Apply),
+    Profiles(Profiles),
+    Profile(Profile),
+    Convert(Convert),
+    ExportBnp(ExportBnp),
+    ConvertPlatform(ConvertPlatform),
+    Validate(Validate),
+    CheckMaps(CheckMaps),
+    Dev(Dev),
     Deploy(Deploy),
     Mode(Mode),
 }
@@ -84,6 +234,7 @@ pub enum UkmmCmd {
 pub struct Install {
     pub path:    PathBuf,
     pub profile: Option<String>,
+    pub option:  Vec<String>,
 }
 
 #[derive(Debug)]
@@ -100,10 +251,123 @@ pub struct Uninstall {
 }
 
 #[derive(Debug)]
-pub struct Remerge;
+pub struct Remerge {
+    pub dry_run: bool,
+}
+
+#[derive(Debug)]
+pub struct Sort {
+    pub profile: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct List;
+
+#[derive(Debug)]
+pub struct Enable {
+    pub name: String,
+}
+
+#[derive(Debug)]
+pub struct Disable {
+    pub name: String,
+}
+
+#[derive(Debug)]
+pub struct Move {
+    pub name:     String,
+    pub position: usize,
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub name:   String,
+    pub option: Vec<String>,
+    pub clear:  bool,
+}
+
+#[derive(Debug)]
+pub struct Apply {
+    pub dry_run: bool,
+}
+
+#[derive(Debug)]
+pub struct Profiles;
+
+#[derive(Debug)]
+pub struct Profile {
+    pub subcommand: ProfileCmd,
+}
+
+#[derive(Debug)]
+pub enum ProfileCmd {
+    Create(Create),
+    Duplicate(Duplicate),
+    Delete(Delete),
+    Switch(Switch),
+}
+
+#[derive(Debug)]
+pub struct Create {
+    pub name: String,
+}
+
+#[derive(Debug)]
+pub struct Duplicate {
+    pub name:     String,
+    pub new_name: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Delete {
+    pub name: String,
+}
+
+#[derive(Debug)]
+pub struct Switch {
+    pub name: String,
+}
+
+#[derive(Debug)]
+pub struct Convert {
+    pub path:   PathBuf,
+    pub output: PathBuf,
+}
+
+#[derive(Debug)]
+pub struct ExportBnp {
+    pub name:   String,
+    pub output: PathBuf,
+    pub option: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct ConvertPlatform {
+    pub name:   String,
+    pub output: PathBuf,
+}
+
+#[derive(Debug)]
+pub struct Validate {
+    pub name:     String,
+    pub no_merge: bool,
+}
+
+#[derive(Debug)]
+pub struct CheckMaps;
 
 #[derive(Debug)]
-pub struct Deploy;
+pub struct Dev {
+    pub name:   String,
+    pub folder: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub struct Deploy {
+    pub target: Option<String>,
+    pub allow_unmanaged: bool,
+    pub dry_run: bool,
+}
 
 #[derive(Debug)]
 pub struct Mode {
@@ -137,6 +401,123 @@ macro_rules! input {
     }};
 }
 
+/// Print progress for people to read, unless printing JSON instead.
+macro_rules! say {
+    ($self:ident, $($arg:tt)*) => {
+        if !$self.cli.json {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Return early with a [`UserError`].
+macro_rules! user_bail {
+    ($($arg:tt)*) => {
+        return Err(UserError(format!($($arg)*)).into())
+    };
+}
+
+/// Exit code when a command did what it was asked.
+const EXIT_OK: i32 = 0;
+/// Exit code when something went wrong inside UKMM.
+const EXIT_FAILURE: i32 = 1;
+/// Exit code when a command was used wrongly, e.g. naming a mod which does
+/// not exist.
+const EXIT_USER_ERROR: i32 = 2;
+/// Exit code when a command had nothing to do, e.g. deploying without any
+/// pending changes.
+const EXIT_NOTHING_TO_DO: i32 = 3;
+/// Exit code when a command finished but found problems, e.g. some mods in a
+/// batch conversion failed or a validated mod has errors.
+const EXIT_PROBLEMS: i32 = 4;
+
+/// An error in how a command was used, rather than a failure in UKMM.
+#[derive(Debug)]
+struct UserError(std::string::String);
+
+impl std::fmt::Display for UserError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for UserError {}
+
+/// What a command did, printed as its result with `--json`.
+#[derive(Debug)]
+enum Outcome {
+    Done(Value),
+    NothingToDo(Value),
+    /// The command finished, but some of its work failed, e.g. some mods in
+    /// a batch conversion.
+    Failed(Value),
+}
+
+/// An error with its context chain, from the outermost context inwards.
+fn error_json(error: &anyhow_ext::Error) -> Value {
+    json!({
+        "message": error.to_string(),
+        "context": error.chain().map(|cause| cause.to_string()).collect::<Vec<_>>(),
+    })
+}
+
+/// Run a command, print its result, and return the exit code.
+pub fn run(cli: Ukmm) -> i32 {
+    if cli.debug {
+        env_logger::init();
+        log::set_max_level(log::LevelFilter::Debug);
+    }
+    let json = cli.json;
+    match Runner::new(cli).and_then(Runner::run) {
+        Ok(outcome) => {
+            let (status, code, result) = match outcome {
+                Outcome::Done(result) => ("ok", EXIT_OK, result),
+                Outcome::NothingToDo(result) => ("nothing_to_do", EXIT_NOTHING_TO_DO, result),
+                Outcome::Failed(result) => ("failed", EXIT_PROBLEMS, result),
+            };
+            if json {
+                println!("{}", json!({ "status": status, "result": result }));
+            }
+            code
+        }
+        Err(e) => {
+            let user = e.chain().any(|cause| cause.is::<UserError>());
+            if json {
+                let mut error = error_json(&e);
+                error["kind"] = json!(if user { "user" } else { "internal" });
+                println!("{}", json!({ "status": "error", "error": error }));
+            } else {
+                eprintln!("Error: {e:?}");
+            }
+            if user {
+                EXIT_USER_ERROR
+            } else {
+                EXIT_FAILURE
+            }
+        }
+    }
+}
+
+/// A mod as listed by the `list` command.
+fn mod_json(index: usize, mod_: &Mod) -> Value {
+    json!({
+        "index": index,
+        "name": mod_.meta.name,
+        "version": mod_.meta.version,
+        "author": mod_.meta.author,
+        "hash": mod_.hash().to_string(),
+        "enabled": mod_.enabled,
+        "options": option_paths(&mod_.enabled_options),
+    })
+}
+
+fn option_paths(options: &[ModOption]) -> Vec<std::borrow::Cow<'_, str>> {
+    options
+        .iter()
+        .map(|opt| opt.path.to_string_lossy())
+        .collect()
+}
+
 #[derive(Debug)]
 pub struct Runner {
     core: core::Manager,
@@ -144,14 +525,14 @@ pub struct Runner {
 }
 
 impl Runner {
-    pub fn new(cli: Ukmm) -> Self {
-        Self {
-            core: core::Manager::init().unwrap(),
+    pub fn new(cli: Ukmm) -> Result<Self> {
+        Ok(Self {
+            core: core::Manager::init()?,
             cli,
-        }
+        })
     }
 
-    fn check_mod(&self, path: &Path) -> Result<Option<PathBuf>> {
+    fn check_mod(&self, path: &Path, options: &[String]) -> Result<(PathBuf, Vec<ModOption>)> {
         let (mod_, path) = match ModReader::open(path, vec![]) {
             Ok(mod_) => (mod_, path.to_path_buf()),
             Err(e) => {
@@ -165,7 +546,7 @@ impl Runner {
                         )
                     }
                     Err(e2) => {
-                        anyhow_ext::bail!(
+                        user_bail!(
                             "Could not open mod. Error when attempting to open as UKMM mod: {}. \
                              Error when attempting to open as legacy mod: {}.",
                             e,
@@ -175,120 +556,879 @@ impl Runner {
                 }
             }
         };
-        if !mod_.meta.options.is_empty() {
-            anyhow_ext::bail!(
-                "This mod contains configuration options and should be installed via the GUI."
-            );
+        let options = select_options(&mod_.meta, options)?;
+        say!(self, "Installing {}...", mod_.meta.name);
+        Ok((path, options))
+    }
+
+    /// Fail if `target` names a deploy target which is not configured.
+    fn check_target(&self, target: Option<&str>) -> Result<()> {
+        if let Some(target) = target {
+            let settings = self.core.settings();
+            if settings
+                .platform_config()
+                .and_then(|c| c.deploy_target(target))
+                .is_none()
+            {
+                user_bail!("No deploy target named {target}");
+            }
         }
-        println!("Installing {}...", mod_.meta.name);
-        Ok(Some(path))
+        Ok(())
     }
 
-    fn deploy(&self) -> Result<()> {
+    /// Deploy pending changes, returning whether there were any.
+    fn deploy(&self, target: Option<&str>) -> Result<bool> {
         let deployer = self.core.deploy_manager();
-        if deployer.pending() {
-            println!("Deploying changes...");
+        if let Some(target) = target {
+            if deployer.target_pending(target) {
+                say!(self, "Deploying changes to {target}...");
+                deployer.deploy_target(target)?;
+                say!(self, "Deployment complete");
+                Ok(true)
+            } else {
+                say!(self, "No changes pending deployment to {target}");
+                Ok(false)
+            }
+        } else if deployer.pending() {
+            say!(self, "Deploying changes...");
             deployer.deploy()?;
-            println!("Deployment complete");
+            say!(self, "Deployment complete");
+            Ok(true)
         } else {
-            println!("No changes pending deployment");
-        };
+            say!(self, "No changes pending deployment");
+            Ok(false)
+        }
+    }
+
+    /// Deploy if asked to with `--deploy`, then add whether anything was
+    /// deployed and how many changes are left to deploy to a result.
+    fn finish(&self, mut result: Value) -> Result<Value> {
+        let deployed = self.cli.deploy && self.deploy(None)?;
+        result["deployed"] = json!(deployed);
+        result["pending_deploy"] = json!(self.core.deploy_manager().pending_len());
+        Ok(result)
+    }
+
+    /// Print the plan from a dry run.
+    fn plan(&self, plan: DeployPlan) -> Result<Outcome> {
+        if !self.cli.json {
+            print!("{plan}");
+        }
+        let result = serde_json::to_value(&plan)?;
+        Ok(if plan.is_empty() {
+            Outcome::NothingToDo(result)
+        } else {
+            Outcome::Done(result)
+        })
+    }
+
+    /// Find a mod in the current profile by its index in the load order or
+    /// by its name.
+    fn find_mod(&self, name: &str) -> Result<Mod> {
+        let mods = self.core.mod_manager().all_mods().collect::<Vec<_>>();
+        if let Ok(index) = name.parse::<usize>() {
+            return match mods.get(index) {
+                Some(mod_) => Ok(mod_.clone()),
+                None => user_bail!("Mod {} does not exist", index),
+            };
+        }
+        let mut found = mods
+            .into_iter()
+            .filter(|m| m.meta.name.trim().eq_ignore_ascii_case(name.trim()));
+        match (found.next(), found.next()) {
+            (Some(mod_), None) => Ok(mod_),
+            (Some(_), Some(_)) => {
+                user_bail!("More than one mod is named {name}, use its index instead")
+            }
+            (None, _) => user_bail!("No mod named {name} in the current profile"),
+        }
+    }
+
+    fn profile_exists(&self, name: &str) -> bool {
+        self.core.settings().profiles().any(|p| p.as_str() == name)
+    }
+
+    /// Changes made to the current profile from the command line which have
+    /// not been applied to the merge yet, kept until the `apply` command.
+    fn dirty_path(&self) -> PathBuf {
+        self.core.mod_manager().path().join("dirty.yml")
+    }
+
+    fn dirty(&self) -> Result<Manifest> {
+        let path = self.dirty_path();
+        if path.exists() {
+            serde_yaml::from_str(&fs::read_to_string(path)?)
+                .context("Failed to read pending changes")
+        } else {
+            Ok(Manifest::default())
+        }
+    }
+
+    fn add_dirty(&self, manifest: &Manifest) -> Result<()> {
+        let mut dirty = self.dirty()?;
+        dirty.extend(manifest);
+        fs::write(self.dirty_path(), serde_yaml::to_string(&dirty)?)?;
         Ok(())
     }
 
-    pub fn run(self) -> Result<()> {
-        if self.cli.debug {
-            env_logger::init();
-            log::set_max_level(log::LevelFilter::Debug);
+    fn set_enabled(&self, name: &str, enabled: bool) -> Result<Outcome> {
+        let mod_ = self.find_mod(name)?;
+        let state = if enabled { "enabled" } else { "disabled" };
+        if mod_.enabled == enabled {
+            say!(self, "{} is already {state}", &mod_.meta.name);
+            return Ok(Outcome::NothingToDo(
+                json!({ "mod": mod_.meta.name, "enabled": enabled }),
+            ));
+        }
+        let mod_manager = self.core.mod_manager();
+        let manifest = mod_manager.set_enabled(mod_.hash(), enabled, None)?;
+        let mut warnings = vec![];
+        if enabled {
+            for issue in mod_manager.dependency_issues(&mod_, None) {
+                say!(self, "Warning: {issue}");
+                warnings.push(issue.to_string());
+            }
+        }
+        mod_manager.save()?;
+        drop(mod_manager);
+        self.add_dirty(&manifest)?;
+        say!(self, "{} is now {state}", &mod_.meta.name);
+        Ok(Outcome::Done(json!({
+            "mod": mod_.meta.name,
+            "enabled": enabled,
+            "warnings": warnings,
+        })))
+    }
+
+    fn set_options(&self, name: &str, options: &[String]) -> Result<Outcome> {
+        let mod_ = self.find_mod(name)?;
+        let options = options
+            .iter()
+            .map(|option| find_option(&mod_.meta, option))
+            .collect::<Result<Vec<_>>>()?;
+        let options = check_options(&mod_.meta, options)?;
+        let mod_manager = self.core.mod_manager();
+        let mut manifest = mod_.manifest()?.as_ref().clone();
+        manifest.extend(
+            mod_manager
+                .set_enabled_options(mod_.hash(), options.clone(), None)?
+                .as_ref(),
+        );
+        mod_manager.save()?;
+        drop(mod_manager);
+        if mod_.enabled {
+            self.add_dirty(&manifest)?;
+        }
+        say!(self, "Updated options of {}", &mod_.meta.name);
+        Ok(Outcome::Done(json!({
+            "mod": mod_.meta.name,
+            "options": option_paths(&options),
+        })))
+    }
+
+    fn switch_profile(&self, name: &str) -> Result<Outcome> {
+        if !self.profile_exists(name) {
+            user_bail!("Profile {name} does not exist");
+        }
+        // Everything either profile merges has to be merged again.
+        let mut dirty = self.dirty()?;
+        {
+            let mod_manager = self.core.mod_manager();
+            if mod_manager.profile().key().as_str() == name {
+                say!(self, "Profile {name} is already active");
+                return Ok(Outcome::NothingToDo(json!({ "profile": name })));
+            }
+            for mod_ in mod_manager.mods() {
+                dirty.extend(mod_.manifest()?.as_ref());
+            }
+            let other =
+                uk_manager::mods::Profile::iter(mod_manager.get_profile(Some(&name.into())));
+            for mod_ in other.filter(|m| m.enabled) {
+                dirty.extend(mod_.manifest()?.as_ref());
+            }
+        }
+        let old_dirty = self.dirty_path();
+        self.core.change_profile(name)?;
+        self.core.settings().save()?;
+        self.add_dirty(&dirty)?;
+        if old_dirty.exists() {
+            fs::remove_file(old_dirty)?;
+        }
+        say!(self, "Switched to profile {name}");
+        Ok(Outcome::Done(json!({ "profile": name })))
+    }
+
+    /// Print the result of updating a mod in development mode and deploy it
+    /// if asked to with `--deploy`. Errors are printed rather than returned,
+    /// so watching carries on.
+    fn dev_updated(&self, update: Result<DevUpdate>) {
+        let result = update.and_then(|update| {
+            let files = update.dirty.content_files.len() + update.dirty.aoc_files.len();
+            say!(
+                self,
+                "{} {} ({files} files changed)",
+                if update.full { "Repackaged" } else { "Updated" },
+                &update.mod_.meta.name
+            );
+            let deployed = self.cli.deploy && self.deploy(None)?;
+            Ok(json!({
+                "mod": update.mod_.meta.name,
+                "full": update.full,
+                "dirty": update.dirty,
+                "deployed": deployed,
+            }))
+        });
+        match result {
+            Ok(result) => {
+                if self.cli.json {
+                    println!("{}", json!({ "status": "ok", "result": result }));
+                }
+            }
+            Err(e) => {
+                if self.cli.json {
+                    println!("{}", json!({ "status": "error", "error": error_json(&e) }));
+                } else {
+                    eprintln!("Error: {e:?}");
+                }
+            }
         }
-        match &self.cli.subcommand {
+    }
+
+    fn run(self) -> Result<Outcome> {
+        let outcome = match &self.cli.subcommand {
             UkmmCmd::Mode(Mode { platform }) => {
                 self.core
                     .settings_mut()
                     .apply(|s| s.current_mode = *platform)?;
                 self.core.reload()?;
-                println!("Mode changed to {:?}", platform);
-                if self.cli.deploy {
-                    self.deploy()?;
-                }
-                println!("Done!");
-            }
-            UkmmCmd::Install(Install { path, profile }) => {
-                if let Some(path) = self.check_mod(path)? {
-                    let mods = self.core.mod_manager();
-                    let mod_ = mods.add(&path, profile.as_ref())?;
-                    mods.set_enabled(mod_.as_map_id(), true, profile.as_ref())?;
-                    mods.save()?;
-                    println!("Applying mod to load order...");
-                    let deployer = self.core.deploy_manager();
-                    deployer.apply(Some(mod_.manifest()?.as_ref().clone()))?;
-                    if self.cli.deploy {
-                        self.deploy()?;
-                    }
-                    println!("Done!");
+                say!(self, "Mode changed to {:?}", platform);
+                let result = self.finish(json!({ "mode": platform }))?;
+                say!(self, "Done!");
+                Outcome::Done(result)
+            }
+            UkmmCmd::Install(Install {
+                path,
+                profile,
+                option,
+            }) => {
+                let (path, options) = self.check_mod(path, option)?;
+                let mods = self.core.mod_manager();
+                let mod_ = mods.add(&path, profile.as_ref())?;
+                let manifest = if options.is_empty() {
+                    mod_.manifest()?
+                } else {
+                    mods.set_enabled_options(mod_.as_map_id(), options.clone(), profile.as_ref())?
+                };
+                mods.set_enabled(mod_.as_map_id(), true, profile.as_ref())?;
+                let mut warnings = vec![];
+                for issue in mods.dependency_issues(&mod_, profile.as_ref()) {
+                    say!(self, "Warning: {issue}");
+                    warnings.push(issue.to_string());
                 }
+                mods.save()?;
+                say!(self, "Applying mod to load order...");
+                let deployer = self.core.deploy_manager();
+                deployer.apply(Some(manifest.as_ref().clone()))?;
+                let result = self.finish(json!({
+                    "mod": mod_.meta.name,
+                    "version": mod_.meta.version,
+                    "hash": mod_.hash().to_string(),
+                    "options": option_paths(&options),
+                    "warnings": warnings,
+                }))?;
+                say!(self, "Done!");
+                Outcome::Done(result)
             }
             UkmmCmd::Package(pkg) => {
-                println!("Packaging mod...");
+                say!(self, "Packaging mod...");
                 let builder = package::ModPackerBuilder {
                     source: pkg.path.clone(),
                     dest:   pkg.output.clone(),
                     meta:   Meta::parse(&pkg.meta)?,
                 };
                 tasks::package_mod(&self.core, builder)?;
-                println!("Done!");
+                say!(self, "Done!");
+                Outcome::Done(json!({ "output": pkg.output }))
+            }
+            UkmmCmd::Remerge(Remerge { dry_run: true }) => {
+                self.plan(self.core.deploy_manager().plan_apply(None)?)?
             }
             UkmmCmd::Remerge(_) => {
-                println!("Remerging...");
+                say!(self, "Remerging...");
                 tasks::apply_changes(&self.core, vec![], None)?;
-                println!("Done!");
+                say!(self, "Done!");
+                Outcome::Done(json!({
+                    "pending_deploy": self.core.deploy_manager().pending_len(),
+                }))
             }
             UkmmCmd::Uninstall(Uninstall { index, profile }) => {
                 let mut manifests = Manifest::default();
                 let mod_manager = self.core.mod_manager();
-                let mods = mod_manager.mods().collect::<Vec<_>>();
+                let mods = mod_manager.all_mods().collect::<Vec<_>>();
+                let mut removed = vec![];
 
                 if let Some(index_value) = index {
-                    let mod_ = mods
-                        .get(*index_value)
-                        .with_context(|| format!("Mod {} does not exist", index_value))?;
-                    println!("Removing mod {}...", &mod_.meta.name);
+                    let Some(mod_) = mods.get(*index_value) else {
+                        user_bail!("Mod {} does not exist", index_value);
+                    };
+                    say!(self, "Removing mod {}...", &mod_.meta.name);
                     mod_manager.del(mod_, profile.as_ref())?;
                     mod_manager.save()?;
                     manifests.extend(mod_.manifest()?.as_ref());
+                    removed.push(mod_.meta.name.clone());
+                } else if self.cli.json {
+                    user_bail!("Choose the mod to uninstall by its index when using --json");
                 } else {
                     println!("Installed mods:");
                     for (i, mod_) in mods.iter().enumerate() {
                         println!(
                             "{}. {} (v{}) by {}",
-                            i + 1,
-                            &mod_.meta.name,
-                            &mod_.meta.version,
-                            &mod_.meta.author
+                            i, &mod_.meta.name, &mod_.meta.version, &mod_.meta.author
                         );
                     }
                     print!("Enter mod(s) to uninstall, separated by commas: ");
-                    let mut manifests = Manifest::default();
+                    let mut selected = vec![];
                     for id in input!().replace(' ', "").split(',') {
-                        let mod_ = mods
-                            .get(id.trim().parse::<usize>().context("Invalid mod number")? - 1)
-                            .with_context(|| format!("Mod {} does not exist", id))?;
+                        let Some(mod_) = id.trim().parse::<usize>().ok().and_then(|i| mods.get(i))
+                        else {
+                            user_bail!("Mod {} does not exist", id.trim());
+                        };
                         println!("Removing mod {}...", &mod_.meta.name);
-                        mod_manager.del(mod_, profile.as_ref())?;
-                        mod_manager.save()?;
-                        manifests.extend(mod_.manifest()?.as_ref());
+                        removed.push(mod_.meta.name.clone());
+                        selected.push(mod_);
                     }
+                    manifests.extend(&mod_manager.del_many(selected, profile.as_ref())?);
+                    mod_manager.save()?;
                 }
 
-                println!("Applying changes to merge...");
+                say!(self, "Applying changes to merge...");
                 self.core.deploy_manager().apply(Some(manifests))?;
-                if self.cli.deploy {
-                    self.deploy()?;
+                let result = self.finish(json!({ "removed": removed }))?;
+                say!(self, "Done!");
+                Outcome::Done(result)
+            }
+            UkmmCmd::Sort(Sort { profile }) => {
+                let mod_manager = self.core.mod_manager();
+                let moved = mod_manager.sort_load_order(profile.as_ref())?;
+                let names = moved
+                    .iter()
+                    .map(|m| m.meta.name.clone())
+                    .collect::<Vec<_>>();
+                let outcome = if moved.is_empty() {
+                    say!(self, "Load order is already sorted");
+                    Outcome::NothingToDo(json!({ "moved": names }))
+                } else {
+                    for name in &names {
+                        say!(self, "Moved {}", name);
+                    }
+                    mod_manager.save()?;
+                    let mut manifests = Manifest::default();
+                    for mod_ in &moved {
+                        manifests.extend(mod_.manifest()?.as_ref());
+                    }
+                    say!(self, "Applying changes to merge...");
+                    self.core.deploy_manager().apply(Some(manifests))?;
+                    Outcome::Done(self.finish(json!({ "moved": names }))?)
+                };
+                say!(self, "Done!");
+                outcome
+            }
+            UkmmCmd::List(_) => {
+                let mod_manager = self.core.mod_manager();
+                let profile = mod_manager.profile().key().clone();
+                say!(self, "Profile {}:", profile);
+                let mut mods = vec![];
+                for (i, mod_) in mod_manager.all_mods().enumerate() {
+                    say!(
+                        self,
+                        "{}. [{}] {} (v{}) {}",
+                        i,
+                        if mod_.enabled { "x" } else { " " },
+                        &mod_.meta.name,
+                        &mod_.meta.version,
+                        mod_.hash()
+                    );
+                    mods.push(mod_json(i, &mod_));
+                }
+                Outcome::Done(json!({ "profile": profile, "mods": mods }))
+            }
+            UkmmCmd::Enable(Enable { name }) => self.set_enabled(name, true)?,
+            UkmmCmd::Disable(Disable { name }) => self.set_enabled(name, false)?,
+            UkmmCmd::Move(Move { name, position }) => {
+                let mod_ = self.find_mod(name)?;
+                let mod_manager = self.core.mod_manager();
+                let mut order = mod_manager.all_mods().map(|m| m.hash()).collect::<Vec<_>>();
+                if *position >= order.len() {
+                    user_bail!(
+                        "Position {} is past the end of the load order, which has {} mods",
+                        position,
+                        order.len()
+                    );
+                }
+                let result = json!({ "mod": mod_.meta.name, "position": position });
+                if order[*position] == mod_.hash() {
+                    say!(
+                        self,
+                        "{} is already at position {}",
+                        &mod_.meta.name,
+                        position
+                    );
+                    return Ok(Outcome::NothingToDo(result));
+                }
+                order.retain(|hash| *hash != mod_.hash());
+                order.insert(*position, mod_.hash());
+                mod_manager.set_order(order);
+                mod_manager.save()?;
+                drop(mod_manager);
+                if mod_.enabled {
+                    self.add_dirty(mod_.manifest()?.as_ref())?;
+                }
+                say!(self, "Moved {} to position {}", &mod_.meta.name, position);
+                Outcome::Done(result)
+            }
+            UkmmCmd::Options(Options {
+                name,
+                option,
+                clear: false,
+            }) if option.is_empty() => {
+                let mod_ = self.find_mod(name)?;
+                if mod_.meta.options.is_empty() {
+                    say!(self, "{} has no options", &mod_.meta.name);
+                }
+                let mut groups = vec![];
+                for group in &mod_.meta.options {
+                    say!(self, "{}:", group.name());
+                    let mut options = vec![];
+                    for opt in group.options() {
+                        let enabled = mod_.enabled_options.iter().any(|o| o.path == opt.path);
+                        say!(
+                            self,
+                            "  [{}] {} ({})",
+                            if enabled { "x" } else { " " },
+                            &opt.name,
+                            opt.path.display()
+                        );
+                        options.push(json!({
+                            "name": opt.name,
+                            "path": opt.path,
+                            "enabled": enabled,
+                        }));
+                    }
+                    groups.push(json!({
+                        "name": group.name(),
+                        "exclusive": matches!(group, OptionGroup::Exclusive(_)),
+                        "required": group.required(),
+                        "options": options,
+                    }));
+                }
+                Outcome::Done(json!({ "mod": mod_.meta.name, "groups": groups }))
+            }
+            UkmmCmd::Options(Options { name, option, .. }) => self.set_options(name, option)?,
+            UkmmCmd::Apply(Apply { dry_run: true }) => {
+                self.plan(self.core.deploy_manager().plan_apply(Some(self.dirty()?))?)?
+            }
+            UkmmCmd::Apply(_) => {
+                let dirty = self.dirty()?;
+                let applied = !dirty.is_empty();
+                if applied {
+                    say!(self, "Applying changes to merge...");
+                    self.core.deploy_manager().apply(Some(dirty))?;
+                    fs::remove_file(self.dirty_path())?;
+                } else {
+                    say!(self, "No changes to apply");
+                }
+                let result = self.finish(json!({ "applied": applied }))?;
+                say!(self, "Done!");
+                if applied || result["deployed"] == true {
+                    Outcome::Done(result)
+                } else {
+                    Outcome::NothingToDo(result)
+                }
+            }
+            UkmmCmd::Profiles(_) => {
+                let current = self.core.mod_manager().profile().key().clone();
+                let mut profiles = self.core.settings().profiles().collect::<Vec<_>>();
+                profiles.sort();
+                for profile in &profiles {
+                    let marker = if profile == &current { "*" } else { " " };
+                    say!(self, "{marker} {profile}");
+                }
+                Outcome::Done(json!({ "current": current, "profiles": profiles }))
+            }
+            UkmmCmd::Profile(Profile { subcommand }) => {
+                match subcommand {
+                    ProfileCmd::Create(Create { name }) => {
+                        if self.profile_exists(name) {
+                            user_bail!("Profile {name} already exists");
+                        }
+                        self.core.mod_manager().create_profile_if(name)?;
+                        say!(self, "Created profile {name}");
+                        Outcome::Done(json!({ "profile": name }))
+                    }
+                    ProfileCmd::Duplicate(Duplicate { name, new_name }) => {
+                        let new_name = new_name
+                            .clone()
+                            .unwrap_or_else(|| format!("{name}_copy").into());
+                        if !self.profile_exists(name) {
+                            user_bail!("Profile {name} does not exist");
+                        } else if self.profile_exists(&new_name) {
+                            user_bail!("Profile {new_name} already exists");
+                        }
+                        let profiles_dir = self.core.settings().profiles_dir();
+                        uk_manager::util::copy_dir(
+                            profiles_dir.join(name.as_str()),
+                            profiles_dir.join(new_name.as_str()),
+                        )?;
+                        self.core.mod_manager().add_profile(new_name.clone());
+                        say!(self, "Copied profile {name} to {new_name}");
+                        Outcome::Done(json!({ "profile": new_name, "source": name }))
+                    }
+                    ProfileCmd::Delete(Delete { name }) => {
+                        if !self.profile_exists(name) {
+                            user_bail!("Profile {name} does not exist");
+                        } else if self.core.mod_manager().profile().key() == name {
+                            user_bail!("Cannot delete the current profile");
+                        }
+                        self.core.mod_manager().delete_profile(name)?;
+                        say!(self, "Deleted profile {name}");
+                        Outcome::Done(json!({ "profile": name }))
+                    }
+                    ProfileCmd::Switch(Switch { name }) => self.switch_profile(name)?,
+                }
+            }
+            UkmmCmd::Convert(Convert { path, output })
+                if path.is_dir()
+                    && !path.join("rules.txt").exists()
+                    && !path.join("info.json").exists() =>
+            {
+                let mut converted = vec![];
+                let mut skipped = vec![];
+                let mut failed = vec![];
+                for (source, result) in uk_manager::mods::convert_mods(&self.core, path, output)? {
+                    match result {
+                        Ok(None) => {
+                            say!(
+                                self,
+                                "Skipped {}, which is already a UKMM mod",
+                                source.display()
+                            );
+                            skipped.push(source);
+                        }
+                        Ok(Some(dest)) => {
+                            say!(self, "Converted {} to {}", source.display(), dest.display());
+                            converted.push(json!({ "source": source, "output": dest }));
+                        }
+                        Err(e) => {
+                            say!(self, "Failed to convert {}: {:#}", source.display(), e);
+                            let mut error = error_json(&e);
+                            error["source"] = json!(source);
+                            failed.push(error);
+                        }
+                    }
+                }
+                say!(
+                    self,
+                    "Converted {} of {} mods",
+                    converted.len(),
+                    converted.len() + failed.len()
+                );
+                let result = json!({
+                    "converted": converted,
+                    "skipped": skipped,
+                    "failed": failed,
+                });
+                if !failed.is_empty() {
+                    Outcome::Failed(result)
+                } else if converted.is_empty() {
+                    Outcome::NothingToDo(result)
+                } else {
+                    Outcome::Done(result)
+                }
+            }
+            UkmmCmd::Convert(Convert { path, output }) => {
+                say!(self, "Converting {}...", path.display());
+                let dest = uk_manager::mods::convert_mod(&self.core, path, output)?;
+                say!(self, "Converted to {}", dest.display());
+                Outcome::Done(json!({ "source": path, "output": dest }))
+            }
+            UkmmCmd::ExportBnp(ExportBnp {
+                name,
+                output,
+                option,
+            }) => {
+                let path = if Path::new(name.as_str()).is_file() {
+                    PathBuf::from(name.as_str())
+                } else {
+                    self.find_mod(name)?.path
+                };
+                let meta = ModReader::open_peek(&path, vec![])
+                    .with_context(|| format!("Failed to open mod at {}", path.display()))?
+                    .meta;
+                let options = option
+                    .iter()
+                    .map(|option| find_option(&meta, option))
+                    .collect::<Result<Vec<_>>>()?;
+                let options = meta
+                    .with_requirements(options)
+                    .map_err(|e| UserError(e.to_string()))?;
+                say!(self, "Exporting {} to BNP...", &meta.name);
+                let reader = ModReader::open(&path, options.clone())?;
+                let dest = uk_manager::bnp::export_bnp(&self.core, reader, output)?;
+                say!(self, "Exported to {}", dest.display());
+                Outcome::Done(json!({
+                    "mod": meta.name,
+                    "output": dest,
+                    "options": option_paths(&options),
+                }))
+            }
+            UkmmCmd::ConvertPlatform(ConvertPlatform { name, output }) => {
+                let path = if Path::new(name.as_str()).is_file() {
+                    PathBuf::from(name.as_str())
+                } else {
+                    self.find_mod(name)?.path
+                };
+                let meta = ModReader::open_peek(&path, vec![])
+                    .with_context(|| format!("Failed to open mod at {}", path.display()))?
+                    .meta;
+                let platform = match meta.platform {
+                    ModPlatform::Specific(Endian::Big) => Platform::Switch,
+                    ModPlatform::Specific(Endian::Little) => Platform::WiiU,
+                    ModPlatform::Universal => {
+                        user_bail!("{} already works on any platform", &meta.name)
+                    }
+                };
+                let dest = if output.is_dir() {
+                    output.join(sanitise(&format!("{} ({platform})", &meta.name)) + ".zip")
+                } else {
+                    output.clone()
+                };
+                say!(self, "Converting {} to {platform}...", &meta.name);
+                let report = uk_mod::convert::convert_platform(&path, &dest, platform.into())?;
+                say!(self, "Converted to {}", dest.display());
+                if !report.is_complete() {
+                    say!(
+                        self,
+                        "{} files could not be converted and must be replaced by hand:",
+                        report.unconvertible.len()
+                    );
+                    for file in &report.unconvertible {
+                        say!(self, "  {}: {}", file.file, file.reason);
+                    }
+                }
+                let result = json!({
+                    "mod": meta.name,
+                    "output": report.output,
+                    "platform": platform,
+                    "converted": report.converted,
+                    "unconvertible": report.unconvertible,
+                });
+                if report.is_complete() {
+                    Outcome::Done(result)
+                } else {
+                    Outcome::Failed(result)
+                }
+            }
+            UkmmCmd::Validate(Validate { name, no_merge }) => {
+                let path = if Path::new(name.as_str()).is_file() {
+                    PathBuf::from(name.as_str())
+                } else {
+                    self.find_mod(name)?.path
+                };
+                let mut validator = ModValidator::new(&path);
+                if !no_merge {
+                    let settings = self.core.settings();
+                    match (settings.dump(), settings.platform_config()) {
+                        (Some(dump), Some(config)) => {
+                            validator = validator.with_dump(
+                                dump,
+                                settings.current_mode.into(),
+                                config.language,
+                            );
+                        }
+                        _ => {
+                            say!(
+                                self,
+                                "No dump for current mode, so the mod will not be merged with it"
+                            )
+                        }
+                    }
+                }
+                say!(self, "Validating {}...", path.display());
+                let report = validator.validate()?;
+                say!(self, "{report}");
+                let result = json!(report);
+                if report.is_valid() {
+                    Outcome::Done(result)
+                } else {
+                    Outcome::Failed(result)
+                }
+            }
+            UkmmCmd::CheckMaps(_) => {
+                say!(self, "Checking map units...");
+                let issues = self.core.deploy_manager().check_maps()?;
+                for issue in &issues {
+                    say!(self, "{issue}");
+                }
+                let result = json!({ "issues": issues });
+                if issues.is_empty() {
+                    say!(self, "No map unit issues found");
+                    Outcome::Done(result)
+                } else {
+                    Outcome::Failed(result)
+                }
+            }
+            UkmmCmd::Dev(Dev { name, folder }) => {
+                let mut mod_ = self.find_mod(name)?;
+                if let Some(folder) = folder {
+                    if !folder.is_dir() {
+                        user_bail!("{} is not a folder", folder.display());
+                    }
+                    let source = dev::link(&self.core, &mod_, folder)?;
+                    say!(self, "Linked {} to {}", &mod_.meta.name, source.display());
+                    mod_.dev_source = Some(source);
+                }
+                let Some(source) = mod_.dev_source.clone() else {
+                    user_bail!(
+                        "{} is not linked to a source folder, pass the folder to link it",
+                        &mod_.meta.name
+                    );
+                };
+                if !mod_.enabled {
+                    say!(
+                        self,
+                        "{} is disabled, so changes will not be merged",
+                        &mod_.meta.name
+                    );
+                }
+                // Catch up with any changes made since the mod was last
+                // packaged before watching for new ones.
+                say!(self, "Repackaging {}...", &mod_.meta.name);
+                let update = dev::update(&self.core, &mod_, None)?;
+                if update.mod_.enabled {
+                    self.core
+                        .deploy_manager()
+                        .apply(Some(update.dirty.clone()))?;
+                }
+                mod_ = update.mod_.clone();
+                self.dev_updated(Ok(update));
+                say!(
+                    self,
+                    "Watching {} for changes, press Ctrl+C to stop",
+                    source.display()
+                );
+                // Stop between updates rather than being killed in the middle
+                // of one, which would leave the mod half packaged.
+                let stop = Arc::new(AtomicBool::new(false));
+                ctrlc::set_handler({
+                    let stop = stop.clone();
+                    move || stop.store(true, Ordering::Relaxed)
+                })
+                .context("Failed to handle Ctrl+C")?;
+                dev::watch(&self.core, &mod_, &stop, |update| self.dev_updated(update))?;
+                say!(self, "Stopped watching {}", source.display());
+                Outcome::Done(json!({ "mod": mod_.meta.name, "source": source }))
+            }
+            UkmmCmd::Deploy(Deploy {
+                target,
+                dry_run: true,
+                ..
+            }) => {
+                self.check_target(target.as_deref())?;
+                let mut plan = self.core.deploy_manager().plan_deploy()?;
+                if let Some(target) = target {
+                    plan.targets.retain(|t| t.name.as_str() == target.as_str());
+                }
+                self.plan(plan)?
+            }
+            UkmmCmd::Deploy(Deploy {
+                target,
+                allow_unmanaged,
+                ..
+            }) => {
+                self.check_target(target.as_deref())?;
+                if *allow_unmanaged {
+                    let names: Vec<_> = self
+                        .core
+                        .settings()
+                        .platform_config()
+                        .map(|c| c.deploy_targets.as_slice())
+                        .unwrap_or_default()
+                        .iter()
+                        .filter(|c| {
+                            target
+                                .as_deref()
+                                .map(|t| c.name.as_str() == t)
+                                .unwrap_or(true)
+                        })
+                        .map(|c| c.name.clone())
+                        .collect();
+                    for name in names {
+                        self.core.deploy_manager().allow_unmanaged(&name)?;
+                    }
+                }
+                let deployed = self.deploy(target.as_deref())?;
+                let result = json!({
+                    "target": target,
+                    "deployed": deployed,
+                    "pending_deploy": self.core.deploy_manager().pending_len(),
+                });
+                if deployed {
+                    Outcome::Done(result)
+                } else {
+                    Outcome::NothingToDo(result)
                 }
-                println!("Done!");
             }
-            UkmmCmd::Deploy(_) => self.deploy()?,
         };
-        Ok(())
+        Ok(outcome)
+    }
+}
+
+/// Find one of a mod's options by its name or its path.
+fn find_option(meta: &Meta, option: &str) -> Result<ModOption> {
+    match meta
+        .all_options()
+        .find(|opt| opt.name.eq_ignore_ascii_case(option) || opt.path == Path::new(option))
+    {
+        Some(opt) => Ok(opt.clone()),
+        None => user_bail!("{} has no option {option}", &meta.name),
+    }
+}
+
+/// Add the options required by the selected options, and check the result
+/// against the rules of the mod's option groups.
+fn check_options(meta: &Meta, options: Vec<ModOption>) -> Result<Vec<ModOption>> {
+    let options = meta
+        .with_requirements(options)
+        .map_err(|e| UserError(e.to_string()))?;
+    meta.check_options(&options)
+        .map_err(|e| UserError(e.to_string()))?;
+    Ok(options)
+}
+
+/// Pick the options to install a mod with from their names or paths. Like
+/// [`uk_manager::mods::Mod::enable_default_options`], groups with no option
+/// selected fall back to their defaults.
+fn select_options(meta: &Meta, selected: &[String]) -> Result<Vec<ModOption>> {
+    let selected = selected
+        .iter()
+        .map(|option| find_option(meta, option))
+        .collect::<Result<Vec<_>>>()?;
+    let mut options = selected.clone();
+    for group in &meta.options {
+        if group.options().iter().any(|opt| selected.contains(opt)) {
+            continue;
+        }
+        let defaults = match group {
+            OptionGroup::Exclusive(group) => group.default.iter().collect::<Vec<_>>(),
+            OptionGroup::Multiple(group) => group.defaults.iter().collect(),
+        };
+        options.extend(
+            group
+                .options()
+                .iter()
+                .filter(|opt| defaults.contains(&&opt.path))
+                .cloned(),
+        );
     }
+    check_options(meta, options)
 }
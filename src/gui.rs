@@ -42,9 +42,10 @@ use serde::{Deserialize, Serialize};
 use uk_content::util::HashMap;
 use uk_manager::{
     core::Manager,
+    deploy::DeployPlan,
     localization::*,
     mods::{LookupMod, Mod},
-    settings::{Platform, Settings},
+    settings::{DeployConfig, Platform, Settings},
 };
 use uk_mod::{pack::sanitise, Manifest, Meta, ModPlatform};
 pub use uk_ui::visuals;
@@ -146,6 +147,7 @@ pub enum Message {
     AddMod(Mod),
     AddToProfile(smartstring::alias::String),
     AddProfile,
+    AllowUnmanaged(Vec<smartstring::alias::String>, Box<Message>),
     Apply,
     ChangeProfile(String),
     ChangeSort(Sort, bool),
@@ -163,6 +165,7 @@ pub enum Message {
     Confirm(Box<Message>, String),
     DeleteProfile(String),
     Deploy,
+    DeployTarget(smartstring::alias::String),
     Deselect(usize),
     DoUpdate,
     DuplicateProfile(String),
@@ -184,6 +187,7 @@ pub enum Message {
     OfferUpdate(VersionResponse),
     OpenMod(PathBuf),
     PackageMod,
+    PreviewDeploy,
     RefreshModsDisplay,
     Remerge,
     ReloadProfiles,
@@ -208,8 +212,10 @@ pub enum Message {
     SetLanguage(LocLang),
     SetTheme(uk_ui::visuals::Theme),
     ShowAbout,
+    ShowDeployPlan(DeployPlan),
     ShowPackagingOptions(FxHashSet<PathBuf>),
     ShowPackagingDependencies,
+    SortLoadOrder,
     StartDrag(usize),
     Toast(String),
     ToggleMods(Option<Vec<Mod>>, bool),
@@ -272,6 +278,7 @@ pub struct App {
     dock_style: uk_ui::egui_dock::Style,
     changelog: Option<String>,
     new_version: Option<VersionResponse>,
+    deploy_plan: Option<DeployPlan>,
 }
 
 impl App {
@@ -354,6 +361,7 @@ impl App {
             update_mod: Default::default(),
             error_queue: Default::default(),
             new_version: None,
+            deploy_plan: None,
             core,
         }
     }
@@ -6,73 +6,22 @@ impl App {
             .core
             .settings()
             .platform_config()
-            .and_then(|c| c.deploy_config.as_ref())
+            .map(|c| c.deploy_targets.as_slice())
+            .filter(|targets| !targets.is_empty())
         {
-            Some(config) => {
+            Some(targets) => {
                 egui::Frame::none().inner_margin(4.0).show(ui, |ui| {
                     ui.spacing_mut().item_spacing.y = 8.0;
                     ui.with_layout(Layout::top_down(Align::Center), |ui| {
                         let pending = self.core.deploy_manager().pending();
                         let loc = LOCALIZATION.read();
-                        ui.horizontal(|ui| {
-                            ui.label(
-                                RichText::new(loc.get("Settings_Platform_Deploy_Method"))
-                                    .family(egui::FontFamily::Name("Bold".into())),
-                            );
-                            // ui.add_space(8.);
-                            ui.with_layout(Layout::right_to_left(Align::Max), |ui| {
-                                ui.label(loc.get(config.method.name()));
-                            })
-                        });
-                        ui.horizontal(|ui| {
-                            ui.label(
-                                RichText::new(loc.get("Settings_Platform_Deploy_Auto"))
-                                    .family(egui::FontFamily::Name("Bold".into())),
-                            );
-                            // ui.add_space(8.);
-                            ui.with_layout(Layout::right_to_left(Align::Max), |ui| {
-                                ui.label(if config.auto {
-                                    RichText::new(loc.get("Generic_Yes"))
-                                        .color(visuals::GREEN)
-                                } else {
-                                    RichText::new(loc.get("Generic_No"))
-                                        .color(visuals::RED)
-                                });
-                            })
-                        });
-                        ui.vertical(|ui| {
-                            ui.label(
-                                RichText::new(loc.get("Settings_Platform_Deploy_Output"))
-                                    .family(egui::FontFamily::Name("Bold".into())),
-                            );
-                            let mut job = LayoutJob::simple_singleline(
-                                config.output.to_string_lossy().into(),
-                                ui.style()
-                                    .text_styles
-                                    .get(&egui::TextStyle::Body)
-                                    .unwrap()
-                                    .clone(),
-                                ui.visuals().text_color(),
-                            );
-                            job.wrap = TextWrapping {
-                                max_rows: 1,
-                                max_width: ui.available_size_before_wrap().x,
-                                ..Default::default()
-                            };
-                            if ui
-                                .link(job)
-                                .on_hover_text(config.output.to_string_lossy())
-                                .clicked()
-                            {
-                                ui.close_menu();
-                                open::that(if config.output.is_dir() {
-                                    &config.output
-                                } else {
-                                    config.output.parent().unwrap()
-                                })
-                                .unwrap_or(());
-                            }
-                        });
+                        let multiple = targets.len() > 1;
+                        for config in targets {
+                            self.render_deploy_target(config, multiple, ui);
+                        }
+                        if let Some(plan) = &self.deploy_plan {
+                            self.render_deploy_plan(plan, ui);
+                        }
                         ui.add_space(4.);
                         ui.with_layout(
                             Layout::from_main_dir_and_cross_align(
@@ -81,7 +30,9 @@ impl App {
                             ),
                             |ui| {
                                 egui::Frame::none().show(ui, |ui| {
-                                    if let Some(ref exe) = config.executable {
+                                    if let Some(exe) =
+                                        targets.iter().find_map(|c| c.executable.as_ref())
+                                    {
                                         ui.add_space(4.);
                                         if ui.button(loc.get("Deploy_OpenEmu")).clicked() {
                                             let cmd = util::default_shell();
@@ -92,7 +43,7 @@ impl App {
                                                 .spawn();
                                         }
                                     }
-                                    if !config.auto || self.core.deploy_manager().pending() {
+                                    if !targets.iter().all(|c| c.auto) || pending {
                                         if ui
                                             .add_enabled(pending, egui::Button::new(
                                                 loc.get("Tab_Deploy")))
@@ -100,7 +51,22 @@ impl App {
                                         {
                                             self.do_update(super::Message::Deploy);
                                         }
-                                        if config.auto {
+                                        if ui
+                                            .add_enabled(
+                                                pending,
+                                                egui::Button::new(loc.get("Deploy_Preview")),
+                                            )
+                                            .clicked()
+                                        {
+                                            self.do_update(super::Message::PreviewDeploy);
+                                        }
+                                        if targets.iter().any(|c| {
+                                            c.auto
+                                                && self
+                                                    .core
+                                                    .deploy_manager()
+                                                    .target_pending(&c.name)
+                                        }) {
                                             ui.label(
                                                 RichText::new(
                                                     loc.get("Deploy_Auto_Failed")
@@ -122,4 +88,150 @@ impl App {
             }
         }
     }
+
+    fn render_deploy_target(&self, config: &DeployConfig, multiple: bool, ui: &mut Ui) {
+        let loc = LOCALIZATION.read();
+        if multiple {
+            ui.horizontal(|ui| {
+                ui.label(
+                    RichText::new(config.name.as_str())
+                        .family(egui::FontFamily::Name("Bold".into()))
+                        .size(16.),
+                );
+                ui.with_layout(Layout::right_to_left(Align::Max), |ui| {
+                    if ui
+                        .add_enabled(
+                            self.core.deploy_manager().target_pending(&config.name),
+                            egui::Button::new(loc.get("Tab_Deploy")).small(),
+                        )
+                        .clicked()
+                    {
+                        self.do_update(super::Message::DeployTarget(config.name.to_string()));
+                    }
+                })
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.label(
+                RichText::new(loc.get("Settings_Platform_Deploy_Method"))
+                    .family(egui::FontFamily::Name("Bold".into())),
+            );
+            // ui.add_space(8.);
+            ui.with_layout(Layout::right_to_left(Align::Max), |ui| {
+                ui.label(loc.get(config.method.name()));
+            })
+        });
+        ui.horizontal(|ui| {
+            ui.label(
+                RichText::new(loc.get("Settings_Platform_Deploy_Auto"))
+                    .family(egui::FontFamily::Name("Bold".into())),
+            );
+            // ui.add_space(8.);
+            ui.with_layout(Layout::right_to_left(Align::Max), |ui| {
+                ui.label(if config.auto {
+                    RichText::new(loc.get("Generic_Yes"))
+                        .color(visuals::GREEN)
+                } else {
+                    RichText::new(loc.get("Generic_No"))
+                        .color(visuals::RED)
+                });
+            })
+        });
+        ui.vertical(|ui| {
+            ui.label(
+                RichText::new(loc.get("Settings_Platform_Deploy_Output"))
+                    .family(egui::FontFamily::Name("Bold".into())),
+            );
+            let mut job = LayoutJob::simple_singleline(
+                config.output.to_string_lossy().into(),
+                ui.style()
+                    .text_styles
+                    .get(&egui::TextStyle::Body)
+                    .unwrap()
+                    .clone(),
+                ui.visuals().text_color(),
+            );
+            job.wrap = TextWrapping {
+                max_rows: 1,
+                max_width: ui.available_size_before_wrap().x,
+                ..Default::default()
+            };
+            if ui
+                .link(job)
+                .on_hover_text(config.output.to_string_lossy())
+                .clicked()
+            {
+                ui.close_menu();
+                open::that(if config.output.is_dir() {
+                    &config.output
+                } else {
+                    config.output.parent().unwrap()
+                })
+                .unwrap_or(());
+            }
+        });
+    }
+
+    fn render_deploy_plan(&self, plan: &DeployPlan, ui: &mut Ui) {
+        let loc = LOCALIZATION.read();
+        if plan.is_empty() {
+            ui.label(loc.get("Deploy_Preview_Empty"));
+            return;
+        }
+        egui::ScrollArea::new([false, true])
+            .id_source("deploy_plan")
+            .auto_shrink([false, true])
+            .max_height(ui.available_height() / 2.)
+            .show(ui, |ui| {
+                for target in plan.targets.iter().filter(|t| !t.is_empty()) {
+                    if plan.targets.len() > 1 {
+                        ui.label(
+                            RichText::new(target.name.as_str())
+                                .family(egui::FontFamily::Name("Bold".into())),
+                        );
+                    }
+                    for (name, root) in [
+                        ("Info_Manifest_BaseFiles", &target.content),
+                        ("Info_Manifest_DLCFiles", &target.aoc),
+                    ] {
+                        if root.is_empty() {
+                            continue;
+                        }
+                        egui::CollapsingHeader::new(format!("{} ({})", loc.get(name), root.len()))
+                            .id_source((target.name.as_str(), name))
+                            .show(ui, |ui| {
+                                for (label, files) in [
+                                    ("Deploy_Preview_Add", &root.add),
+                                    ("Deploy_Preview_Replace", &root.replace),
+                                    ("Deploy_Preview_Delete", &root.delete),
+                                ] {
+                                    if files.is_empty() {
+                                        continue;
+                                    }
+                                    egui::CollapsingHeader::new(format!(
+                                        "{} ({})",
+                                        loc.get(label),
+                                        files.len()
+                                    ))
+                                    .id_source((target.name.as_str(), name, label))
+                                    .show(ui, |ui| {
+                                        for file in files {
+                                            ui.label(file.as_str());
+                                        }
+                                    });
+                                }
+                            });
+                    }
+                }
+            });
+        ui.horizontal(|ui| {
+            ui.label(
+                RichText::new(loc.get("Deploy_Preview_Size"))
+                    .family(egui::FontFamily::Name("Bold".into())),
+            );
+            ui.with_layout(Layout::right_to_left(Align::Max), |ui| {
+                ui.label(plan.size());
+            })
+        });
+    }
 }
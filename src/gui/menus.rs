@@ -43,6 +43,10 @@ impl App {
             ui.close_menu();
             self.do_update(Message::ResetPending);
         }
+        if ui.button(loc.get("Menu_Tools_SortLoadOrder")).clicked() {
+            ui.close_menu();
+            self.do_update(Message::SortLoadOrder);
+        }
         if ui.button(loc.get("Menu_Tools_ConfigFolder")).clicked() {
             ui.close_menu();
             open::that(Settings::config_dir()).unwrap_or(());
@@ -42,6 +42,8 @@ impl MetaInputModal {
             category: ModCategory::Other,
             author: Default::default(),
             masters: Default::default(),
+            load_after: Default::default(),
+            load_before: Default::default(),
             options: Default::default(),
             platform: uk_mod::ModPlatform::Specific(platform.into()),
             url: Default::default(),
@@ -45,6 +45,8 @@ impl ModPackerBuilder {
                 url: Default::default(),
                 options: Default::default(),
                 masters: Default::default(),
+                load_after: Default::default(),
+                load_before: Default::default(),
             },
         }
     }
@@ -103,7 +103,7 @@ pub struct PlatformSettingsUI {
     pub language: Language,
     pub profile: String,
     pub dump: DumpType,
-    pub deploy_config: DeployConfig,
+    pub deploy_targets: Vec<DeployConfig>,
 }
 
 impl Default for PlatformSettingsUI {
@@ -117,7 +117,7 @@ impl Default for PlatformSettingsUI {
                 update_dir:  Default::default(),
                 aoc_dir:     Default::default(),
             },
-            deploy_config: Default::default(),
+            deploy_targets: vec![Default::default()],
         }
     }
 }
@@ -143,15 +143,17 @@ impl TryFrom<PlatformSettingsUI> for PlatformSettings {
                 Arc::new(ResourceReader::from_zarchive(host_path)?)
             }
         };
+        let deploy_targets: Vec<DeployConfig> = settings
+            .deploy_targets
+            .into_iter()
+            .filter(|t| !t.output.as_os_str().is_empty())
+            .collect();
+        uk_manager::settings::validate_deploy_targets(&deploy_targets)?;
         Ok(Self {
             language: settings.language,
             profile: settings.profile.into(),
             dump,
-            deploy_config: if settings.deploy_config.output.as_os_str().is_empty() {
-                None
-            } else {
-                Some(settings.deploy_config)
-            },
+            deploy_targets,
         })
     }
 }
@@ -162,7 +164,11 @@ impl From<&PlatformSettings> for PlatformSettingsUI {
             language: settings.language,
             profile: settings.profile.to_string(),
             dump: settings.dump.as_ref().into(),
-            deploy_config: settings.deploy_config.as_ref().cloned().unwrap_or_default(),
+            deploy_targets: if settings.deploy_targets.is_empty() {
+                vec![Default::default()]
+            } else {
+                settings.deploy_targets.clone()
+            },
         }
     }
 }
@@ -170,7 +176,11 @@ impl From<&PlatformSettings> for PlatformSettingsUI {
 impl PartialEq<PlatformSettings> for PlatformSettingsUI {
     fn eq(&self, other: &PlatformSettings) -> bool {
         self.language == other.language
-            && other.deploy_config.contains(&self.deploy_config)
+            && self
+                .deploy_targets
+                .iter()
+                .filter(|t| !t.output.as_os_str().is_empty())
+                .eq(other.deploy_targets.iter())
             && self.dump.host_path() == other.dump.source().host_path()
     }
 }
@@ -178,14 +188,75 @@ impl PartialEq<PlatformSettings> for PlatformSettingsUI {
 pub static CONFIG: LazyLock<RwLock<FxHashMap<Platform, PlatformSettingsUI>>> =
     LazyLock::new(|| RwLock::new(Default::default()));
 
-fn render_deploy_config(config: &mut DeployConfig, platform: Platform, ui: &mut Ui) -> bool {
+fn render_deploy_targets(targets: &mut Vec<DeployConfig>, platform: Platform, ui: &mut Ui) -> bool {
+    let loc = LOCALIZATION.read();
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        ui.label(loc.get("Settings_Platform_Deploy"));
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+            if ui
+                .icon_button(icons::Icon::Add)
+                .on_hover_text(loc.get("Settings_Platform_Deploy_Add"))
+                .clicked()
+            {
+                targets.push(DeployConfig {
+                    name: format!("Target {}", targets.len() + 1).into(),
+                    ..Default::default()
+                });
+                changed = true;
+            }
+        });
+    });
+    let removable = targets.len() > 1;
+    let mut removed = None;
+    for (i, config) in targets.iter_mut().enumerate() {
+        let mut remove = false;
+        changed |= render_deploy_config(config, platform, removable.then_some(&mut remove), ui);
+        if remove {
+            removed = Some(i);
+        }
+    }
+    if let Some(i) = removed {
+        targets.remove(i);
+        changed = true;
+    }
+    changed
+}
+
+fn render_deploy_config(
+    config: &mut DeployConfig,
+    platform: Platform,
+    remove: Option<&mut bool>,
+    ui: &mut Ui,
+) -> bool {
     let loc = LOCALIZATION.read();
-    ui.label(loc.get("Settings_Platform_Deploy"));
     let mut changed = false;
     ui.group(|ui| {
         ui.allocate_space([ui.available_width(), -8.0].into());
-        let mut name = loc.get("Settings_Platform_Deploy_Method");
-        let mut description = loc.get("Settings_Platform_Deploy_Method_Desc");
+        let mut name = loc.get("Settings_Platform_Deploy_Name");
+        let mut description = loc.get("Settings_Platform_Deploy_Name_Desc");
+        render_setting(
+            &name,
+            &description,
+            ui,
+            |ui| {
+                let mut target_name = config.name.to_string();
+                if ui.text_edit_singleline(&mut target_name).changed() {
+                    config.name = target_name.into();
+                    changed = true;
+                }
+                if let Some(remove) = remove {
+                    ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                        *remove = ui
+                            .icon_button(icons::Icon::Delete)
+                            .on_hover_text(loc.get("Settings_Platform_Deploy_Remove"))
+                            .clicked();
+                    });
+                }
+            },
+        );
+        name = loc.get("Settings_Platform_Deploy_Method");
+        description = loc.get("Settings_Platform_Deploy_Method_Desc");
         render_setting(
             &name,
             &description,
@@ -205,6 +276,13 @@ fn render_deploy_config(config: &mut DeployConfig, platform: Platform, ui: &mut
                         loc.get("Settings_Platform_Deploy_Method_HardLink"),
                     )
                     .changed();
+                changed |= ui
+                    .radio_value(
+                        &mut config.method,
+                        uk_manager::settings::DeployMethod::Reflink,
+                        loc.get("Settings_Platform_Deploy_Method_Reflink"),
+                    )
+                    .changed();
                 changed |= ui
                     .radio_value(
                         &mut config.method,
@@ -212,6 +290,13 @@ fn render_deploy_config(config: &mut DeployConfig, platform: Platform, ui: &mut
                         loc.get("Settings_Platform_Deploy_Method_Symlink"),
                     )
                     .changed();
+                changed |= ui
+                    .radio_value(
+                        &mut config.method,
+                        uk_manager::settings::DeployMethod::Archive,
+                        loc.get("Settings_Platform_Deploy_Method_Archive"),
+                    )
+                    .changed();
             },
         );
         name = loc.get("Settings_Platform_Deploy_Layout");
@@ -248,6 +333,15 @@ fn render_deploy_config(config: &mut DeployConfig, platform: Platform, ui: &mut
                         },
                     )
                     .changed();
+                if platform == Platform::Switch {
+                    changed |= ui
+                        .radio_value(
+                            &mut config.layout,
+                            uk_manager::settings::DeployLayout::SdRoot,
+                            loc.get("Settings_Platform_Deploy_Layout_NX_SdRoot"),
+                        )
+                        .changed();
+                }
             }
         );
         name = loc.get("Settings_Platform_Deploy_Auto");
@@ -260,6 +354,21 @@ fn render_deploy_config(config: &mut DeployConfig, platform: Platform, ui: &mut
                 changed |= ui.checkbox(&mut config.auto, "").changed();
             },
         );
+        if !matches!(
+            config.method,
+            uk_manager::settings::DeployMethod::Symlink | uk_manager::settings::DeployMethod::Archive
+        ) {
+            name = loc.get("Settings_Platform_Deploy_Hash");
+            description = loc.get("Settings_Platform_Deploy_Hash_Desc");
+            render_setting(
+                &name,
+                &description,
+                ui,
+                |ui| {
+                    changed |= ui.checkbox(&mut config.content_hash, "").changed();
+                },
+            );
+        }
         if platform == Platform::WiiU {
             name = loc.get("Settings_Platform_Deploy_Rules");
             description = loc.get("Settings_Platform_Deploy_Rules_Desc");
@@ -458,7 +567,7 @@ fn render_platform_config(
             }
         }
     });
-    changed |= render_deploy_config(&mut config.deploy_config, platform, ui);
+    changed |= render_deploy_targets(&mut config.deploy_targets, platform, ui);
     changed
 }
 
@@ -651,6 +760,14 @@ impl App {
                             ui,
                             |ui| ui.add(Checkbox::new(&mut settings.show_changelog, "")),
                         );
+                        name = loc.get("Settings_RehashMap");
+                        description = loc.get("Settings_RehashMap_Desc");
+                        render_setting(
+                            &name,
+                            &description,
+                            ui,
+                            |ui| ui.add(Checkbox::new(&mut settings.rehash_map_objects, "")),
+                        );
                     });
                 egui::CollapsingHeader::new(loc.get("Settings_Config_WiiU")).show(ui, |ui| {
                     if ui
@@ -688,7 +805,10 @@ impl App {
                     (None, None) | (None, Some(_)) => false,
                     (Some(config), None) => {
                         !config.dump.is_empty()
-                            || !config.deploy_config.output.as_os_str().is_empty()
+                            || config
+                                .deploy_targets
+                                .iter()
+                                .any(|t| !t.output.as_os_str().is_empty())
                     }
                     (Some(tmp_config), Some(config)) => tmp_config.ne(config),
                 }
@@ -701,7 +821,10 @@ impl App {
                     (None, None) | (None, Some(_)) => false,
                     (Some(config), None) => {
                         !config.dump.is_empty()
-                            || !config.deploy_config.output.as_os_str().is_empty()
+                            || config
+                                .deploy_targets
+                                .iter()
+                                .any(|t| !t.output.as_os_str().is_empty())
                     }
                     (Some(tmp_config), Some(config)) => tmp_config.ne(config),
                 }
@@ -143,6 +143,7 @@ pub fn apply_changes(core: &Manager, mods: Vec<Mod>, dirty: Option<Manifest>) ->
     log::info!("Applying pending changes to mod configuration");
     if !mods.is_empty() {
         log::info!("Updating mod states");
+        let mut states = vec![];
         mods.iter()
             .try_for_each(|m| -> Result<()> {
                 let mod_ = mod_manager
@@ -150,17 +151,9 @@ pub fn apply_changes(core: &Manager, mods: Vec<Mod>, dirty: Option<Manifest>) ->
                     .find(|m2| m2.hash() == m.hash())
                     .unwrap();
                 if !mod_.state_eq(m) {
+                    states.push((m.hash(), m.enabled));
                     mod_manager
-                        .set_enabled(m.hash(), m.enabled, None)
-                        .with_context(|| {
-                            format!(
-                                "Failed to {} {}",
-                                if m.enabled { "enable" } else { "disable" },
-                                m.meta.name.as_str()
-                            )
-                        })?;
-                    mod_manager
-                        .set_enabled_options(m.hash(), m.enabled_options.clone())
+                        .set_enabled_options(m.hash(), m.enabled_options.clone(), None)
                         .with_context(|| {
                             format!("Failed to update options on {}", m.meta.name.as_str())
                         })?;
@@ -168,6 +161,9 @@ pub fn apply_changes(core: &Manager, mods: Vec<Mod>, dirty: Option<Manifest>) ->
                 Ok(())
             })
             .context("Failed to update mod state")?;
+        mod_manager
+            .set_enabled_many(&states, None)
+            .context("Failed to enable or disable mods")?;
         log::info!("Updating load order");
         let order = mods.iter().map(|m| m.hash()).collect();
         mod_manager.set_order(order);
@@ -183,12 +179,12 @@ pub fn apply_changes(core: &Manager, mods: Vec<Mod>, dirty: Option<Manifest>) ->
     if core
         .settings()
         .platform_config()
-        .and_then(|c| c.deploy_config.as_ref().map(|c| c.auto))
+        .map(|c| c.deploy_targets.iter().any(|t| t.auto))
         .unwrap_or(false)
     {
         log::info!("Deploying changes");
         deploy_manager
-            .deploy()
+            .deploy_auto()
             .context("Failed to deploy update to merged mod(s)")?;
     }
     log::info!("Done");
@@ -211,37 +207,71 @@ pub fn package_mod(core: &Manager, builder: ModPackerBuilder) -> Result<Message>
     Ok(Message::ResetPacker)
 }
 
+/// If a deploy target about to be deployed to has game files on its SD card
+/// which UKMM did not deploy, ask before deploying over them.
+pub fn confirm_unmanaged(
+    core: &Manager,
+    target: Option<&str>,
+    then: Message,
+) -> Result<Option<Message>> {
+    let names: Vec<smartstring::alias::String> = core
+        .settings()
+        .platform_config()
+        .map(|c| {
+            c.deploy_targets
+                .iter()
+                .filter(|t| target.map(|name| t.name.as_str() == name).unwrap_or(true))
+                .map(|t| t.name.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+    let deploy_manager = core.deploy_manager();
+    let mut targets = vec![];
+    let mut count = 0;
+    for name in names {
+        let unmanaged = deploy_manager.unmanaged_files(&name)?;
+        let len = unmanaged.content_files.len() + unmanaged.aoc_files.len();
+        if len > 0 {
+            count += len;
+            targets.push(name);
+        }
+    }
+    if targets.is_empty() {
+        return Ok(None);
+    }
+    let loc = LOCALIZATION.read();
+    let message = loc.get("Deploy_Unmanaged_Confirm");
+    let vars = std::collections::HashMap::from([
+        ("targets".to_string(), targets.join(", ")),
+        ("count".to_string(), count.to_string()),
+    ]);
+    Ok(Some(Message::Confirm(
+        Message::AllowUnmanaged(targets, then.into()).into(),
+        message.format(&vars).unwrap(),
+    )))
+}
+
 pub fn dev_update_mods(core: &Manager, mods: Vec<Mod>) -> Result<Message> {
     let mut dirty = Manifest::default();
-    for mod_ in mods {
+    for mut mod_ in mods {
         log::info!("Updating {}…", mod_.meta.name.as_str());
-        let loc = LOCALIZATION.read();
-        let message = loc.get("Mod_Update_Folder");
-        let vars = std::collections::HashMap::from(
-            [("mod_name".to_string(), mod_.meta.name.to_string())]
-        );
-        if let Some(folder) = rfd::FileDialog::new()
-            .set_title(message.format(&vars).unwrap())
-            .pick_folder()
-        {
-            dirty.extend(&mod_.manifest().unwrap_or_default());
-            let hash = mod_.hash();
-            uk_mod::pack::ModPacker::new(
-                folder,
-                &mod_.path,
-                None,
-                [core.settings().dump().unwrap()].into_iter().collect(),
-            )
-            .context("Failed to initialize mod packager")?
-            .pack()
-            .context("Failed to package mod")?;
-            let new_mod = ModReader::open_peek(mod_.path, vec![])?;
-            dirty.extend(new_mod.manifest());
-            core.mod_manager_mut()
-                .replace(Mod::from_reader(new_mod), hash)?;
-        } else {
-            return Ok(Message::Noop);
+        if mod_.dev_source.is_none() {
+            let loc = LOCALIZATION.read();
+            let message = loc.get("Mod_Update_Folder");
+            let vars = std::collections::HashMap::from(
+                [("mod_name".to_string(), mod_.meta.name.to_string())]
+            );
+            if let Some(folder) = rfd::FileDialog::new()
+                .set_title(message.format(&vars).unwrap())
+                .pick_folder()
+            {
+                mod_.dev_source = Some(uk_manager::dev::link(core, &mod_, folder)?);
+            } else {
+                return Ok(Message::Noop);
+            }
         }
+        let update = uk_manager::dev::update(core, &mod_, None)?;
+        dirty.extend(&update.dirty);
     }
     Ok(Message::ResetMods(Some(dirty)))
 }
@@ -465,7 +495,10 @@ pub fn import_cemu_settings(core: &Manager, path: &Path) -> Result<Message> {
     };
     if let Some(wiiu_config) = settings.wiiu_config.as_mut() {
         wiiu_config.dump = dump;
-        let deploy_config = wiiu_config.deploy_config.get_or_insert_default();
+        if wiiu_config.deploy_targets.is_empty() {
+            wiiu_config.deploy_targets.push(Default::default());
+        }
+        let deploy_config = &mut wiiu_config.deploy_targets[0];
         deploy_config.auto = true;
         deploy_config.output = gfx_folder.clone();
         deploy_config.executable = path
@@ -478,7 +511,8 @@ pub fn import_cemu_settings(core: &Manager, path: &Path) -> Result<Message> {
             language: uk_content::constants::Language::USen,
             profile: "Default".into(),
             dump,
-            deploy_config: Some(DeployConfig {
+            deploy_targets: vec![DeployConfig {
+                name: "Cemu".into(),
                 auto: true,
                 method: uk_manager::settings::DeployMethod::Symlink,
                 output: gfx_folder.clone(),
@@ -488,7 +522,8 @@ pub fn import_cemu_settings(core: &Manager, path: &Path) -> Result<Message> {
                     .exists_then()
                     .map(|p| p.display().to_string()),
                 layout: uk_manager::settings::DeployLayout::WithName,
-            }),
+                content_hash: false,
+            }],
         })
     };
     settings.save()?;
@@ -536,7 +571,7 @@ pub fn migrate_bcml(core: Arc<Manager>) -> Result<Message> {
             settings.wiiu_config = Some(PlatformSettings {
                 language: bcml_settings.lang,
                 profile: "Default".into(),
-                deploy_config: bcml_settings
+                deploy_targets: bcml_settings
                     .export_dir
                     .map(|export_dir| {
                         DeployConfig {
@@ -553,7 +588,9 @@ pub fn migrate_bcml(core: Arc<Manager>) -> Result<Message> {
                                 ..Default::default()
                             }
                         })
-                    }),
+                    })
+                    .into_iter()
+                    .collect(),
                 dump: Arc::new(ResourceReader::from_unpacked_dirs(
                     Some(game_dir),
                     Some(update_dir),
@@ -577,12 +614,16 @@ pub fn migrate_bcml(core: Arc<Manager>) -> Result<Message> {
             settings.switch_config = Some(PlatformSettings {
                 language: bcml_settings.lang,
                 profile: "Default".into(),
-                deploy_config: bcml_settings.export_dir_nx.map(|export_dir| {
-                    DeployConfig {
-                        output: export_dir,
-                        ..Default::default()
-                    }
-                }),
+                deploy_targets: bcml_settings
+                    .export_dir_nx
+                    .map(|export_dir| {
+                        DeployConfig {
+                            output: export_dir,
+                            ..Default::default()
+                        }
+                    })
+                    .into_iter()
+                    .collect(),
                 dump: Arc::new(ResourceReader::from_unpacked_dirs(
                     Some(game_dir),
                     None::<PathBuf>,
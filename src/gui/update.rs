@@ -14,6 +14,7 @@ impl App {
                 Message::Noop => self.busy.set(false),
                 Message::ResetMods(dirty) => {
                     self.busy.set(false);
+                    self.deploy_plan = None;
                     self.dirty_mut().clear();
                     if let Some(dirty) = dirty {
                         self.dirty_mut().extend(&dirty);
@@ -49,8 +50,18 @@ impl App {
                 Message::CloseAbout => self.show_about = false,
                 Message::CloseProfiles => self.profiles_state.borrow_mut().show = false,
                 Message::Confirm(msg, prompt) => {
+                    self.busy.set(false);
                     self.confirm = Some((*msg, prompt));
                 }
+                Message::AllowUnmanaged(targets, then) => {
+                    match targets
+                        .iter()
+                        .try_for_each(|t| self.core.deploy_manager().allow_unmanaged(t))
+                    {
+                        Ok(()) => self.do_update(*then),
+                        Err(e) => self.do_update(Message::Error(e)),
+                    }
+                }
                 Message::SelectOnly(i) => {
                     let index = i.clamp(0, self.mods.len() - 1);
                     let mod_ = &self.mods[index];
@@ -139,6 +150,29 @@ impl App {
                         Err(e) => self.do_update(Message::Error(e)),
                     };
                 }
+                Message::SortLoadOrder => {
+                    match uk_manager::mods::sort_load_order(&self.mods) {
+                        Ok(sorted) => {
+                            let moved = self
+                                .mods
+                                .iter()
+                                .zip(sorted.iter())
+                                .filter(|(old, new)| old != new)
+                                .map(|(_, new)| new.clone())
+                                .collect::<Vec<_>>();
+                            self.mods = sorted;
+                            match moved.iter().try_for_each(|m| {
+                                self.dirty_mut()
+                                    .extend(m.manifest_with_options(&m.enabled_options)?.as_ref());
+                                Ok(())
+                            }) {
+                                Ok(()) => self.do_update(Message::RefreshModsDisplay),
+                                Err(e) => self.do_update(Message::Error(e)),
+                            };
+                        }
+                        Err(e) => self.do_update(Message::Error(e)),
+                    }
+                }
                 Message::FilePickerUp => {
                     let has_parent = self.picker_state.path.parent().is_some();
                     if has_parent {
@@ -279,14 +313,8 @@ impl App {
                 Message::HandleMod(mod_) => {
                     self.busy.set(false);
                     log::debug!("{:#?}", &mod_);
-                    for (hash, (name, version)) in mod_.meta.masters.iter() {
-                        if !self.mods.iter().any(|m| m.hash() == *hash) {
-                            self.do_update(Message::Error(anyhow_ext::anyhow!(
-                                "Could not find required mod dependency {} (version {})",
-                                name,
-                                version
-                            )));
-                        }
+                    for issue in self.core.mod_manager().dependency_issues(&mod_, None) {
+                        self.do_update(Message::Error(anyhow_ext::anyhow!("{issue}")));
                     }
                     if !matches!(mod_.meta.platform, ModPlatform::Universal)
                         && mod_.meta.platform != ModPlatform::Specific(self.platform().into())
@@ -321,7 +349,7 @@ impl App {
                             let mod_ = mods.add(&tmp_mod_.path, None)?;
                             let hash = mod_.as_map_id();
                             if !tmp_mod_.enabled_options.is_empty() {
-                                mods.set_enabled_options(hash, tmp_mod_.enabled_options)?;
+                                mods.set_enabled_options(hash, tmp_mod_.enabled_options, None)?;
                             }
                             mods.save()?;
                             log::info!("Added mod {} to current profile", mod_.meta.name.as_str());
@@ -334,11 +362,10 @@ impl App {
                     let mods = mods.unwrap_or_else(|| self.selected.clone());
                     self.do_task(move |core| {
                         let manager = core.mod_manager();
-                        mods.iter().try_for_each(|m| -> Result<()> {
-                            manager.del(m.as_map_id(), None)?;
+                        manager.del_many(mods.iter(), None)?;
+                        for m in mods.iter() {
                             log::info!("Removed mod {} from current profile", m.meta.name.as_str());
-                            Ok(())
-                        })?;
+                        }
                         manager.save()?;
                         Ok(Message::RemoveMods(mods))
                     });
@@ -458,14 +485,48 @@ impl App {
                     let dirty = std::mem::take(self.dirty_mut().deref_mut());
                     self.do_task(move |core| tasks::apply_changes(&core, mods, Some(dirty)));
                 }
+                Message::PreviewDeploy => {
+                    self.do_task(|core| {
+                        log::info!("Planning deployment");
+                        Ok(Message::ShowDeployPlan(
+                            core.deploy_manager().plan_deploy()?,
+                        ))
+                    })
+                }
+                Message::ShowDeployPlan(plan) => {
+                    self.busy.set(false);
+                    self.deploy_plan = Some(plan);
+                }
                 Message::Deploy => {
+                    self.deploy_plan = None;
                     self.do_task(move |core| {
+                        if let Some(confirm) =
+                            tasks::confirm_unmanaged(&core, None, Message::Deploy)?
+                        {
+                            return Ok(confirm);
+                        }
                         log::info!("Deploying current mod configuration");
                         core.deploy_manager().deploy()?;
                         Ok(Message::ResetMods(None))
                     })
                 }
+                Message::DeployTarget(target) => {
+                    self.deploy_plan = None;
+                    self.do_task(move |core| {
+                        if let Some(confirm) = tasks::confirm_unmanaged(
+                            &core,
+                            Some(&target),
+                            Message::DeployTarget(target.clone()),
+                        )? {
+                            return Ok(confirm);
+                        }
+                        log::info!("Deploying current mod configuration to {target}");
+                        core.deploy_manager().deploy_target(&target)?;
+                        Ok(Message::ResetMods(None))
+                    })
+                }
                 Message::ResetPending => {
+                    self.deploy_plan = None;
                     self.do_task(|core| {
                         log::info!("Resetting pending deployment data");
                         core.deploy_manager().reset_pending()?;
@@ -482,22 +543,33 @@ impl App {
                 }
                 Message::SaveSettings => {
                     let mut needs_reset = false;
-                    self.core.settings().platform_config().map(|old_plat| {
-                        old_plat.deploy_config.as_ref().map(|old_dep| {
-                            if let Some(new_plat) = &self.temp_settings.platform_config() {
-                                new_plat.deploy_config.as_ref().map(|new_dep| {
-                                    if old_dep.layout != new_dep.layout ||
-                                        old_dep.method != new_dep.method ||
-                                        old_dep.output != new_dep.output {
-                                        if let Ok(_) = self.core.settings()
-                                            .wipe_output(self.core.settings().current_mode.into()) {
+                    {
+                        let settings = self.core.settings();
+                        let endian = settings.current_mode.into();
+                        if let (Some(old_plat), Some(new_plat)) = (
+                            settings.platform_config(),
+                            self.temp_settings.platform_config(),
+                        ) {
+                            for old_dep in &old_plat.deploy_targets {
+                                match new_plat.deploy_target(&old_dep.name) {
+                                    Some(new_dep) => {
+                                        if (old_dep.layout != new_dep.layout
+                                            || old_dep.method != new_dep.method
+                                            || old_dep.output != new_dep.output)
+                                            && old_dep.wipe_output(endian).is_ok()
+                                        {
                                             needs_reset = true;
                                         }
                                     }
-                                });
+                                    None => needs_reset = true,
+                                }
                             }
-                        });
-                    });
+                            needs_reset |= new_plat
+                                .deploy_targets
+                                .iter()
+                                .any(|t| old_plat.deploy_target(&t.name).is_none());
+                        }
+                    }
                     let save_res = self.temp_settings.save().and_then(|_| {
                         self.core.reload()?;
                         Ok(())
@@ -549,10 +621,13 @@ impl App {
                     match self
                         .core
                         .mod_manager()
-                        .set_enabled_options(mod_.hash(), opts)
+                        .set_enabled_options(mod_.hash(), opts, None)
                     {
                         Ok(manifest) => {
                             self.dirty_mut().extend(&manifest);
+                            // Pick up any required options which were enabled
+                            // automatically.
+                            let mod_ = self.core.mod_manager().get_mod(mod_.hash()).unwrap_or(mod_);
                             if let Some(old_mod) =
                                 self.mods.iter_mut().find(|m| m.hash() == mod_.hash())
                             {
@@ -35,7 +35,7 @@ fn main() -> Result<()> {
 
     match Ukmm::from_env() {
         Ok(command) => {
-            cli::Runner::new(command).run()?;
+            std::process::exit(cli::run(command));
         }
         Err(e) => {
             if !e.is_help() {